@@ -0,0 +1,109 @@
+use crate::error::{AuthError, Error};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::HeaderValue;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// The credentials that have been configured via the `ClientBuilder`.
+pub(crate) enum Credentials {
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    Bearer(String),
+    BearerFile(PathBuf),
+}
+
+// The value of the `Authorization` header that a client adds to every request.
+#[derive(Clone)]
+pub(crate) enum Authorization {
+    // Basic auth or a static bearer token, encoded once when the client is built.
+    Static(HeaderValue),
+    // A bearer token that is read from a file and read again once the file was modified,
+    // e.g. a service account token that is rotated periodically.
+    File(Arc<TokenFile>),
+}
+
+impl Authorization {
+    // Encode the credentials. A token file is read once to make sure that it exists.
+    pub(crate) fn new(credentials: Credentials) -> Result<Self, Error> {
+        match credentials {
+            Credentials::Basic { username, password } => {
+                let encoded =
+                    STANDARD.encode(format!("{}:{}", username, password.unwrap_or_default()));
+                header_value(&format!("Basic {}", encoded)).map(Authorization::Static)
+            }
+            Credentials::Bearer(token) => {
+                header_value(&format!("Bearer {}", token)).map(Authorization::Static)
+            }
+            Credentials::BearerFile(path) => {
+                let file = TokenFile {
+                    path,
+                    cache: Mutex::new(None),
+                };
+                file.header_value()?;
+                Ok(Authorization::File(Arc::new(file)))
+            }
+        }
+    }
+
+    pub(crate) fn header_value(&self) -> Result<HeaderValue, Error> {
+        match self {
+            Authorization::Static(value) => Ok(value.clone()),
+            Authorization::File(file) => file.header_value(),
+        }
+    }
+}
+
+pub(crate) struct TokenFile {
+    path: PathBuf,
+    cache: Mutex<Option<(SystemTime, HeaderValue)>>,
+}
+
+impl TokenFile {
+    // Return the cached token unless the modification time of the file has changed.
+    fn header_value(&self) -> Result<HeaderValue, Error> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .map_err(|source| self.error("failed to read bearer token file", Some(source)))?;
+
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some((cached, value)) = cache.as_ref() {
+            if *cached == modified {
+                return Ok(value.clone());
+            }
+        }
+
+        let token = std::fs::read_to_string(&self.path)
+            .map_err(|source| self.error("failed to read bearer token file", Some(source)))?;
+
+        let value = header_value(&format!("Bearer {}", token.trim()))
+            .map_err(|_| self.error("bearer token file contains invalid characters", None))?;
+
+        *cache = Some((modified, value.clone()));
+        Ok(value)
+    }
+
+    fn error(&self, message: &'static str, source: Option<std::io::Error>) -> Error {
+        Error::Auth(AuthError {
+            message,
+            path: Some(self.path.clone()),
+            source,
+        })
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, Error> {
+    let mut value = HeaderValue::from_str(value).map_err(|_| {
+        Error::Auth(AuthError {
+            message: "credentials contain invalid characters",
+            path: None,
+            source: None,
+        })
+    })?;
+    value.set_sensitive(true);
+    Ok(value)
+}
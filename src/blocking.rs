@@ -0,0 +1,1359 @@
+//! A blocking client for the Prometheus HTTP API.
+//!
+//! The blocking [`Client`] mirrors the API of the asynchronous [`crate::Client`]: it creates
+//! the same builders, which provide the same methods to set query parameters, and returns
+//! the same response and error types. Only the methods that finally send the request block
+//! the current thread instead of returning a future.
+//!
+//! Internally the blocking client uses a [`reqwest::blocking::Client`], which manages its own
+//! runtime. Thus it must not be used from within an asynchronous context.
+//!
+//! This module requires the `blocking` feature.
+//!
+//! ```rust
+//! use prometheus_http_query::blocking::Client;
+//!
+//! fn main() -> Result<(), anyhow::Error> {
+//!     let client = Client::default();
+//!
+//!     let response = client.query("up").get()?;
+//!
+//!     assert!(response.data().as_vector().is_some());
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::auth::Authorization;
+use crate::client::Request;
+use crate::error::{ClientError, Error};
+use crate::ha::{ReplicaSet, ReplicaStatus};
+use crate::response::*;
+use crate::retry::{Retries, RetryPolicy};
+use crate::selector::Selector;
+use crate::stream::{Layout, SeriesStream};
+use crate::util::{
+    self, build_final_url, tenant_header_value, Resolution, Step, TargetState, Timestamp,
+    ToBaseUrl, DEFAULT_TENANT_HEADER,
+};
+use futures_util::{FutureExt, StreamExt};
+use reqwest::blocking::Response;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::Method as HttpMethod;
+use reqwest::StatusCode;
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use url::Url;
+
+/// A builder for instant queries that are sent by the blocking [`Client`],
+/// see [`crate::InstantQueryBuilder`].
+pub type InstantQueryBuilder = crate::InstantQueryBuilder<Client>;
+
+/// A builder for range queries that are sent by the blocking [`Client`],
+/// see [`crate::RangeQueryBuilder`].
+pub type RangeQueryBuilder = crate::RangeQueryBuilder<Client>;
+
+/// A builder for rules queries that are sent by the blocking [`Client`],
+/// see [`crate::RulesQueryBuilder`].
+pub type RulesQueryBuilder = crate::RulesQueryBuilder<Client>;
+
+/// A builder for target metadata queries that are sent by the blocking [`Client`],
+/// see [`crate::TargetMetadataQueryBuilder`].
+pub type TargetMetadataQueryBuilder = crate::TargetMetadataQueryBuilder<Client>;
+
+/// A builder for metric metadata queries that are sent by the blocking [`Client`],
+/// see [`crate::MetricMetadataQueryBuilder`].
+pub type MetricMetadataQueryBuilder = crate::MetricMetadataQueryBuilder<Client>;
+
+/// A builder for series metadata queries that are sent by the blocking [`Client`],
+/// see [`crate::SeriesQueryBuilder`].
+pub type SeriesQueryBuilder = crate::SeriesQueryBuilder<Client>;
+
+/// A builder for label name queries that are sent by the blocking [`Client`],
+/// see [`crate::LabelNamesQueryBuilder`].
+pub type LabelNamesQueryBuilder = crate::LabelNamesQueryBuilder<Client>;
+
+/// A builder for label value queries that are sent by the blocking [`Client`],
+/// see [`crate::LabelValuesQueryBuilder`].
+pub type LabelValuesQueryBuilder = crate::LabelValuesQueryBuilder<Client>;
+
+/// A builder for exemplar queries that are sent by the blocking [`Client`],
+/// see [`crate::ExemplarsQueryBuilder`].
+pub type ExemplarsQueryBuilder = crate::ExemplarsQueryBuilder<Client>;
+
+/// A builder to format PromQL queries using the blocking [`Client`],
+/// see [`crate::FormatQueryBuilder`].
+pub type FormatQueryBuilder = crate::FormatQueryBuilder<Client>;
+
+/// A builder to parse PromQL queries using the blocking [`Client`],
+/// see [`crate::ParseQueryBuilder`].
+pub type ParseQueryBuilder = crate::ParseQueryBuilder<Client>;
+
+/// A builder to create TSDB snapshots using the blocking [`Client`],
+/// see [`crate::SnapshotBuilder`].
+pub type SnapshotBuilder = crate::SnapshotBuilder<Client>;
+
+/// A builder to delete time series using the blocking [`Client`],
+/// see [`crate::DeleteSeriesBuilder`].
+pub type DeleteSeriesBuilder = crate::DeleteSeriesBuilder<Client>;
+
+/// A builder to send samples to a remote write receiver using the blocking [`Client`],
+/// see [`crate::RemoteWriteBuilder`].
+#[cfg(feature = "remote-write")]
+pub type RemoteWriteBuilder = crate::RemoteWriteBuilder<Client>;
+
+/// A builder to read samples from a remote read endpoint using the blocking [`Client`],
+/// see [`crate::RemoteReadBuilder`].
+#[cfg(feature = "remote-read")]
+pub type RemoteReadBuilder = crate::RemoteReadBuilder<Client>;
+
+/// A blocking client used to execute queries. It uses a [`reqwest::blocking::Client`]
+/// internally that manages connections for us.
+#[derive(Clone)]
+pub struct Client {
+    pub(crate) client: reqwest::blocking::Client,
+    pub(crate) base_url: Url,
+    pub(crate) authorization: Option<Authorization>,
+    pub(crate) tenant_header: HeaderName,
+    pub(crate) tenant: Option<HeaderValue>,
+    pub(crate) replicas: Option<Arc<ReplicaSet>>,
+    pub(crate) retry: Option<Arc<RetryPolicy>>,
+}
+
+impl Default for Client {
+    /// Create a standard Client that sends requests to "http://127.0.0.1:9090/".
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// let client = Client::default();
+    /// ```
+    fn default() -> Self {
+        Client {
+            client: reqwest::blocking::Client::new(),
+            base_url: Url::parse("http://127.0.0.1:9090/").unwrap(),
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
+        }
+    }
+}
+
+impl std::str::FromStr for Client {
+    type Err = crate::error::Error;
+
+    /// Create a Client from a custom base URL. Note that the API-specific
+    /// path segments (like `/api/v1/query`) are added automatically.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    /// use std::str::FromStr;
+    ///
+    /// let client = Client::from_str("http://proxy.example.com/prometheus");
+    /// assert!(client.is_ok());
+    /// ```
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let client = Client {
+            base_url: url.to_base_url()?,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
+            client: reqwest::blocking::Client::new(),
+        };
+        Ok(client)
+    }
+}
+
+impl std::convert::TryFrom<&str> for Client {
+    type Error = crate::error::Error;
+
+    /// Create a [`Client`] from a custom base URL. Note that the API-specific
+    /// path segments (like `/api/v1/query`) are added automatically.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    /// use std::convert::TryFrom;
+    ///
+    /// let client = Client::try_from("http://proxy.example.com/prometheus");
+    /// assert!(client.is_ok());
+    /// ```
+    fn try_from(url: &str) -> Result<Self, Self::Error> {
+        Client::from_str(url)
+    }
+}
+
+impl std::convert::TryFrom<String> for Client {
+    type Error = crate::error::Error;
+
+    /// Create a [`Client`] from a custom base URL. Note that the API-specific
+    /// path segments (like `/api/v1/query`) are added automatically.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    /// use std::convert::TryFrom;
+    ///
+    /// let url = String::from("http://proxy.example.com/prometheus");
+    /// let client = Client::try_from(url);
+    /// assert!(client.is_ok());
+    /// ```
+    fn try_from(url: String) -> Result<Self, Self::Error> {
+        Client::from_str(&url)
+    }
+}
+
+impl Client {
+    /// Return a reference to the wrapped [`reqwest::blocking::Client`], i.e. to
+    /// use it for other requests unrelated to the Prometheus API.
+    pub fn inner(&self) -> &reqwest::blocking::Client {
+        &self.client
+    }
+
+    /// Return a reference to the base URL that is used in requests to
+    /// the Prometheus API.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// let client = Client::default();
+    ///
+    /// assert_eq!(client.base_url().as_str(), "http://127.0.0.1:9090/");
+    /// ```
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Return the tenant ID that is sent along with every request, if any.
+    /// Multiple tenants of a federated query are separated by `|`.
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_ref().and_then(|t| t.to_str().ok())
+    }
+
+    /// Return a copy of this client that sends requests on behalf of another
+    /// tenant. The copy shares the connection pool and all other settings with
+    /// this client, so this is cheap enough to be used for individual requests.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// fn main() -> Result<(), prometheus_http_query::Error> {
+    ///     let client = prometheus_http_query::Client::builder().tenant("team-a").build_blocking()?;
+    ///     let other = client.with_tenant("team-b")?;
+    ///
+    ///     assert_eq!(client.tenant(), Some("team-a"));
+    ///     assert_eq!(other.tenant(), Some("team-b"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_tenant(&self, tenant: impl std::fmt::Display) -> Result<Self, Error> {
+        self.with_tenants([tenant])
+    }
+
+    /// Return a copy of this client that queries data across several tenants.
+    /// The tenant IDs are joined by `|`, which e.g. Mimir and Cortex interpret as a
+    /// federated query (given that tenant federation is enabled). An empty set of
+    /// tenants removes the tenant header altogether.
+    pub fn with_tenants<T>(&self, tenants: T) -> Result<Self, Error>
+    where
+        T: IntoIterator,
+        T::Item: std::fmt::Display,
+    {
+        Ok(Self {
+            tenant: tenant_header_value(tenants)?,
+            ..self.clone()
+        })
+    }
+
+    /// Create a Client from a custom [`reqwest::blocking::Client`] and URL.
+    /// This way you can account for all extra parameters (e.g. x509 authentication)
+    /// that may be needed to connect to Prometheus or an intermediate proxy,
+    /// by building it into the [`reqwest::blocking::Client`].
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// fn main() -> Result<(), anyhow::Error> {
+    ///     let client = {
+    ///         let c = reqwest::blocking::Client::builder()
+    ///             .no_proxy()
+    ///             .build()?;
+    ///         Client::from(c, "https://prometheus.example.com")
+    ///     };
+    ///
+    ///     assert!(client.is_ok());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from(client: reqwest::blocking::Client, url: &str) -> Result<Self, Error> {
+        let base_url = url.to_base_url()?;
+        Ok(Client {
+            base_url,
+            client,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
+        })
+    }
+
+    // Build and send the final HTTP request and block until the response headers
+    // have been received.
+    fn send<S: Serialize>(
+        &self,
+        path: &str,
+        params: &S,
+        method: HttpMethod,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Result<Response, Error> {
+        let Some(policy) = &self.retry else {
+            return self.send_once(path, params, method, headers);
+        };
+
+        let mut retries = Retries::new(policy);
+        loop {
+            let result = self.send_once(path, params, method.clone(), headers.clone());
+            let attempt = policy.inspect_blocking(result);
+            match retries.next_delay(&attempt) {
+                Some(delay) => std::thread::sleep(delay),
+                None => return attempt.result,
+            }
+        }
+    }
+
+    // Send a request once, to the replicas according to the configured strategy if any.
+    fn send_once<S: Serialize>(
+        &self,
+        path: &str,
+        params: &S,
+        method: HttpMethod,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Result<Response, Error> {
+        match &self.replicas {
+            Some(replicas) => replicas.send_blocking(|base_url| {
+                self.send_to(
+                    base_url.clone(),
+                    path,
+                    params,
+                    method.clone(),
+                    headers.clone(),
+                )
+            }),
+            None => self.send_to(self.base_url.clone(), path, params, method, headers),
+        }
+    }
+
+    // Send a request to the Prometheus server (or replica) at the given base URL.
+    fn send_to<S: Serialize>(
+        &self,
+        base_url: Url,
+        path: &str,
+        params: &S,
+        method: HttpMethod,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Result<Response, Error> {
+        let url = build_final_url(base_url, path);
+
+        let mut request = match method {
+            HttpMethod::GET => self.request(method, url)?.query(params),
+            HttpMethod::POST => self.request(method, url)?.form(params),
+            _ => unreachable!(),
+        };
+
+        if let Some(headers) = headers {
+            request = request.headers(headers);
+        }
+
+        request.send().map_err(|source| {
+            Error::Client(ClientError {
+                message: "failed to send request to server",
+                source: Some(source),
+            })
+        })
+    }
+
+    // Create a request to the given URL that carries the configured credentials.
+    pub(crate) fn request(
+        &self,
+        method: HttpMethod,
+        url: Url,
+    ) -> Result<reqwest::blocking::RequestBuilder, Error> {
+        let mut request = self.client.request(method, url);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(AUTHORIZATION, authorization.header_value()?);
+        }
+        if let Some(tenant) = &self.tenant {
+            request = request.header(self.tenant_header.clone(), tenant.clone());
+        }
+        Ok(request)
+    }
+
+    // Send a request that has been assembled by one of the builders.
+    fn execute(&self, request: Request, method: HttpMethod) -> Result<Response, Error> {
+        self.send(&request.path, &request.params, method, request.headers)
+    }
+
+    // Send a request that has been assembled by one of the builders without retrying it,
+    // as the request is not idempotent (e.g. creating a snapshot).
+    fn execute_once(&self, request: Request, method: HttpMethod) -> Result<Response, Error> {
+        self.send_once(&request.path, &request.params, method, request.headers)
+    }
+
+    /// Create an [`InstantQueryBuilder`] from a PromQL query, see [`crate::Client::query`].
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let response = client.query("prometheus_http_request_total").get()?;
+    ///
+    ///     assert!(response.data().as_vector().is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query(&self, query: impl std::fmt::Display) -> InstantQueryBuilder {
+        InstantQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`RangeQueryBuilder`] from a PromQL query, see [`crate::Client::query_range`].
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let q = "prometheus_http_requests_total";
+    ///
+    ///     let response = client.query_range(q, 1648373100, 1648373300, 10.0).get()?;
+    ///
+    ///     assert!(response.data().as_matrix().is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_range(
+        &self,
+        query: impl std::fmt::Display,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+        step: impl Into<Step>,
+    ) -> RangeQueryBuilder {
+        RangeQueryBuilder::new(self.clone(), query, start.into(), end.into(), step.into())
+    }
+
+    /// Create a [`RangeQueryBuilder`] and select the step automatically from the time range
+    /// and the desired [`Resolution`], see [`crate::Client::query_range_auto`].
+    pub fn query_range_auto(
+        &self,
+        query: impl std::fmt::Display,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+        resolution: impl Into<Resolution>,
+    ) -> RangeQueryBuilder {
+        let end = end.into();
+        let (start, step) = resolution.into().resolve(start.into(), end);
+        self.query_range(query, start, end, step)
+    }
+
+    /// Create a [`SeriesQueryBuilder`] to apply filters to a series metadata
+    /// query, see [`crate::Client::series`].
+    pub fn series<T>(&self, selectors: T) -> Result<SeriesQueryBuilder, Error>
+    where
+        T: IntoIterator,
+        T::Item: Borrow<Selector>,
+    {
+        SeriesQueryBuilder::new(self.clone(), selectors)
+    }
+
+    /// Create a [`LabelNamesQueryBuilder`] to apply filters to a query for the label
+    /// names endpoint, see [`crate::Client::label_names`].
+    pub fn label_names(&self) -> LabelNamesQueryBuilder {
+        LabelNamesQueryBuilder::new(self.clone())
+    }
+
+    /// Create a [`LabelValuesQueryBuilder`] to apply filters to a query for the label
+    /// values endpoint, see [`crate::Client::label_values`].
+    pub fn label_values(&self, label: impl std::fmt::Display) -> LabelValuesQueryBuilder {
+        LabelValuesQueryBuilder::new(self.clone(), label)
+    }
+
+    /// Create an [`ExemplarsQueryBuilder`] to retrieve the exemplars of the time series
+    /// that are selected by a PromQL query, see [`crate::Client::exemplars`].
+    pub fn exemplars(&self, query: impl std::fmt::Display) -> ExemplarsQueryBuilder {
+        ExemplarsQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`FormatQueryBuilder`] to format a PromQL query server-side,
+    /// see [`crate::Client::format_query`].
+    pub fn format_query(&self, query: impl std::fmt::Display) -> FormatQueryBuilder {
+        FormatQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`ParseQueryBuilder`] to parse a PromQL query server-side,
+    /// see [`crate::Client::parse_query`].
+    pub fn parse_query(&self, query: impl std::fmt::Display) -> ParseQueryBuilder {
+        ParseQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Query the current state of target discovery, see [`crate::Client::targets`].
+    pub fn targets(&self, state: Option<TargetState>) -> Result<Targets, Error> {
+        let mut params = vec![];
+
+        if let Some(s) = &state {
+            params.push(("state", s.to_string()))
+        }
+
+        let response = self.send("api/v1/targets", &params, HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Create a [`RulesQueryBuilder`] to retrieve recording and alerting rules,
+    /// see [`crate::Client::rules`].
+    pub fn rules(&self) -> RulesQueryBuilder {
+        RulesQueryBuilder::new(self.clone())
+    }
+
+    /// Retrieve a list of active alerts, see [`crate::Client::alerts`].
+    pub fn alerts(&self) -> Result<Vec<Alert>, Error> {
+        let response = self.send("api/v1/alerts", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response).map(|r: Alerts| r.alerts)
+    }
+
+    /// Retrieve a list of flags that Prometheus was configured with,
+    /// see [`crate::Client::flags`].
+    pub fn flags(&self) -> Result<HashMap<String, String>, Error> {
+        let response = self.send("api/v1/status/flags", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Retrieve Prometheus server build information,
+    /// see [`crate::Client::build_information`].
+    pub fn build_information(&self) -> Result<BuildInformation, Error> {
+        let response = self.send("api/v1/status/buildinfo", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Retrieve Prometheus server runtime information,
+    /// see [`crate::Client::runtime_information`].
+    pub fn runtime_information(&self) -> Result<RuntimeInformation, Error> {
+        let response = self.send("api/v1/status/runtimeinfo", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Retrieve Prometheus TSDB statistics, see [`crate::Client::tsdb_statistics`].
+    pub fn tsdb_statistics(&self) -> Result<TsdbStatistics, Error> {
+        let response = self.send("api/v1/status/tsdb", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Retrieve WAL replay statistics, see [`crate::Client::wal_replay_statistics`].
+    pub fn wal_replay_statistics(&self) -> Result<WalReplayStatistics, Error> {
+        let response = self.send("api/v1/status/walreplay", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Query the current state of alertmanager discovery,
+    /// see [`crate::Client::alertmanagers`].
+    pub fn alertmanagers(&self) -> Result<Alertmanagers, Error> {
+        let response = self.send("api/v1/alertmanagers", &(), HttpMethod::GET, None)?;
+        Client::deserialize(response)
+    }
+
+    /// Create a [`TargetMetadataQueryBuilder`] to retrieve metric metadata from one or
+    /// more targets, see [`crate::Client::target_metadata`].
+    pub fn target_metadata(&self) -> TargetMetadataQueryBuilder {
+        TargetMetadataQueryBuilder::new(self.clone())
+    }
+
+    /// Create a [`MetricMetadataQueryBuilder`] to retrieve metadata about metrics,
+    /// see [`crate::Client::metric_metadata`].
+    pub fn metric_metadata(&self) -> MetricMetadataQueryBuilder {
+        MetricMetadataQueryBuilder::new(self.clone())
+    }
+
+    /// Create a [`SnapshotBuilder`] to create a snapshot of the TSDB using the admin API,
+    /// see [`crate::Client::snapshot`].
+    pub fn snapshot(&self) -> SnapshotBuilder {
+        SnapshotBuilder::new(self.clone())
+    }
+
+    /// Create a [`DeleteSeriesBuilder`] to delete time series from the TSDB using the
+    /// admin API, see [`crate::Client::delete_series`].
+    pub fn delete_series<T>(&self, selectors: T) -> Result<DeleteSeriesBuilder, Error>
+    where
+        T: IntoIterator,
+        T::Item: Borrow<Selector>,
+    {
+        DeleteSeriesBuilder::new(self.clone(), selectors)
+    }
+
+    /// Remove deleted data from disk and clean up existing tombstones using the admin API,
+    /// see [`crate::Client::clean_tombstones`].
+    pub fn clean_tombstones(&self) -> Result<(), Error> {
+        let response = self.send_once(
+            "api/v1/admin/tsdb/clean_tombstones",
+            &(),
+            HttpMethod::POST,
+            None,
+        )?;
+        Client::deserialize_empty(response).map_err(crate::Client::map_admin_error)
+    }
+
+    /// Create a [`RemoteWriteBuilder`] to send samples to a remote write receiver,
+    /// see [`crate::Client::remote_write`].
+    #[cfg(feature = "remote-write")]
+    pub fn remote_write(&self) -> RemoteWriteBuilder {
+        RemoteWriteBuilder::new(self.clone())
+    }
+
+    /// Create a [`RemoteReadBuilder`] to read raw samples from a remote read endpoint,
+    /// see [`crate::Client::remote_read`].
+    #[cfg(feature = "remote-read")]
+    pub fn remote_read(&self) -> RemoteReadBuilder {
+        RemoteReadBuilder::new(self.clone())
+    }
+
+    /// Check Prometheus server health, see [`crate::Client::is_server_healthy`].
+    pub fn is_server_healthy(&self) -> Result<bool, Error> {
+        let url = build_final_url(self.base_url.clone(), "-/healthy");
+        self.request(HttpMethod::GET, url)?
+            .send()
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "failed to send request to health endpoint",
+                    source: Some(source),
+                })
+            })?
+            .error_for_status()
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "request to health endpoint returned an error",
+                    source: Some(source),
+                })
+            })
+            .map(|_| true)
+    }
+
+    /// Check Prometheus server readiness, see [`crate::Client::is_server_ready`].
+    pub fn is_server_ready(&self) -> Result<bool, Error> {
+        self.check_ready(self.base_url.clone())
+    }
+
+    /// Return the replicas of this client and their health as observed by the client,
+    /// see [`Client::replicas`](crate::Client::replicas).
+    pub fn replicas(&self) -> Vec<ReplicaStatus> {
+        self.replicas
+            .as_ref()
+            .map(|r| r.status())
+            .unwrap_or_default()
+    }
+
+    /// Check the readiness of all replicas one after another, see
+    /// [`Client::check_replicas`](crate::Client::check_replicas).
+    pub fn check_replicas(&self) -> Vec<ReplicaStatus> {
+        if let Some(replicas) = &self.replicas {
+            for (index, base_url) in replicas.base_urls().enumerate() {
+                let ready = self.check_ready(base_url.clone()).is_ok();
+                replicas.set_ready(index, ready);
+            }
+        }
+        self.replicas()
+    }
+
+    fn check_ready(&self, base_url: Url) -> Result<bool, Error> {
+        let url = build_final_url(base_url, "-/ready");
+        self.request(HttpMethod::GET, url)?
+            .send()
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "failed to send request to readiness endpoint",
+                    source: Some(source),
+                })
+            })?
+            .error_for_status()
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "request to readiness endpoint returned an error",
+                    source: Some(source),
+                })
+            })
+            .map(|_| true)
+    }
+
+    // Deserialize the raw blocking response into a type `D`, see [`crate::Client`].
+    fn deserialize<D: DeserializeOwned>(response: Response) -> Result<D, Error> {
+        Client::parse(response)?.into_data()
+    }
+
+    // Like `deserialize`, but keep the warnings and infos of the response.
+    fn deserialize_annotated<D: DeserializeOwned>(
+        response: Response,
+    ) -> Result<Annotated<D>, Error> {
+        Client::parse(response)?.into_annotated()
+    }
+
+    // Handle responses of endpoints that reply with HTTP 204 (No Content) on success.
+    fn deserialize_empty(response: Response) -> Result<(), Error> {
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(());
+        }
+        Client::deserialize::<IgnoredAny>(response).map(|_| ())
+    }
+
+    // Deserialize the raw blocking response into a [`PromqlResult`].
+    fn deserialize_promql(response: Response, strict: bool) -> Result<PromqlResult, Error> {
+        Client::parse::<PromqlResult>(response)?.into_promql(strict)
+    }
+
+    // Parse the raw blocking response into an [`ApiResponse`] after making sure that
+    // the server actually sent a JSON-encoded body.
+    fn parse<D: DeserializeOwned>(response: Response) -> Result<ApiResponse<D>, Error> {
+        if !util::is_json(response.headers().get(CONTENT_TYPE)) {
+            return Err(Error::Client(ClientError {
+                message: "failed to parse response from server due to invalid media type",
+                source: response.error_for_status().err(),
+            }));
+        }
+        response.json::<ApiResponse<D>>().map_err(|source| {
+            Error::Client(ClientError {
+                message: "failed to parse JSON response from server",
+                source: Some(source),
+            })
+        })
+    }
+
+    // Check the raw blocking response and deserialize its body incrementally. Error responses
+    // are small enough to be deserialized completely before the iterator is created.
+    fn deserialize_stream<T: DeserializeOwned + Send + 'static>(
+        response: Response,
+        layout: Layout,
+        strict: bool,
+    ) -> Result<SeriesIter<T>, Error> {
+        if !util::is_json(response.headers().get(CONTENT_TYPE)) {
+            return Err(Error::Client(ClientError {
+                message: "failed to parse response from server due to invalid media type",
+                source: response.error_for_status().err(),
+            }));
+        }
+
+        if let Err(source) = response.error_for_status_ref() {
+            return match Client::parse::<IgnoredAny>(response)? {
+                ApiResponse::Error(e) => Err(Error::Prometheus(e)),
+                ApiResponse::Success { .. } => Err(Error::Client(ClientError {
+                    message: "request failed with an unexpected status code",
+                    source: Some(source),
+                })),
+            };
+        }
+
+        Ok(SeriesIter {
+            stream: SeriesStream::from_blocking_response(response, layout, strict),
+        })
+    }
+}
+
+/// An iterator over time series that are deserialized one at a time while the response
+/// body is received. This is the blocking counterpart of [`SeriesStream`], see e.g. the
+/// `get_stream` method of a blocking [`InstantQueryBuilder`].
+///
+/// As Prometheus sends warnings and infos after the result, they are only available via
+/// [`SeriesIter::warnings`] and [`SeriesIter::infos`] once the iterator is exhausted.
+/// In strict mode the iterator yields [`Error::Warnings`] as its last item if Prometheus
+/// attached any warnings to the response.
+pub struct SeriesIter<T> {
+    stream: SeriesStream<T>,
+}
+
+impl<T> SeriesIter<T> {
+    /// Return the warnings that Prometheus attached to the response. This is always empty
+    /// until the iterator has been consumed completely.
+    pub fn warnings(&self) -> &[Annotation] {
+        self.stream.warnings()
+    }
+
+    /// Return the informational annotations that Prometheus attached to the response.
+    /// This is always empty until the iterator has been consumed completely.
+    pub fn infos(&self) -> &[Annotation] {
+        self.stream.infos()
+    }
+}
+
+impl<T> Iterator for SeriesIter<T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The underlying stream reads the body synchronously and is always ready.
+        self.stream.next().now_or_never().flatten()
+    }
+}
+
+impl InstantQueryBuilder {
+    /// Execute the instant query (using HTTP GET) and return the parsed API response.
+    pub fn get(self) -> Result<PromqlResult, Error> {
+        let strict = self.strict;
+        let response = self.get_raw()?;
+        Client::deserialize_promql(response, strict)
+    }
+
+    /// Execute the instant query (using HTTP POST) and return the parsed API response.
+    pub fn post(self) -> Result<PromqlResult, Error> {
+        let strict = self.strict;
+        let response = self.post_raw()?;
+        Client::deserialize_promql(response, strict)
+    }
+
+    /// Execute the instant query (using HTTP GET) and return the raw API response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET)
+    }
+
+    /// Execute the instant query (using HTTP POST) and return the raw API response.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::POST)
+    }
+
+    /// Execute the instant query (using HTTP GET) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub fn get_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.get()?.decode()
+    }
+
+    /// Execute the instant query (using HTTP POST) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub fn post_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.post()?.decode()
+    }
+
+    /// Execute the instant query (using HTTP GET) and return an iterator that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesIter`].
+    /// Only results of type vector can be streamed, other result types produce an error.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::blocking::Client;
+    ///
+    /// fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let mut series = client.query("up").get_stream()?;
+    ///
+    ///     for vector in series.by_ref() {
+    ///         let vector = vector?;
+    ///         println!("{:?}: {:?}", vector.metric(), vector.sample());
+    ///     }
+    ///
+    ///     println!("warnings: {:?}", series.warnings());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn get_stream(self) -> Result<SeriesIter<InstantVector>, Error> {
+        let strict = self.strict;
+        let response = self.get_raw()?;
+        Client::deserialize_stream(response, Layout::Vector, strict)
+    }
+
+    /// Execute the instant query (using HTTP POST) and return an iterator that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesIter`].
+    pub fn post_stream(self) -> Result<SeriesIter<InstantVector>, Error> {
+        let strict = self.strict;
+        let response = self.post_raw()?;
+        Client::deserialize_stream(response, Layout::Vector, strict)
+    }
+}
+
+impl RangeQueryBuilder {
+    /// Execute the range query (using HTTP GET) and return the parsed API response.
+    pub fn get(self) -> Result<PromqlResult, Error> {
+        if self.split.is_some() {
+            return self.send_split(HttpMethod::GET);
+        }
+        let strict = self.strict;
+        let response = self.get_raw()?;
+        Client::deserialize_promql(response, strict)
+    }
+
+    /// Execute the range query (using HTTP POST) and return the parsed API response.
+    pub fn post(self) -> Result<PromqlResult, Error> {
+        if self.split.is_some() {
+            return self.send_split(HttpMethod::POST);
+        }
+        let strict = self.strict;
+        let response = self.post_raw()?;
+        Client::deserialize_promql(response, strict)
+    }
+
+    // Send one request per sub-range one after another and merge the results.
+    // The concurrency limit does not apply to the blocking client.
+    fn send_split(self, method: HttpMethod) -> Result<PromqlResult, Error> {
+        let strict = self.strict;
+        let (client, requests) = self.into_split_requests()?;
+
+        let results = requests
+            .into_iter()
+            .map(|request| {
+                let response = client.execute(request, method.clone())?;
+                Client::deserialize_promql(response, strict)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(PromqlResult::merge(results))
+    }
+
+    /// Execute the range query (using HTTP GET) and return the raw API response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET)
+    }
+
+    /// Execute the range query (using HTTP POST) and return the raw API response.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::POST)
+    }
+
+    /// Execute the range query (using HTTP GET) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub fn get_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.get()?.decode()
+    }
+
+    /// Execute the range query (using HTTP POST) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub fn post_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.post()?.decode()
+    }
+
+    /// Execute the range query (using HTTP GET) and return an iterator that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesIter`].
+    /// The query is always sent as a single request.
+    pub fn get_stream(self) -> Result<SeriesIter<RangeVector>, Error> {
+        let strict = self.strict;
+        let response = self.get_raw()?;
+        Client::deserialize_stream(response, Layout::Matrix, strict)
+    }
+
+    /// Execute the range query (using HTTP POST) and return an iterator that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesIter`].
+    /// The query is always sent as a single request.
+    pub fn post_stream(self) -> Result<SeriesIter<RangeVector>, Error> {
+        let strict = self.strict;
+        let response = self.post_raw()?;
+        Client::deserialize_stream(response, Layout::Matrix, strict)
+    }
+}
+
+impl RulesQueryBuilder {
+    /// Execute the rules query (using HTTP GET) and return the [`RuleGroup`]s sent
+    /// by Prometheus.
+    pub fn get(self) -> Result<Annotated<Vec<RuleGroup>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response).map(|r: Annotated<RuleGroups>| r.map(|r| r.groups))
+    }
+
+    /// Execute the rules query (using HTTP GET) and return the raw response sent
+    /// by Prometheus.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl TargetMetadataQueryBuilder {
+    /// Execute the target metadata query (using HTTP GET) and return the collection of
+    /// [`TargetMetadata`] sent by Prometheus.
+    pub fn get(self) -> Result<Annotated<Vec<TargetMetadata>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the target metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl MetricMetadataQueryBuilder {
+    /// Execute the metric metadata query (using HTTP GET) and return the collection of
+    /// [`MetricMetadata`] sent by Prometheus.
+    pub fn get(self) -> Result<Annotated<HashMap<String, Vec<MetricMetadata>>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the metric metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl SeriesQueryBuilder {
+    /// Execute the series metadata query (using HTTP GET) and return a collection of
+    /// matching time series sent by Prometheus.
+    pub fn get(self) -> Result<Annotated<Vec<HashMap<String, String>>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the series metadata query (using HTTP GET) and return an iterator that
+    /// yields the matching time series one at a time while the response is received,
+    /// see [`SeriesIter`].
+    pub fn get_stream(self) -> Result<SeriesIter<HashMap<String, String>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_stream(response, Layout::Series, false)
+    }
+
+    /// Execute the series metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl LabelNamesQueryBuilder {
+    /// Execute the query (using HTTP GET) and retrieve a collection of
+    /// label names.
+    pub fn get(self) -> Result<Annotated<Vec<String>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the query (using HTTP GET) and retrieve the raw response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl LabelValuesQueryBuilder {
+    /// Execute the query (using HTTP GET) and retrieve a collection of
+    /// label values for the given label name.
+    pub fn get(self) -> Result<Annotated<Vec<String>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the query (using HTTP GET) and retrieve the raw response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl ExemplarsQueryBuilder {
+    /// Execute the exemplars query (using HTTP GET) and return a collection of
+    /// [`ExemplarSeries`] sent by Prometheus.
+    pub fn get(self) -> Result<Annotated<Vec<ExemplarSeries>>, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize_annotated(response)
+    }
+
+    /// Execute the exemplars query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+}
+
+impl FormatQueryBuilder {
+    /// Format the query (using HTTP GET) and return the formatted query.
+    pub fn get(self) -> Result<String, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize(response)
+    }
+
+    /// Format the query (using HTTP POST) and return the formatted query.
+    pub fn post(self) -> Result<String, Error> {
+        let response = self.post_raw()?;
+        Client::deserialize(response)
+    }
+
+    /// Format the query (using HTTP GET) and return the raw API response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+
+    /// Format the query (using HTTP POST) and return the raw API response.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::POST)
+    }
+}
+
+impl ParseQueryBuilder {
+    /// Parse the query (using HTTP GET) and return the root node of the syntax tree.
+    pub fn get(self) -> Result<AstNode, Error> {
+        let response = self.get_raw()?;
+        Client::deserialize(response)
+    }
+
+    /// Parse the query (using HTTP POST) and return the root node of the syntax tree.
+    pub fn post(self) -> Result<AstNode, Error> {
+        let response = self.post_raw()?;
+        Client::deserialize(response)
+    }
+
+    /// Parse the query (using HTTP GET) and return the raw API response.
+    pub fn get_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET)
+    }
+
+    /// Parse the query (using HTTP POST) and return the raw API response.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::POST)
+    }
+}
+
+impl SnapshotBuilder {
+    /// Create the snapshot (using HTTP POST) and return the [`Snapshot`] sent
+    /// by Prometheus.
+    pub fn post(self) -> Result<Snapshot, Error> {
+        let response = self.post_raw()?;
+        Client::deserialize(response).map_err(crate::Client::map_admin_error)
+    }
+
+    /// Create the snapshot (using HTTP POST) and return the raw response sent
+    /// by Prometheus.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute_once(request, HttpMethod::POST)
+    }
+}
+
+impl DeleteSeriesBuilder {
+    /// Delete the matching time series (using HTTP POST).
+    /// Note that the data is not removed from disk until the next compaction
+    /// or until [`Client::clean_tombstones`] is called.
+    pub fn post(self) -> Result<(), Error> {
+        let response = self.post_raw()?;
+        Client::deserialize_empty(response).map_err(crate::Client::map_admin_error)
+    }
+
+    /// Delete the matching time series (using HTTP POST) and return the raw
+    /// response sent by Prometheus.
+    pub fn post_raw(self) -> Result<Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute_once(request, HttpMethod::POST)
+    }
+}
+
+#[cfg(feature = "remote-write")]
+impl RemoteWriteBuilder {
+    /// Encode all time series and send them to the remote write receiver (using HTTP POST).
+    pub fn send(self) -> Result<(), Error> {
+        let (client, request, bodies) = self.into_requests();
+        let url = build_final_url(client.base_url.clone(), &request.path);
+        let headers = request.headers.unwrap_or_default();
+
+        for body in bodies {
+            let response = client
+                .request(HttpMethod::POST, url.clone())?
+                .headers(headers.clone())
+                .body(body)
+                .send()
+                .map_err(|source| {
+                    Error::Client(ClientError {
+                        message: "failed to send remote write request to receiver",
+                        source: Some(source),
+                    })
+                })?;
+
+            let status = response.status();
+
+            if !status.is_success() {
+                let message = response.text().unwrap_or_default();
+                return Err(Error::RemoteWrite(crate::error::RemoteWriteError {
+                    status,
+                    message: message.trim().to_string(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "remote-read")]
+impl RemoteReadBuilder {
+    /// Send the read request to the remote read endpoint (using HTTP POST).
+    /// Returns one set of time series per query, in the order the queries were added.
+    pub fn post(self) -> Result<Vec<Vec<RangeVector>>, Error> {
+        let (request, body) = self.to_request();
+        let url = build_final_url(self.client.base_url.clone(), &request.path);
+
+        let response = self
+            .client
+            .request(HttpMethod::POST, url)?
+            .headers(request.headers.unwrap_or_default())
+            .body(body)
+            .send()
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "failed to send remote read request to server",
+                    source: Some(source),
+                })
+            })?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().unwrap_or_default();
+            return Err(Error::RemoteRead(crate::error::RemoteReadError {
+                status,
+                message: message.trim().to_string(),
+            }));
+        }
+
+        let content_type = response.headers().get(CONTENT_TYPE).cloned();
+
+        let body = response.bytes().map_err(|source| {
+            Error::Client(ClientError {
+                message: "failed to read remote read response body",
+                source: Some(source),
+            })
+        })?;
+
+        self.decode(content_type.as_ref(), &body)
+    }
+}
+
+/// Execute an instant query using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::query`].
+pub fn query(host: &str, query: impl std::fmt::Display) -> Result<InstantQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.query(query))
+}
+
+/// Execute a range query using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::query_range`].
+pub fn query_range(
+    host: &str,
+    query: impl std::fmt::Display,
+    start: impl Into<Timestamp>,
+    end: impl Into<Timestamp>,
+    step: impl Into<Step>,
+) -> Result<RangeQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.query_range(query, start, end, step))
+}
+
+/// Create a [`SeriesQueryBuilder`] to apply filters to a series metadata query using the
+/// blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::series`].
+pub fn series<T>(host: &str, selectors: T) -> Result<SeriesQueryBuilder, Error>
+where
+    T: IntoIterator,
+    T::Item: Borrow<Selector>,
+{
+    Client::from_str(host).and_then(|c| c.series(selectors))
+}
+
+/// Create a [`LabelNamesQueryBuilder`] to retrieve label names using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::label_names`].
+pub fn label_names(host: &str) -> Result<LabelNamesQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.label_names())
+}
+
+/// Create a [`LabelValuesQueryBuilder`] to retrieve label values using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::label_values`].
+pub fn label_values(host: &str, label: &str) -> Result<LabelValuesQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.label_values(label))
+}
+
+/// Create an [`ExemplarsQueryBuilder`] to retrieve exemplars using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::exemplars`].
+pub fn exemplars(
+    host: &str,
+    query: impl std::fmt::Display,
+) -> Result<ExemplarsQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.exemplars(query))
+}
+
+/// Query the current state of target discovery using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::targets`].
+pub fn targets(host: &str, state: Option<TargetState>) -> Result<Targets, Error> {
+    Client::from_str(host)?.targets(state)
+}
+
+/// Create a [`RulesQueryBuilder`] to retrieve rules using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::rules`].
+pub fn rules(host: &str) -> Result<RulesQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.rules())
+}
+
+/// Retrieve a list of active alerts using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::alerts`].
+pub fn alerts(host: &str) -> Result<Vec<Alert>, Error> {
+    Client::from_str(host)?.alerts()
+}
+
+/// Retrieve a list of flags that Prometheus was configured with using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::flags`].
+pub fn flags(host: &str) -> Result<HashMap<String, String>, Error> {
+    Client::from_str(host)?.flags()
+}
+
+/// Retrieve Prometheus server build information using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::build_information`].
+pub fn build_information(host: &str) -> Result<BuildInformation, Error> {
+    Client::from_str(host)?.build_information()
+}
+
+/// Retrieve Prometheus server runtime information using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::runtime_information`].
+pub fn runtime_information(host: &str) -> Result<RuntimeInformation, Error> {
+    Client::from_str(host)?.runtime_information()
+}
+
+/// Query the current state of alertmanager discovery using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::alertmanagers`].
+pub fn alertmanagers(host: &str) -> Result<Alertmanagers, Error> {
+    Client::from_str(host)?.alertmanagers()
+}
+
+/// Create a [`TargetMetadataQueryBuilder`] to retrieve target metadata using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::target_metadata`].
+pub fn target_metadata(host: &str) -> Result<TargetMetadataQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.target_metadata())
+}
+
+/// Create a [`MetricMetadataQueryBuilder`] to retrieve metric metadata using the blocking [`Client`].
+///
+/// This is just a convenience function for one-off requests, see [`Client::metric_metadata`].
+pub fn metric_metadata(host: &str) -> Result<MetricMetadataQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.metric_metadata())
+}
@@ -1,12 +1,30 @@
-use crate::error::{ClientError, Error};
+use crate::auth::{Authorization, Credentials};
+use crate::error::{ClientError, Error, WarningsError};
+use crate::ha::{ReplicaSet, ReplicaStatus, Strategy, DEFAULT_COOLDOWN};
+use crate::promql::Expr;
 use crate::response::*;
+use crate::retry::{Retries, RetryPolicy};
 use crate::selector::Selector;
-use crate::util::{self, build_final_url, RuleKind, TargetState, ToBaseUrl};
-use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName, CONTENT_TYPE};
+use crate::stream::{Layout, SeriesStream};
+use crate::util::{
+    self, build_final_url, tenant_header_value, NameValidationScheme, Resolution, RuleKind, Step,
+    TargetState, Timeout, Timestamp, ToBaseUrl, DEFAULT_TENANT_HEADER,
+};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, IntoHeaderName, AUTHORIZATION, CONTENT_TYPE,
+};
 use reqwest::Method as HttpMethod;
-use serde::{de::DeserializeOwned, Serialize};
-use std::borrow::Borrow;
+use reqwest::StatusCode;
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use url::Url;
 
 #[cfg(feature = "middleware")]
@@ -26,30 +44,66 @@ impl reqwest_middleware::Middleware for EmptyMiddleware {
     }
 }
 
+// The path, query parameters and headers of an API request that has been assembled
+// by one of the builders, independent of the client that finally sends it.
+pub(crate) struct Request {
+    pub(crate) path: Cow<'static, str>,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl Request {
+    pub(crate) fn new(
+        path: impl Into<Cow<'static, str>>,
+        params: Vec<(&'static str, String)>,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Self {
+        Request {
+            path: path.into(),
+            params,
+            headers,
+        }
+    }
+}
+
 /// Provides a builder to set some query parameters in the context
 /// of an instant query before sending it to Prometheus.
 #[derive(Clone)]
-pub struct InstantQueryBuilder {
-    client: Client,
-    params: Vec<(&'static str, String)>,
-    headers: Option<HeaderMap<HeaderValue>>,
+pub struct InstantQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+    pub(crate) strict: bool,
+    pub(crate) validate: bool,
 }
 
-impl InstantQueryBuilder {
-    /// Set the evaluation timestamp (Unix timestamp in seconds, e.g. 1659182624).
+impl<C> InstantQueryBuilder<C> {
+    pub(crate) fn new(client: C, query: impl std::fmt::Display) -> Self {
+        InstantQueryBuilder {
+            client,
+            params: vec![("query", query.to_string())],
+            headers: Default::default(),
+            strict: false,
+            validate: false,
+        }
+    }
+
+    /// Set the evaluation timestamp (e.g. a Unix timestamp in seconds like 1659182624,
+    /// see [`Timestamp`] for other types that may be used).
     /// If this is not set the evaluation timestamp will default to the current Prometheus
     /// server time.
     /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#instant-queries)
-    pub fn at(mut self, time: i64) -> Self {
-        self.params.push(("time", time.to_string()));
+    pub fn at(mut self, time: impl Into<Timestamp>) -> Self {
+        self.params.push(("time", time.into().to_string()));
         self
     }
 
-    /// Set the evaluation timeout (milliseconds, e.g. 1000).
+    /// Set the evaluation timeout (milliseconds, e.g. 1000, or a [`time::Duration`],
+    /// see [`Timeout`]).
     /// If this is not set the timeout will default to the value of the "-query.timeout" flag of the Prometheus server.
     /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#instant-queries)
-    pub fn timeout(mut self, timeout: i64) -> Self {
-        self.params.push(("timeout", format!("{}ms", timeout)));
+    pub fn timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.params.push(("timeout", timeout.into().to_string()));
         self
     }
 
@@ -59,6 +113,33 @@ impl InstantQueryBuilder {
         self
     }
 
+    /// Enable strict mode. When Prometheus attaches warnings to the response
+    /// (e.g. because the result may be incomplete) the query fails with
+    /// [`Error::Warnings`] instead of returning the result.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Parse the query locally before sending it to Prometheus. Queries that are not valid
+    /// PromQL expressions fail with [`Error::Parse`] without a round trip to the server.
+    /// See also [`Expr`](crate::promql::Expr).
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Error};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = Client::default();
+    ///     let result = client.query("sum(rate(foo))").validate().get().await;
+    ///     assert!(matches!(result, Err(Error::Parse(_))));
+    /// }
+    /// ```
+    pub fn validate(mut self) -> Self {
+        self.validate = true;
+        self
+    }
+
     /// Include an additional header to the request.
     pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
         self.headers
@@ -73,10 +154,30 @@ impl InstantQueryBuilder {
         self
     }
 
+    pub(crate) fn into_request(self) -> Result<(C, Request), Error> {
+        validate_query(self.validate, &self.params)?;
+        let request = Request::new("api/v1/query", self.params, self.headers);
+        Ok((self.client, request))
+    }
+
+    // Copy the query parameters to a builder that sends the same query with another client.
+    pub(crate) fn with_client<D>(&self, client: D) -> InstantQueryBuilder<D> {
+        InstantQueryBuilder {
+            client,
+            params: self.params.clone(),
+            headers: self.headers.clone(),
+            strict: self.strict,
+            validate: self.validate,
+        }
+    }
+}
+
+impl InstantQueryBuilder {
     /// Execute the instant query (using HTTP GET) and return the parsed API response.
     pub async fn get(self) -> Result<PromqlResult, Error> {
+        let strict = self.strict;
         let response = self.get_raw().await?;
-        Client::deserialize(response).await
+        Client::deserialize_promql(response, strict).await
     }
 
     /// Execute the instant query (using HTTP POST) and return the parsed API response.
@@ -84,15 +185,15 @@ impl InstantQueryBuilder {
     /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
     /// character limits.
     pub async fn post(self) -> Result<PromqlResult, Error> {
+        let strict = self.strict;
         let response = self.post_raw().await?;
-        Client::deserialize(response).await
+        Client::deserialize_promql(response, strict).await
     }
 
     /// Execute the instant query (using HTTP GET) and return the raw API response.
     pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
-        self.client
-            .send("api/v1/query", &self.params, HttpMethod::GET, self.headers)
-            .await
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET).await
     }
 
     /// Execute the instant query (using HTTP POST) and return the raw API response.
@@ -100,27 +201,122 @@ impl InstantQueryBuilder {
     /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
     /// character limits.
     pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
-        self.client
-            .send("api/v1/query", &self.params, HttpMethod::POST, self.headers)
-            .await
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::POST).await
+    }
+
+    /// Execute the instant query (using HTTP GET) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub async fn get_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.get().await?.decode()
+    }
+
+    /// Execute the instant query (using HTTP POST) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub async fn post_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.post().await?.decode()
+    }
+
+    /// Execute the instant query (using HTTP GET) and return a stream that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesStream`].
+    /// Only results of type vector can be streamed, other result types produce an error.
+    ///
+    /// ```rust
+    /// use futures::TryStreamExt;
+    /// use prometheus_http_query::Client;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let mut stream = client.query("up").get_stream().await?;
+    ///
+    ///     while let Some(series) = stream.try_next().await? {
+    ///         println!("{:?}: {:?}", series.metric(), series.sample());
+    ///     }
+    ///
+    ///     println!("warnings: {:?}", stream.warnings());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn get_stream(self) -> Result<SeriesStream<InstantVector>, Error> {
+        let strict = self.strict;
+        let response = self.get_raw().await?;
+        Client::deserialize_stream(response, Layout::Vector, strict).await
+    }
+
+    /// Execute the instant query (using HTTP POST) and return a stream that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesStream`].
+    pub async fn post_stream(self) -> Result<SeriesStream<InstantVector>, Error> {
+        let strict = self.strict;
+        let response = self.post_raw().await?;
+        Client::deserialize_stream(response, Layout::Vector, strict).await
+    }
+}
+
+// Parse the PromQL query of an instant or range query if local validation is enabled.
+pub(crate) fn validate_query(
+    validate: bool,
+    params: &[(&'static str, String)],
+) -> Result<(), Error> {
+    if validate {
+        if let Some((_, query)) = params.iter().find(|(name, _)| *name == "query") {
+            query.parse::<Expr>().map_err(Error::Parse)?;
+        }
     }
+    Ok(())
 }
 
 /// Provides a builder to set some query parameters in the context
 /// of a range query before sending it to Prometheus.
 #[derive(Clone)]
-pub struct RangeQueryBuilder {
-    client: Client,
-    params: Vec<(&'static str, String)>,
-    headers: Option<HeaderMap<HeaderValue>>,
+pub struct RangeQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+    pub(crate) strict: bool,
+    pub(crate) validate: bool,
+    pub(crate) start: Timestamp,
+    pub(crate) end: Timestamp,
+    pub(crate) step: Step,
+    pub(crate) split: Option<usize>,
+    pub(crate) concurrency: usize,
 }
 
-impl RangeQueryBuilder {
-    /// Set the evaluation timeout (milliseconds, e.g. 1000).
+impl<C> RangeQueryBuilder<C> {
+    pub(crate) fn new(
+        client: C,
+        query: impl std::fmt::Display,
+        start: Timestamp,
+        end: Timestamp,
+        step: Step,
+    ) -> Self {
+        RangeQueryBuilder {
+            client,
+            params: vec![
+                ("query", query.to_string()),
+                ("start", start.to_string()),
+                ("end", end.to_string()),
+                ("step", step.to_string()),
+            ],
+            headers: Default::default(),
+            strict: false,
+            validate: false,
+            start,
+            end,
+            step,
+            split: None,
+            concurrency: 4,
+        }
+    }
+
+    /// Set the evaluation timeout (milliseconds, e.g. 1000, or a [`time::Duration`],
+    /// see [`Timeout`]).
     /// If this is not set the timeout will default to the value of the "-query.timeout" flag of the Prometheus server.
     /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#range-queries)
-    pub fn timeout(mut self, timeout: i64) -> Self {
-        self.params.push(("timeout", format!("{}ms", timeout)));
+    pub fn timeout(mut self, timeout: impl Into<Timeout>) -> Self {
+        self.params.push(("timeout", timeout.into().to_string()));
         self
     }
 
@@ -130,6 +326,66 @@ impl RangeQueryBuilder {
         self
     }
 
+    /// Enable strict mode. When Prometheus attaches warnings to the response
+    /// (e.g. because the result may be incomplete) the query fails with
+    /// [`Error::Warnings`] instead of returning the result.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Parse the query locally before sending it to Prometheus. Queries that are not valid
+    /// PromQL expressions fail with [`Error::Parse`] without a round trip to the server.
+    /// See also [`Expr`](crate::promql::Expr).
+    pub fn validate(mut self) -> Self {
+        self.validate = true;
+        self
+    }
+
+    /// Split the range into consecutive, step-aligned sub-ranges of at most `max_points`
+    /// evaluation steps each and send one request per sub-range. The partial results are
+    /// stitched back together by label set, samples that occur in more than one partial
+    /// result are de-duplicated and the [`Stats`] of all requests are aggregated.
+    ///
+    /// This helps with ranges that exceed Prometheus' limit of 11,000 points per series or
+    /// that would otherwise run into the evaluation timeout. Only affects [`RangeQueryBuilder::get`]
+    /// and [`RangeQueryBuilder::post`], raw requests are always sent as a single request.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Timestamp};
+    /// use time::Duration;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let start = Timestamp::ago(Duration::days(30));
+    ///     let end = Timestamp::now();
+    ///
+    ///     let response = client
+    ///         .query_range("up", start, end, Duration::seconds(15))
+    ///         .split(11_000)
+    ///         .concurrency(2)
+    ///         .get()
+    ///         .await?;
+    ///
+    ///     assert!(response.data().as_matrix().is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn split(mut self, max_points: usize) -> Self {
+        self.split = Some(max_points.max(1));
+        self
+    }
+
+    /// Set the maximum number of sub-range requests that are in flight at the same time
+    /// when the query is split (see [`RangeQueryBuilder::split`]). Defaults to 4.
+    pub fn concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
     /// Include an additional header to the request.
     pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
         self.headers
@@ -144,10 +400,64 @@ impl RangeQueryBuilder {
         self
     }
 
+    pub(crate) fn into_request(self) -> Result<(C, Request), Error> {
+        validate_query(self.validate, &self.params)?;
+        let request = Request::new("api/v1/query_range", self.params, self.headers);
+        Ok((self.client, request))
+    }
+
+    // Copy the query parameters to a builder that sends the same query with another client.
+    pub(crate) fn with_client<D>(&self, client: D) -> RangeQueryBuilder<D> {
+        RangeQueryBuilder {
+            client,
+            params: self.params.clone(),
+            headers: self.headers.clone(),
+            strict: self.strict,
+            validate: self.validate,
+            start: self.start,
+            end: self.end,
+            step: self.step,
+            split: self.split,
+            concurrency: self.concurrency,
+        }
+    }
+
+    // Build one request per sub-range of at most `split` evaluation steps.
+    pub(crate) fn into_split_requests(self) -> Result<(C, Vec<Request>), Error> {
+        validate_query(self.validate, &self.params)?;
+
+        let max_points = self.split.unwrap_or(usize::MAX);
+        let ranges = split_range(self.start, self.end, self.step, max_points);
+
+        let requests = ranges
+            .into_iter()
+            .map(|(start, end)| {
+                let params = self
+                    .params
+                    .iter()
+                    .map(|(name, value)| match *name {
+                        "start" => (*name, start.to_string()),
+                        "end" => (*name, end.to_string()),
+                        _ => (*name, value.clone()),
+                    })
+                    .collect();
+                Request::new("api/v1/query_range", params, self.headers.clone())
+            })
+            .collect();
+
+        Ok((self.client, requests))
+    }
+}
+
+impl RangeQueryBuilder {
     /// Execute the range query (using HTTP GET) and return the parsed API response.
     pub async fn get(self) -> Result<PromqlResult, Error> {
+        if self.split.is_some() {
+            return self.send_split(HttpMethod::GET).await;
+        }
+        let strict = self.strict;
         let response = self.get_raw().await?;
-        Client::deserialize(response).await
+        Client::deserialize_promql(response, strict).await
     }
 
     /// Execute the instant query (using HTTP POST) and return the parsed API response.
@@ -155,20 +465,40 @@ impl RangeQueryBuilder {
     /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
     /// character limits.
     pub async fn post(self) -> Result<PromqlResult, Error> {
+        if self.split.is_some() {
+            return self.send_split(HttpMethod::POST).await;
+        }
+        let strict = self.strict;
         let response = self.post_raw().await?;
-        Client::deserialize(response).await
+        Client::deserialize_promql(response, strict).await
+    }
+
+    // Send one request per sub-range with bounded concurrency and merge the results.
+    async fn send_split(self, method: HttpMethod) -> Result<PromqlResult, Error> {
+        let (strict, concurrency) = (self.strict, self.concurrency);
+        let (client, requests) = self.into_split_requests()?;
+
+        let requests = requests.into_iter().map(|request| {
+            let method = method.clone();
+            let client = &client;
+            async move {
+                let response = client.execute(request, method).await?;
+                Client::deserialize_promql(response, strict).await
+            }
+        });
+
+        let results: Vec<PromqlResult> = stream::iter(requests)
+            .buffered(concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(PromqlResult::merge(results))
     }
 
     /// Execute the range query (using HTTP GET) and return the raw API response.
     pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
-        self.client
-            .send(
-                "api/v1/query_range",
-                &self.params,
-                HttpMethod::GET,
-                self.headers,
-            )
-            .await
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET).await
     }
 
     /// Execute the instant query (using HTTP POST) and return the raw API response.
@@ -176,32 +506,106 @@ impl RangeQueryBuilder {
     /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
     /// character limits.
     pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
-        self.client
-            .send(
-                "api/v1/query_range",
-                &self.params,
-                HttpMethod::POST,
-                self.headers,
-            )
-            .await
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::POST).await
+    }
+
+    /// Execute the range query (using HTTP GET) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub async fn get_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.get().await?.decode()
+    }
+
+    /// Execute the range query (using HTTP POST) and decode the resulting time series
+    /// into a user-defined type, see [`PromqlResult::decode`].
+    pub async fn post_as<T: DeserializeOwned>(self) -> Result<Vec<T>, Error> {
+        self.post().await?.decode()
+    }
+
+    /// Execute the range query (using HTTP GET) and return a stream that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesStream`].
+    /// The query is always sent as a single request, see [`RangeQueryBuilder::split`].
+    pub async fn get_stream(self) -> Result<SeriesStream<RangeVector>, Error> {
+        let strict = self.strict;
+        let response = self.get_raw().await?;
+        Client::deserialize_stream(response, Layout::Matrix, strict).await
     }
+
+    /// Execute the range query (using HTTP POST) and return a stream that yields the
+    /// resulting time series one at a time while the response is received, see [`SeriesStream`].
+    /// The query is always sent as a single request, see [`RangeQueryBuilder::split`].
+    pub async fn post_stream(self) -> Result<SeriesStream<RangeVector>, Error> {
+        let strict = self.strict;
+        let response = self.post_raw().await?;
+        Client::deserialize_stream(response, Layout::Matrix, strict).await
+    }
+}
+
+// Split the range between `start` and `end` into consecutive sub-ranges of at most
+// `max_points` evaluation steps. Sub-ranges start at evaluation timestamps of the
+// original range so that the stitched result contains the same samples.
+fn split_range(
+    start: Timestamp,
+    end: Timestamp,
+    step: Step,
+    max_points: usize,
+) -> Vec<(Timestamp, Timestamp)> {
+    let step = (step.as_seconds_f64() * 1000.0).round() as i64;
+    let (start, end) = (start.unix_millis(), end.unix_millis());
+
+    if step <= 0 || end <= start {
+        return vec![(
+            Timestamp::from_unix_millis(start),
+            Timestamp::from_unix_millis(end),
+        )];
+    }
+
+    let width = step.saturating_mul(max_points.max(1) as i64);
+    let mut ranges = vec![];
+    let mut from = start;
+
+    loop {
+        let to = from.saturating_add(width - step).min(end);
+        ranges.push((
+            Timestamp::from_unix_millis(from),
+            Timestamp::from_unix_millis(to),
+        ));
+        match from.checked_add(width) {
+            Some(next) if next <= end => from = next,
+            _ => break,
+        }
+    }
+
+    ranges
 }
 
 /// Provides methods to build a query to the rules endpoint and send it to Prometheus.
 #[derive(Clone)]
-pub struct RulesQueryBuilder {
-    client: Client,
-    kind: Option<RuleKind>,
-    names: Vec<String>,
-    groups: Vec<String>,
-    files: Vec<String>,
+pub struct RulesQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) kind: Option<RuleKind>,
+    pub(crate) names: Vec<String>,
+    pub(crate) groups: Vec<String>,
+    pub(crate) files: Vec<String>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
 }
 
 /// Note that Prometheus combines all filters that have been set in the final request
 /// and only returns rules that match all filters.<br>
 /// See the official documentation for a thorough explanation on the filters that can
 /// be set: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#rules).
-impl RulesQueryBuilder {
+impl<C> RulesQueryBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        RulesQueryBuilder {
+            client,
+            kind: None,
+            names: vec![],
+            groups: vec![],
+            files: vec![],
+            headers: Default::default(),
+        }
+    }
+
     /// Set this to instruct Prometheus to only return a specific type of rule
     /// (either recording or alerting rules) instead of both. Calling this repeatedly
     /// will replace the current setting.
@@ -271,18 +675,15 @@ impl RulesQueryBuilder {
         self
     }
 
-    /// Execute the rules query (using HTTP GET) and return the [`RuleGroup`]s sent
-    /// by Prometheus.
-    pub async fn get(self) -> Result<Vec<RuleGroup>, Error> {
-        let response = self.get_raw().await?;
-        Client::deserialize(response)
-            .await
-            .map(|r: RuleGroups| r.groups)
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
     }
 
-    /// Execute the rules query (using HTTP GET) and return the raw response sent
-    /// by Prometheus.
-    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+    pub(crate) fn into_request(self) -> (C, Request) {
         let mut params = vec![];
 
         if let Some(k) = self.kind {
@@ -301,30 +702,61 @@ impl RulesQueryBuilder {
             params.push(("file[]", file))
         }
 
-        self.client
-            .send("api/v1/rules", &params, HttpMethod::GET, None)
+        (
+            self.client,
+            Request::new("api/v1/rules", params, self.headers),
+        )
+    }
+}
+
+impl RulesQueryBuilder {
+    /// Execute the rules query (using HTTP GET) and return the [`RuleGroup`]s sent
+    /// by Prometheus.
+    pub async fn get(self) -> Result<Annotated<Vec<RuleGroup>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response)
             .await
+            .map(|r: Annotated<RuleGroups>| r.map(|r| r.groups))
+    }
+
+    /// Execute the rules query (using HTTP GET) and return the raw response sent
+    /// by Prometheus.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
     }
 }
 
 /// Provides methods to build a query to the target metadata endpoint and send it to Prometheus.
 #[derive(Clone)]
-pub struct TargetMetadataQueryBuilder<'a> {
-    client: Client,
-    match_target: Option<Selector<'a>>,
-    metric: Option<String>,
-    limit: Option<i32>,
+pub struct TargetMetadataQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) match_target: Option<Selector>,
+    pub(crate) metric: Option<String>,
+    pub(crate) limit: Option<i32>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
 }
 
 /// Note that Prometheus combines all filters that have been set in the final request
 /// and only returns target metadata that matches all filters.<br>
 /// See the official documentation for a thorough explanation on the filters that can
 /// be set: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#querying-target-metadata).
-impl<'a> TargetMetadataQueryBuilder<'a> {
+impl<C> TargetMetadataQueryBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        TargetMetadataQueryBuilder {
+            client,
+            match_target: None,
+            metric: None,
+            limit: None,
+            headers: Default::default(),
+        }
+    }
+
     /// Pass a label selector to instruct Prometheus to filter targets by their label
-    /// sets.
+    /// sets. The selector is validated using [`NameValidationScheme::Utf8`] before the
+    /// query is sent.
     /// Calling this repeatedly will replace the current label selector.
-    pub fn match_target(mut self, selector: &'a Selector<'a>) -> Self {
+    pub fn match_target(mut self, selector: &Selector) -> Self {
         self.match_target = Some(selector.clone());
         self
     }
@@ -343,16 +775,15 @@ impl<'a> TargetMetadataQueryBuilder<'a> {
         self
     }
 
-    /// Execute the target metadata query (using HTTP GET) and return the collection of
-    /// [`TargetMetadata`] sent by Prometheus.
-    pub async fn get(self) -> Result<Vec<TargetMetadata>, Error> {
-        let response = self.get_raw().await?;
-        Client::deserialize(response).await
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
     }
 
-    /// Execute the target metadata query (using HTTP GET) and return the raw response
-    /// sent by Prometheus.
-    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+    pub(crate) fn into_request(self) -> Result<(C, Request), Error> {
         let mut params = vec![];
 
         if let Some(metric) = self.metric {
@@ -360,6 +791,7 @@ impl<'a> TargetMetadataQueryBuilder<'a> {
         }
 
         if let Some(match_target) = self.match_target {
+            match_target.validate(NameValidationScheme::Utf8)?;
             params.push(("match_target", match_target.to_string()))
         }
 
@@ -367,26 +799,54 @@ impl<'a> TargetMetadataQueryBuilder<'a> {
             params.push(("limit", limit.to_string()))
         }
 
-        self.client
-            .send("api/v1/targets/metadata", &params, HttpMethod::GET, None)
-            .await
+        Ok((
+            self.client,
+            Request::new("api/v1/targets/metadata", params, self.headers),
+        ))
+    }
+}
+
+impl TargetMetadataQueryBuilder {
+    /// Execute the target metadata query (using HTTP GET) and return the collection of
+    /// [`TargetMetadata`] sent by Prometheus.
+    pub async fn get(self) -> Result<Annotated<Vec<TargetMetadata>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response).await
+    }
+
+    /// Execute the target metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET).await
     }
 }
 
 /// Provides methods to build a query to the metric metadata endpoint and send it to Prometheus.
 #[derive(Clone)]
-pub struct MetricMetadataQueryBuilder {
-    client: Client,
-    metric: Option<String>,
-    limit: Option<i32>,
-    limit_per_metric: Option<i32>,
+pub struct MetricMetadataQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) metric: Option<String>,
+    pub(crate) limit: Option<i32>,
+    pub(crate) limit_per_metric: Option<i32>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
 }
 
 /// Note that Prometheus combines all filters that have been set in the final request
 /// and only returns metric metadata that matches all filters.<br>
 /// See the official documentation for a thorough explanation on the filters that can
 /// be set: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#querying-metric-metadata).
-impl MetricMetadataQueryBuilder {
+impl<C> MetricMetadataQueryBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        MetricMetadataQueryBuilder {
+            client,
+            metric: None,
+            limit: None,
+            limit_per_metric: None,
+            headers: Default::default(),
+        }
+    }
+
     /// Instruct Prometheus to filter metadata by this metric name.
     /// Calling this repeatedly will replace the current setting.
     pub fn metric(mut self, metric: impl std::fmt::Display) -> Self {
@@ -408,16 +868,15 @@ impl MetricMetadataQueryBuilder {
         self
     }
 
-    /// Execute the metric metadata query (using HTTP GET) and return the collection of
-    /// [`MetricMetadata`] sent by Prometheus.
-    pub async fn get(self) -> Result<HashMap<String, Vec<MetricMetadata>>, Error> {
-        let response = self.get_raw().await?;
-        Client::deserialize(response).await
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
     }
 
-    /// Execute the metric metadata query (using HTTP GET) and return the raw response
-    /// sent by Prometheus.
-    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+    pub(crate) fn into_request(self) -> (C, Request) {
         let mut params = vec![];
 
         if let Some(metric) = self.metric {
@@ -432,48 +891,107 @@ impl MetricMetadataQueryBuilder {
             params.push(("limit_per_metric", limit_per_metric.to_string()))
         }
 
-        self.client
-            .send("api/v1/metadata", &params, HttpMethod::GET, None)
-            .await
+        (
+            self.client,
+            Request::new("api/v1/metadata", params, self.headers),
+        )
     }
 }
 
-/// Provides methods to build a query to the series endpoint and send it to Prometheus.
-#[derive(Clone)]
-pub struct SeriesQueryBuilder {
-    client: Client,
-    selectors: Vec<(&'static str, String)>,
-    start: Option<i64>,
-    end: Option<i64>,
-}
-
-impl SeriesQueryBuilder {
-    /// Limit the amount of metadata returned by setting a start time
-    /// (UNIX timestamp in seconds).
-    /// Calling this repeatedly will replace the current setting.
-    pub fn start(mut self, start: i64) -> Self {
-        self.start = Some(start);
-        self
+impl MetricMetadataQueryBuilder {
+    /// Execute the metric metadata query (using HTTP GET) and return the collection of
+    /// [`MetricMetadata`] sent by Prometheus.
+    pub async fn get(self) -> Result<Annotated<HashMap<String, Vec<MetricMetadata>>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response).await
+    }
+
+    /// Execute the metric metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
+    }
+}
+
+// Validate series selectors using the UTF-8 name validation scheme and turn them into
+// `match[]` parameters.
+fn match_params(
+    selectors: impl IntoIterator<Item = impl Borrow<Selector>>,
+) -> Result<Vec<(&'static str, String)>, Error> {
+    selectors
+        .into_iter()
+        .map(|s| {
+            let s = s.borrow();
+            s.validate(NameValidationScheme::Utf8)
+                .map(|_| ("match[]", s.to_string()))
+        })
+        .collect()
+}
+
+/// Provides methods to build a query to the series endpoint and send it to Prometheus.
+#[derive(Clone)]
+pub struct SeriesQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) selectors: Vec<(&'static str, String)>,
+    pub(crate) start: Option<Timestamp>,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> SeriesQueryBuilder<C> {
+    pub(crate) fn new(
+        client: C,
+        selectors: impl IntoIterator<Item = impl Borrow<Selector>>,
+    ) -> Result<Self, Error> {
+        let selectors = match_params(selectors)?;
+
+        if selectors.is_empty() {
+            Err(Error::EmptySeriesSelector)
+        } else {
+            Ok(SeriesQueryBuilder {
+                client,
+                selectors,
+                start: None,
+                end: None,
+                params: vec![],
+                headers: Default::default(),
+            })
+        }
+    }
+
+    /// Limit the amount of metadata returned by setting a start time
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// Calling this repeatedly will replace the current setting.
+    pub fn start(mut self, start: impl Into<Timestamp>) -> Self {
+        self.start = Some(start.into());
+        self
     }
 
     /// Limit the amount of metadata returned by setting an end time
-    /// (UNIX timestamp in seconds).
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
     /// Calling this repeatedly will replace the current setting.
-    pub fn end(mut self, end: i64) -> Self {
-        self.end = Some(end);
+    pub fn end(mut self, end: impl Into<Timestamp>) -> Self {
+        self.end = Some(end.into());
         self
     }
 
-    /// Execute the series metadata query (using HTTP GET) and return a collection of
-    /// matching time series sent by Prometheus.
-    pub async fn get(self) -> Result<Vec<HashMap<String, String>>, Error> {
-        let response = self.get_raw().await?;
-        Client::deserialize(response).await
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
     }
 
-    /// Execute the series metadata query (using HTTP GET) and return the raw response
-    /// sent by Prometheus.
-    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+    /// Include an additional parameter to the request.
+    pub fn query(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub(crate) fn into_request(self) -> (C, Request) {
         let mut params = vec![];
 
         if let Some(start) = self.start {
@@ -485,65 +1003,217 @@ impl SeriesQueryBuilder {
         }
 
         params.extend(self.selectors);
+        params.extend(self.params);
 
-        self.client
-            .send("api/v1/series", &params, HttpMethod::GET, None)
-            .await
+        (
+            self.client,
+            Request::new("api/v1/series", params, self.headers),
+        )
+    }
+}
+
+impl SeriesQueryBuilder {
+    /// Execute the series metadata query (using HTTP GET) and return a collection of
+    /// matching time series sent by Prometheus.
+    pub async fn get(self) -> Result<Annotated<Vec<HashMap<String, String>>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response).await
+    }
+
+    /// Execute the series metadata query (using HTTP GET) and return a stream that yields
+    /// the matching time series one at a time while the response is received,
+    /// see [`SeriesStream`].
+    pub async fn get_stream(self) -> Result<SeriesStream<HashMap<String, String>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_stream(response, Layout::Series, false).await
+    }
+
+    /// Execute the series metadata query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
     }
 }
 
 /// Provides methods to build a query to retrieve label names from Prometheus.
 #[derive(Clone)]
-pub struct LabelNamesQueryBuilder {
-    client: Client,
-    selectors: Vec<(&'static str, String)>,
-    start: Option<i64>,
-    end: Option<i64>,
+pub struct LabelNamesQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) selectors: Vec<Selector>,
+    pub(crate) start: Option<Timestamp>,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
 }
 
-impl LabelNamesQueryBuilder {
+impl<C> LabelNamesQueryBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        LabelNamesQueryBuilder {
+            client,
+            selectors: vec![],
+            start: None,
+            end: None,
+            params: vec![],
+            headers: Default::default(),
+        }
+    }
+
     /// Set series selectors to filter the time series from wich Prometheus
     /// reads labels from.
     /// This can be called multiple times to merge the series selectors with
-    /// those that have been set before.
-    pub fn selectors<'a, T>(mut self, selectors: T) -> Self
+    /// those that have been set before.<br>
+    /// The selectors are validated using [`NameValidationScheme::Utf8`] before the
+    /// query is sent.
+    pub fn selectors<T>(mut self, selectors: T) -> Self
     where
         T: IntoIterator,
-        T::Item: Borrow<Selector<'a>>,
+        T::Item: Borrow<Selector>,
     {
-        self.selectors.extend(
-            selectors
-                .into_iter()
-                .map(|s| ("match[]", s.borrow().to_string())),
-        );
+        self.selectors
+            .extend(selectors.into_iter().map(|s| s.borrow().clone()));
         self
     }
 
     /// Limit the amount of metadata returned by setting a start time
-    /// (UNIX timestamp in seconds).
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
     /// Calling this repeatedly will replace the current setting.
-    pub fn start(mut self, start: i64) -> Self {
-        self.start = Some(start);
+    pub fn start(mut self, start: impl Into<Timestamp>) -> Self {
+        self.start = Some(start.into());
         self
     }
 
     /// Limit the amount of metadata returned by setting an end time
-    /// (UNIX timestamp in seconds).
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
     /// Calling this repeatedly will replace the current setting.
-    pub fn end(mut self, end: i64) -> Self {
-        self.end = Some(end);
+    pub fn end(mut self, end: impl Into<Timestamp>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
         self
     }
 
+    /// Include an additional parameter to the request.
+    pub fn query(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub(crate) fn into_request(self) -> Result<(C, Request), Error> {
+        let mut params = vec![];
+
+        if let Some(start) = self.start {
+            params.push(("start", start.to_string()));
+        }
+
+        if let Some(end) = self.end {
+            params.push(("end", end.to_string()));
+        }
+
+        params.extend(match_params(&self.selectors)?);
+        params.extend(self.params);
+
+        Ok((
+            self.client,
+            Request::new("api/v1/labels", params, self.headers),
+        ))
+    }
+}
+
+impl LabelNamesQueryBuilder {
     /// Execute the query (using HTTP GET) and retrieve a collection of
     /// label names.
-    pub async fn get(self) -> Result<Vec<String>, Error> {
+    pub async fn get(self) -> Result<Annotated<Vec<String>>, Error> {
         let response = self.get_raw().await?;
-        Client::deserialize(response).await
+        Client::deserialize_annotated(response).await
     }
 
     /// Execute the query (using HTTP GET) and retrieve the raw response.
     pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET).await
+    }
+}
+
+/// Provides methods to build a query to retrieve label values for a specific
+/// label from Prometheus.
+#[derive(Clone)]
+pub struct LabelValuesQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) label: String,
+    pub(crate) selectors: Vec<Selector>,
+    pub(crate) start: Option<Timestamp>,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> LabelValuesQueryBuilder<C> {
+    pub(crate) fn new(client: C, label: impl std::fmt::Display) -> Self {
+        LabelValuesQueryBuilder {
+            client,
+            label: label.to_string(),
+            selectors: vec![],
+            start: None,
+            end: None,
+            params: vec![],
+            headers: Default::default(),
+        }
+    }
+
+    /// Set series selectors to filter the time series from wich Prometheus
+    /// reads label values from.
+    /// This can be called multiple times to merge the series selectors with
+    /// those that have been set before.<br>
+    /// The selectors are validated using [`NameValidationScheme::Utf8`] before the
+    /// query is sent.
+    pub fn selectors<T>(mut self, selectors: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Borrow<Selector>,
+    {
+        self.selectors
+            .extend(selectors.into_iter().map(|s| s.borrow().clone()));
+        self
+    }
+
+    /// Limit the amount of metadata returned by setting a start time
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// Calling this repeatedly will replace the current setting.
+    pub fn start(mut self, start: impl Into<Timestamp>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    /// Limit the amount of metadata returned by setting an end time
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// Calling this repeatedly will replace the current setting.
+    pub fn end(mut self, end: impl Into<Timestamp>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    /// Include an additional parameter to the request.
+    pub fn query(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    pub(crate) fn into_request(self) -> Result<(C, Request), Error> {
         let mut params = vec![];
 
         if let Some(start) = self.start {
@@ -554,69 +1224,350 @@ impl LabelNamesQueryBuilder {
             params.push(("end", end.to_string()));
         }
 
-        params.extend(self.selectors);
+        params.extend(match_params(&self.selectors)?);
+        params.extend(self.params);
+
+        let path = format!("api/v1/label/{}/values", self.label);
+        Ok((self.client, Request::new(path, params, self.headers)))
+    }
+}
+
+impl LabelValuesQueryBuilder {
+    /// Execute the query (using HTTP GET) and retrieve a collection of
+    /// label values for the given label name.
+    pub async fn get(self) -> Result<Annotated<Vec<String>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response).await
+    }
+
+    /// Execute the query (using HTTP GET) and retrieve a collection of
+    /// label values for the given label name.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request()?;
+        client.execute(request, HttpMethod::GET).await
+    }
+}
+
+/// Provides methods to build a query to the exemplars endpoint and send it to Prometheus.
+#[derive(Clone)]
+pub struct ExemplarsQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) query: String,
+    pub(crate) start: Option<Timestamp>,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> ExemplarsQueryBuilder<C> {
+    pub(crate) fn new(client: C, query: impl std::fmt::Display) -> Self {
+        ExemplarsQueryBuilder {
+            client,
+            query: query.to_string(),
+            start: None,
+            end: None,
+            headers: Default::default(),
+        }
+    }
+
+    /// Limit the exemplars returned by setting a start time
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// Calling this repeatedly will replace the current setting.
+    pub fn start(mut self, start: impl Into<Timestamp>) -> Self {
+        self.start = Some(start.into());
+        self
+    }
+
+    /// Limit the exemplars returned by setting an end time
+    /// (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// Calling this repeatedly will replace the current setting.
+    pub fn end(mut self, end: impl Into<Timestamp>) -> Self {
+        self.end = Some(end.into());
+        self
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    pub(crate) fn into_request(self) -> (C, Request) {
+        let mut params = vec![("query", self.query)];
+
+        if let Some(start) = self.start {
+            params.push(("start", start.to_string()));
+        }
+
+        if let Some(end) = self.end {
+            params.push(("end", end.to_string()));
+        }
+
+        (
+            self.client,
+            Request::new("api/v1/query_exemplars", params, self.headers),
+        )
+    }
+}
+
+impl ExemplarsQueryBuilder {
+    /// Execute the exemplars query (using HTTP GET) and return a collection of
+    /// [`ExemplarSeries`] sent by Prometheus.
+    pub async fn get(self) -> Result<Annotated<Vec<ExemplarSeries>>, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize_annotated(response).await
+    }
+
+    /// Execute the exemplars query (using HTTP GET) and return the raw response
+    /// sent by Prometheus.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
+    }
+}
+
+/// Provides a builder to format a PromQL query using the `format_query` endpoint of Prometheus.
+#[derive(Clone)]
+pub struct FormatQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> FormatQueryBuilder<C> {
+    pub(crate) fn new(client: C, query: impl std::fmt::Display) -> Self {
+        FormatQueryBuilder {
+            client,
+            params: vec![("query", query.to_string())],
+            headers: Default::default(),
+        }
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    pub(crate) fn into_request(self) -> (C, Request) {
+        let request = Request::new("api/v1/format_query", self.params, self.headers);
+        (self.client, request)
+    }
+}
+
+impl FormatQueryBuilder {
+    /// Format the query (using HTTP GET) and return the formatted query.
+    pub async fn get(self) -> Result<String, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize(response).await
+    }
+
+    /// Format the query (using HTTP POST) and return the formatted query.
+    /// Using a POST request is useful in the context of larger PromQL queries when
+    /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
+    /// character limits.
+    pub async fn post(self) -> Result<String, Error> {
+        let response = self.post_raw().await?;
+        Client::deserialize(response).await
+    }
+
+    /// Format the query (using HTTP GET) and return the raw API response.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
+    }
+
+    /// Format the query (using HTTP POST) and return the raw API response.
+    pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::POST).await
+    }
+}
+
+/// Provides a builder to parse a PromQL query into an abstract syntax tree using the
+/// experimental `parse_query` endpoint of Prometheus.
+#[derive(Clone)]
+pub struct ParseQueryBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) params: Vec<(&'static str, String)>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> ParseQueryBuilder<C> {
+    pub(crate) fn new(client: C, query: impl std::fmt::Display) -> Self {
+        ParseQueryBuilder {
+            client,
+            params: vec![("query", query.to_string())],
+            headers: Default::default(),
+        }
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    pub(crate) fn into_request(self) -> (C, Request) {
+        let request = Request::new("api/v1/parse_query", self.params, self.headers);
+        (self.client, request)
+    }
+}
+
+impl ParseQueryBuilder {
+    /// Parse the query (using HTTP GET) and return the root node of the syntax tree.
+    pub async fn get(self) -> Result<AstNode, Error> {
+        let response = self.get_raw().await?;
+        Client::deserialize(response).await
+    }
+
+    /// Parse the query (using HTTP POST) and return the root node of the syntax tree.
+    /// Using a POST request is useful in the context of larger PromQL queries when
+    /// the size of the final URL may break Prometheus' or an intermediate proxies' URL
+    /// character limits.
+    pub async fn post(self) -> Result<AstNode, Error> {
+        let response = self.post_raw().await?;
+        Client::deserialize(response).await
+    }
 
-        self.client
-            .send("api/v1/labels", &params, HttpMethod::GET, None)
+    /// Parse the query (using HTTP GET) and return the raw API response.
+    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::GET).await
+    }
+
+    /// Parse the query (using HTTP POST) and return the raw API response.
+    pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute(request, HttpMethod::POST).await
+    }
+}
+
+/// Provides methods to create a snapshot of the TSDB using the admin API.
+#[derive(Clone)]
+pub struct SnapshotBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) skip_head: Option<bool>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
+}
+
+impl<C> SnapshotBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        SnapshotBuilder {
+            client,
+            skip_head: None,
+            headers: Default::default(),
+        }
+    }
+
+    /// Instruct Prometheus to skip data that is only present in the head block
+    /// and has not yet been compacted to disk.
+    /// Calling this repeatedly will replace the current setting.
+    pub fn skip_head(mut self, skip_head: bool) -> Self {
+        self.skip_head = Some(skip_head);
+        self
+    }
+
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    pub(crate) fn into_request(self) -> (C, Request) {
+        let mut params = vec![];
+
+        if let Some(skip_head) = self.skip_head {
+            params.push(("skip_head", skip_head.to_string()));
+        }
+
+        (
+            self.client,
+            Request::new("api/v1/admin/tsdb/snapshot", params, self.headers),
+        )
+    }
+}
+
+impl SnapshotBuilder {
+    /// Create the snapshot (using HTTP POST) and return the [`Snapshot`] sent
+    /// by Prometheus.
+    pub async fn post(self) -> Result<Snapshot, Error> {
+        let response = self.post_raw().await?;
+        Client::deserialize(response)
             .await
+            .map_err(Client::map_admin_error)
+    }
+
+    /// Create the snapshot (using HTTP POST) and return the raw response sent
+    /// by Prometheus.
+    pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute_once(request, HttpMethod::POST).await
     }
 }
 
-/// Provides methods to build a query to retrieve label values for a specific
-/// label from Prometheus.
+/// Provides methods to delete time series from the TSDB using the admin API.
 #[derive(Clone)]
-pub struct LabelValuesQueryBuilder {
-    client: Client,
-    label: String,
-    selectors: Vec<(&'static str, String)>,
-    start: Option<i64>,
-    end: Option<i64>,
+pub struct DeleteSeriesBuilder<C = Client> {
+    pub(crate) client: C,
+    pub(crate) selectors: Vec<(&'static str, String)>,
+    pub(crate) start: Option<Timestamp>,
+    pub(crate) end: Option<Timestamp>,
+    pub(crate) headers: Option<HeaderMap<HeaderValue>>,
 }
 
-impl LabelValuesQueryBuilder {
-    /// Set series selectors to filter the time series from wich Prometheus
-    /// reads label values from.
-    /// This can be called multiple times to merge the series selectors with
-    /// those that have been set before.
-    pub fn selectors<'a, T>(mut self, selectors: T) -> Self
-    where
-        T: IntoIterator,
-        T::Item: Borrow<Selector<'a>>,
-    {
-        self.selectors.extend(
-            selectors
-                .into_iter()
-                .map(|s| ("match[]", s.borrow().to_string())),
-        );
-        self
+impl<C> DeleteSeriesBuilder<C> {
+    pub(crate) fn new(
+        client: C,
+        selectors: impl IntoIterator<Item = impl Borrow<Selector>>,
+    ) -> Result<Self, Error> {
+        let selectors = match_params(selectors)?;
+
+        if selectors.is_empty() {
+            Err(Error::EmptySeriesSelector)
+        } else {
+            Ok(DeleteSeriesBuilder {
+                client,
+                selectors,
+                start: None,
+                end: None,
+                headers: Default::default(),
+            })
+        }
     }
 
-    /// Limit the amount of metadata returned by setting a start time
-    /// (UNIX timestamp in seconds).
+    /// Only delete data points after this start time (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// If this is not set data points are deleted starting from the earliest possible time.
     /// Calling this repeatedly will replace the current setting.
-    pub fn start(mut self, start: i64) -> Self {
-        self.start = Some(start);
+    pub fn start(mut self, start: impl Into<Timestamp>) -> Self {
+        self.start = Some(start.into());
         self
     }
 
-    /// Limit the amount of metadata returned by setting an end time
-    /// (UNIX timestamp in seconds).
+    /// Only delete data points before this end time (e.g. a Unix timestamp in seconds, see [`Timestamp`]).
+    /// If this is not set data points are deleted up to the latest possible time.
     /// Calling this repeatedly will replace the current setting.
-    pub fn end(mut self, end: i64) -> Self {
-        self.end = Some(end);
+    pub fn end(mut self, end: impl Into<Timestamp>) -> Self {
+        self.end = Some(end.into());
         self
     }
 
-    /// Execute the query (using HTTP GET) and retrieve a collection of
-    /// label values for the given label name.
-    pub async fn get(self) -> Result<Vec<String>, Error> {
-        let response = self.get_raw().await?;
-        Client::deserialize(response).await
+    /// Include an additional header to the request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
     }
 
-    /// Execute the query (using HTTP GET) and retrieve a collection of
-    /// label values for the given label name.
-    pub async fn get_raw(self) -> Result<reqwest::Response, Error> {
+    pub(crate) fn into_request(self) -> (C, Request) {
         let mut params = vec![];
 
         if let Some(start) = self.start {
@@ -629,10 +1580,29 @@ impl LabelValuesQueryBuilder {
 
         params.extend(self.selectors);
 
-        let path = format!("api/v1/label/{}/values", self.label);
-        self.client
-            .send(&path, &params, HttpMethod::GET, None)
+        (
+            self.client,
+            Request::new("api/v1/admin/tsdb/delete_series", params, self.headers),
+        )
+    }
+}
+
+impl DeleteSeriesBuilder {
+    /// Delete the matching time series (using HTTP POST).
+    /// Note that the data is not removed from disk until the next compaction
+    /// or until [`Client::clean_tombstones`] is called.
+    pub async fn post(self) -> Result<(), Error> {
+        let response = self.post_raw().await?;
+        Client::deserialize_empty(response)
             .await
+            .map_err(Client::map_admin_error)
+    }
+
+    /// Delete the matching time series (using HTTP POST) and return the raw
+    /// response sent by Prometheus.
+    pub async fn post_raw(self) -> Result<reqwest::Response, Error> {
+        let (client, request) = self.into_request();
+        client.execute_once(request, HttpMethod::POST).await
     }
 }
 
@@ -645,8 +1615,19 @@ pub struct Client {
     #[cfg(feature = "middleware")]
     pub(crate) client: reqwest_middleware::ClientWithMiddleware,
     pub(crate) base_url: Url,
+    pub(crate) authorization: Option<Authorization>,
+    pub(crate) tenant_header: HeaderName,
+    pub(crate) tenant: Option<HeaderValue>,
+    pub(crate) replicas: Option<Arc<ReplicaSet>>,
+    pub(crate) retry: Option<Arc<RetryPolicy>>,
 }
 
+#[cfg(not(feature = "middleware"))]
+pub(crate) type RequestBuilder = reqwest::RequestBuilder;
+
+#[cfg(feature = "middleware")]
+pub(crate) type RequestBuilder = reqwest_middleware::RequestBuilder;
+
 impl Default for Client {
     /// Create a standard Client that sends requests to "http://127.0.0.1:9090/".
     ///
@@ -664,6 +1645,11 @@ impl Default for Client {
                 .with(EmptyMiddleware::default())
                 .build(),
             base_url: Url::parse("http://127.0.0.1:9090/").unwrap(),
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
         }
     }
 }
@@ -684,6 +1670,11 @@ impl std::str::FromStr for Client {
     fn from_str(url: &str) -> Result<Self, Self::Err> {
         let client = Client {
             base_url: url.to_base_url()?,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
             #[cfg(not(feature = "middleware"))]
             client: reqwest::Client::new(),
             #[cfg(feature = "middleware")]
@@ -711,6 +1702,11 @@ impl std::convert::TryFrom<&str> for Client {
     fn try_from(url: &str) -> Result<Self, Self::Error> {
         let client = Client {
             base_url: url.to_base_url()?,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
             #[cfg(not(feature = "middleware"))]
             client: reqwest::Client::new(),
             #[cfg(feature = "middleware")]
@@ -739,6 +1735,11 @@ impl std::convert::TryFrom<String> for Client {
     fn try_from(url: String) -> Result<Self, Self::Error> {
         let client = Client {
             base_url: url.to_base_url()?,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
             #[cfg(not(feature = "middleware"))]
             client: reqwest::Client::new(),
             #[cfg(feature = "middleware")]
@@ -750,6 +1751,461 @@ impl std::convert::TryFrom<String> for Client {
     }
 }
 
+/// A builder to configure a [`Client`], e.g. with credentials, timeouts or
+/// custom TLS settings. These settings apply to every request that is sent
+/// by the client, including requests to the health and readiness endpoints.
+///
+/// ```rust
+/// use prometheus_http_query::Client;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), prometheus_http_query::Error> {
+///     let client = Client::builder()
+///         .base_url("https://prometheus.example.com")
+///         .basic_auth("user", Some("password"))
+///         .timeout(Duration::from_secs(30))
+///         .connect_timeout(Duration::from_secs(5))
+///         .build()?;
+///
+///     assert_eq!(client.base_url().as_str(), "https://prometheus.example.com/");
+///     Ok(())
+/// }
+/// ```
+pub struct ClientBuilder {
+    base_url: String,
+    headers: HeaderMap<HeaderValue>,
+    credentials: Option<Credentials>,
+    tenant_header: HeaderName,
+    tenants: Vec<String>,
+    replicas: Vec<String>,
+    strategy: Strategy,
+    replica_cooldown: Duration,
+    retry: Option<RetryPolicy>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    no_proxy: bool,
+    #[cfg(any(
+        feature = "default",
+        feature = "default-tls",
+        feature = "native-tls",
+        feature = "native-tls-vendored",
+        feature = "native-tls-alpn",
+        feature = "rustls-tls",
+        feature = "rustls-tls-manual-roots",
+        feature = "rustls-tls-webpki-roots",
+        feature = "rustls-tls-native-roots"
+    ))]
+    root_certificates: Vec<reqwest::Certificate>,
+    #[cfg(any(
+        feature = "native-tls",
+        feature = "native-tls-vendored",
+        feature = "native-tls-alpn",
+        feature = "rustls-tls",
+        feature = "rustls-tls-manual-roots",
+        feature = "rustls-tls-webpki-roots",
+        feature = "rustls-tls-native-roots"
+    ))]
+    identity: Option<reqwest::Identity>,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder::new()
+    }
+}
+
+// Apply the settings of a `ClientBuilder` to either an async or a blocking
+// reqwest client builder as both provide the same set of methods.
+macro_rules! build_http_client {
+    ($settings:expr, $builder:expr) => {{
+        let settings = $settings;
+        let mut builder = $builder.default_headers(settings.headers);
+        if let Some(timeout) = settings.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = settings.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if settings.no_proxy {
+            builder = builder.no_proxy();
+        }
+        for proxy in settings.proxies {
+            builder = builder.proxy(proxy);
+        }
+        #[cfg(any(
+            feature = "default",
+            feature = "default-tls",
+            feature = "native-tls",
+            feature = "native-tls-vendored",
+            feature = "native-tls-alpn",
+            feature = "rustls-tls",
+            feature = "rustls-tls-manual-roots",
+            feature = "rustls-tls-webpki-roots",
+            feature = "rustls-tls-native-roots"
+        ))]
+        for certificate in settings.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        #[cfg(any(
+            feature = "native-tls",
+            feature = "native-tls-vendored",
+            feature = "native-tls-alpn",
+            feature = "rustls-tls",
+            feature = "rustls-tls-manual-roots",
+            feature = "rustls-tls-webpki-roots",
+            feature = "rustls-tls-native-roots"
+        ))]
+        if let Some(identity) = settings.identity {
+            builder = builder.identity(identity);
+        }
+        builder.build().map_err(|source| {
+            Error::Client(ClientError {
+                message: "failed to build HTTP client",
+                source: Some(source),
+            })
+        })
+    }};
+}
+
+impl ClientBuilder {
+    /// Create a new builder with the default settings, i.e. a client that
+    /// sends requests to "http://127.0.0.1:9090/" without credentials.
+    pub fn new() -> Self {
+        ClientBuilder {
+            base_url: String::from("http://127.0.0.1:9090/"),
+            headers: HeaderMap::new(),
+            credentials: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenants: vec![],
+            replicas: vec![],
+            strategy: Strategy::default(),
+            replica_cooldown: DEFAULT_COOLDOWN,
+            retry: None,
+            timeout: None,
+            connect_timeout: None,
+            proxies: vec![],
+            no_proxy: false,
+            #[cfg(any(
+                feature = "default",
+                feature = "default-tls",
+                feature = "native-tls",
+                feature = "native-tls-vendored",
+                feature = "native-tls-alpn",
+                feature = "rustls-tls",
+                feature = "rustls-tls-manual-roots",
+                feature = "rustls-tls-webpki-roots",
+                feature = "rustls-tls-native-roots"
+            ))]
+            root_certificates: vec![],
+            #[cfg(any(
+                feature = "native-tls",
+                feature = "native-tls-vendored",
+                feature = "native-tls-alpn",
+                feature = "rustls-tls",
+                feature = "rustls-tls-manual-roots",
+                feature = "rustls-tls-webpki-roots",
+                feature = "rustls-tls-native-roots"
+            ))]
+            identity: None,
+        }
+    }
+
+    /// Set the base URL of the Prometheus server. Note that the API-specific
+    /// path segments (like `/api/v1/query`) are added automatically.
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into();
+        self
+    }
+
+    /// Authenticate every request using HTTP basic authentication.
+    /// This replaces any other credentials that have been set before.
+    pub fn basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: Option<impl Into<String>>,
+    ) -> Self {
+        self.credentials = Some(Credentials::Basic {
+            username: username.into(),
+            password: password.map(Into::into),
+        });
+        self
+    }
+
+    /// Authenticate every request using a bearer token.
+    /// This replaces any other credentials that have been set before.
+    pub fn bearer_auth(mut self, token: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Bearer(token.into()));
+        self
+    }
+
+    /// Authenticate every request using a bearer token that is read from a file,
+    /// e.g. a Kubernetes service account token. The file is read again whenever
+    /// its modification time changes, so that rotated tokens are picked up
+    /// without creating a new client.
+    /// This replaces any other credentials that have been set before.
+    ///
+    /// The file is read once when the client is built, which fails with
+    /// [`Error::Auth`] if the file cannot be read.
+    pub fn bearer_auth_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.credentials = Some(Credentials::BearerFile(path.into()));
+        self
+    }
+
+    /// Send every request on behalf of the given tenant, as required by multi-tenant
+    /// systems like Cortex, Mimir or Thanos. The tenant ID is sent in the
+    /// `X-Scope-OrgID` header unless another header is set via
+    /// [`ClientBuilder::tenant_header`].
+    /// This replaces any other tenants that have been set before.
+    ///
+    /// Use [`Client::with_tenant`] to send individual requests on behalf of another
+    /// tenant, or set the header on a single request using e.g.
+    /// [`InstantQueryBuilder::header`].
+    ///
+    /// Building the client fails with [`Error::InvalidTenant`] if the tenant ID is
+    /// empty or contains characters that are not allowed.
+    pub fn tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenants = vec![tenant.into()];
+        self
+    }
+
+    /// Query data across several tenants. The tenant IDs are joined by `|`, which
+    /// e.g. Mimir and Cortex interpret as a federated query (given that tenant
+    /// federation is enabled). This replaces any other tenants that have been set before.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    ///
+    /// fn main() -> Result<(), prometheus_http_query::Error> {
+    ///     let client = Client::builder()
+    ///         .base_url("https://mimir.example.com/prometheus")
+    ///         .tenants(["team-a", "team-b"])
+    ///         .build()?;
+    ///
+    ///     assert_eq!(client.tenant(), Some("team-a|team-b"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn tenants<T>(mut self, tenants: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: std::fmt::Display,
+    {
+        self.tenants = tenants.into_iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Set the name of the header that carries the tenant ID, e.g. `THANOS-TENANT`
+    /// for Thanos. Defaults to `X-Scope-OrgID`.
+    pub fn tenant_header(mut self, name: HeaderName) -> Self {
+        self.tenant_header = name;
+        self
+    }
+
+    /// Add a replica of the Prometheus server, e.g. the second instance of an HA pair.
+    /// The base URL of the client is the primary replica. Requests are distributed
+    /// among all replicas according to the [`Strategy`](crate::ha::Strategy), see
+    /// the [`ha`](crate::ha) module for details.
+    /// This can be called repeatedly to add more replicas.
+    pub fn replica(mut self, url: impl Into<String>) -> Self {
+        self.replicas.push(url.into());
+        self
+    }
+
+    /// Set the strategy that determines how requests are distributed among the
+    /// replicas. Defaults to [`Strategy::Failover`](crate::ha::Strategy::Failover).
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Set the period that a replica is considered unhealthy after it could not be
+    /// reached. It is used again once this period has elapsed. Defaults to 30 seconds.
+    pub fn replica_cooldown(mut self, cooldown: Duration) -> Self {
+        self.replica_cooldown = cooldown;
+        self
+    }
+
+    /// Retry requests that failed due to a transient error according to the given
+    /// policy, see [`retry`](crate::retry). By default requests are not retried.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{retry::RetryPolicy, Client};
+    ///
+    /// let client = Client::builder().retry(RetryPolicy::default()).build();
+    ///
+    /// assert!(client.is_ok());
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Include an additional header in every request. Headers that are set
+    /// on individual requests take precedence.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers.append(name, value.into());
+        self
+    }
+
+    /// Set a timeout for each request, from connecting to the server until
+    /// the response body has been received completely.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for establishing a connection to the server.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests via the given proxy. This may be called multiple times to
+    /// add proxies for different schemes.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Do not use any proxies, including the system proxies that are
+    /// configured via environment variables like `HTTPS_PROXY`.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. the certificate of a
+    /// private certificate authority.
+    #[cfg(any(
+        feature = "default",
+        feature = "default-tls",
+        feature = "native-tls",
+        feature = "native-tls-vendored",
+        feature = "native-tls-alpn",
+        feature = "rustls-tls",
+        feature = "rustls-tls-manual-roots",
+        feature = "rustls-tls-webpki-roots",
+        feature = "rustls-tls-native-roots"
+    ))]
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Present a client certificate to the server (mutual TLS).
+    ///
+    /// Requires one of the `native-tls*` or `rustls-tls*` features.
+    #[cfg(any(
+        feature = "native-tls",
+        feature = "native-tls-vendored",
+        feature = "native-tls-alpn",
+        feature = "rustls-tls",
+        feature = "rustls-tls-manual-roots",
+        feature = "rustls-tls-webpki-roots",
+        feature = "rustls-tls-native-roots"
+    ))]
+    pub fn identity(mut self, identity: reqwest::Identity) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Build the [`Client`].
+    ///
+    /// This fails if the base URL is invalid, the credentials cannot be encoded
+    /// as a header value, the bearer token file cannot be read or the
+    /// underlying [`reqwest::Client`] cannot be built.
+    pub fn build(mut self) -> Result<Client, Error> {
+        let Prepared {
+            base_url,
+            authorization,
+            tenant,
+            replicas,
+            retry,
+        } = self.prepare()?;
+        let tenant_header = self.tenant_header.clone();
+        let client = build_http_client!(self, reqwest::Client::builder())?;
+        Ok(Client {
+            #[cfg(not(feature = "middleware"))]
+            client,
+            #[cfg(feature = "middleware")]
+            client: reqwest_middleware::ClientBuilder::new(client)
+                .with(EmptyMiddleware)
+                .build(),
+            base_url,
+            authorization,
+            tenant_header,
+            tenant,
+            replicas,
+            retry,
+        })
+    }
+
+    /// Build a [`blocking::Client`](crate::blocking::Client) using the same settings.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(mut self) -> Result<crate::blocking::Client, Error> {
+        let Prepared {
+            base_url,
+            authorization,
+            tenant,
+            replicas,
+            retry,
+        } = self.prepare()?;
+        let tenant_header = self.tenant_header.clone();
+        let client = build_http_client!(self, reqwest::blocking::Client::builder())?;
+        Ok(crate::blocking::Client {
+            client,
+            base_url,
+            authorization,
+            tenant_header,
+            tenant,
+            replicas,
+            retry,
+        })
+    }
+
+    fn prepare(&mut self) -> Result<Prepared, Error> {
+        let base_url = self.base_url.as_str().to_base_url()?;
+        let authorization = self
+            .credentials
+            .take()
+            .map(Authorization::new)
+            .transpose()?;
+        let tenant = tenant_header_value(&self.tenants)?;
+
+        let replicas = if self.replicas.is_empty() {
+            None
+        } else {
+            let mut base_urls = vec![base_url.clone()];
+            for url in &self.replicas {
+                base_urls.push(url.as_str().to_base_url()?);
+            }
+            Some(Arc::new(ReplicaSet::new(
+                base_urls,
+                self.strategy,
+                self.replica_cooldown,
+            )))
+        };
+
+        Ok(Prepared {
+            base_url,
+            authorization,
+            tenant,
+            replicas,
+            retry: self.retry.take().map(Arc::new),
+        })
+    }
+}
+
+// The settings of a `ClientBuilder` that are shared by the async and blocking client.
+struct Prepared {
+    base_url: Url,
+    authorization: Option<Authorization>,
+    tenant: Option<HeaderValue>,
+    replicas: Option<Arc<ReplicaSet>>,
+    retry: Option<Arc<RetryPolicy>>,
+}
+
 impl Client {
     /// Return a reference to the wrapped [`reqwest::Client`], i.e. to
     /// use it for other requests unrelated to the Prometheus API.
@@ -802,6 +2258,64 @@ impl Client {
         &self.base_url
     }
 
+    /// Return the tenant ID that is sent along with every request, if any.
+    /// Multiple tenants of a federated query are separated by `|`.
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_ref().and_then(|t| t.to_str().ok())
+    }
+
+    /// Return a copy of this client that sends requests on behalf of another
+    /// tenant. The copy shares the connection pool and all other settings with
+    /// this client, so this is cheap enough to be used for individual requests.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    ///
+    /// fn main() -> Result<(), prometheus_http_query::Error> {
+    ///     let client = Client::builder().tenant("team-a").build()?;
+    ///     let other = client.with_tenant("team-b")?;
+    ///
+    ///     assert_eq!(client.tenant(), Some("team-a"));
+    ///     assert_eq!(other.tenant(), Some("team-b"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_tenant(&self, tenant: impl std::fmt::Display) -> Result<Self, Error> {
+        self.with_tenants([tenant])
+    }
+
+    /// Return a copy of this client that queries data across several tenants.
+    /// The tenant IDs are joined by `|`, which e.g. Mimir and Cortex interpret as a
+    /// federated query (given that tenant federation is enabled). An empty set of
+    /// tenants removes the tenant header altogether.
+    pub fn with_tenants<T>(&self, tenants: T) -> Result<Self, Error>
+    where
+        T: IntoIterator,
+        T::Item: std::fmt::Display,
+    {
+        Ok(Self {
+            tenant: tenant_header_value(tenants)?,
+            ..self.clone()
+        })
+    }
+
+    /// Create a [`ClientBuilder`] to configure credentials, timeouts, proxies
+    /// or TLS settings.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    ///
+    /// let client = Client::builder()
+    ///     .base_url("https://prometheus.example.com")
+    ///     .bearer_auth("token")
+    ///     .build();
+    ///
+    /// assert!(client.is_ok());
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
     /// Create a Client from a custom [`reqwest::Client`] and URL.
     /// This way you can account for all extra parameters (e.g. x509 authentication)
     /// that may be needed to connect to Prometheus or an intermediate proxy,
@@ -825,7 +2339,15 @@ impl Client {
     #[cfg(not(feature = "middleware"))]
     pub fn from(client: reqwest::Client, url: &str) -> Result<Self, Error> {
         let base_url = url.to_base_url()?;
-        Ok(Client { base_url, client })
+        Ok(Client {
+            base_url,
+            client,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
+        })
     }
 
     #[cfg(feature = "middleware")]
@@ -834,7 +2356,15 @@ impl Client {
         url: &str,
     ) -> Result<Self, Error> {
         let base_url = url.to_base_url()?;
-        Ok(Client { base_url, client })
+        Ok(Client {
+            base_url,
+            client,
+            authorization: None,
+            tenant_header: DEFAULT_TENANT_HEADER,
+            tenant: None,
+            replicas: None,
+            retry: None,
+        })
     }
 
     /// Build and send the final HTTP request. Parse the result as JSON if the
@@ -847,11 +2377,66 @@ impl Client {
         method: HttpMethod,
         headers: Option<HeaderMap<HeaderValue>>,
     ) -> Result<reqwest::Response, Error> {
-        let url = build_final_url(self.base_url.clone(), path);
+        let Some(policy) = &self.retry else {
+            return self.send_once(path, params, method, headers).await;
+        };
+
+        let mut retries = Retries::new(policy);
+        loop {
+            let result = self
+                .send_once(path, params, method.clone(), headers.clone())
+                .await;
+            let attempt = policy.inspect(result).await;
+            match retries.next_delay(&attempt) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return attempt.result,
+            }
+        }
+    }
+
+    // Send a request once, to the replicas according to the configured strategy if any.
+    async fn send_once<S: Serialize>(
+        &self,
+        path: &str,
+        params: &S,
+        method: HttpMethod,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Result<reqwest::Response, Error> {
+        match &self.replicas {
+            Some(replicas) => {
+                replicas
+                    .send(|base_url| {
+                        self.send_to(
+                            base_url.clone(),
+                            path,
+                            params,
+                            method.clone(),
+                            headers.clone(),
+                        )
+                    })
+                    .await
+            }
+            None => {
+                self.send_to(self.base_url.clone(), path, params, method, headers)
+                    .await
+            }
+        }
+    }
+
+    // Send a request to the Prometheus server (or replica) at the given base URL.
+    async fn send_to<S: Serialize>(
+        &self,
+        base_url: Url,
+        path: &str,
+        params: &S,
+        method: HttpMethod,
+        headers: Option<HeaderMap<HeaderValue>>,
+    ) -> Result<reqwest::Response, Error> {
+        let url = build_final_url(base_url, path);
 
         let mut request = match method {
-            HttpMethod::GET => self.client.get(url).query(params),
-            HttpMethod::POST => self.client.post(url).form(params),
+            HttpMethod::GET => self.request(method, url)?.query(params),
+            HttpMethod::POST => self.request(method, url)?.form(params),
             _ => unreachable!(),
         };
 
@@ -874,6 +2459,39 @@ impl Client {
         Ok(response)
     }
 
+    // Create a request to the given URL that carries the configured credentials.
+    pub(crate) fn request(&self, method: HttpMethod, url: Url) -> Result<RequestBuilder, Error> {
+        let mut request = self.client.request(method, url);
+        if let Some(authorization) = &self.authorization {
+            request = request.header(AUTHORIZATION, authorization.header_value()?);
+        }
+        if let Some(tenant) = &self.tenant {
+            request = request.header(self.tenant_header.clone(), tenant.clone());
+        }
+        Ok(request)
+    }
+
+    // Send a request that has been assembled by one of the builders.
+    async fn execute(
+        &self,
+        request: Request,
+        method: HttpMethod,
+    ) -> Result<reqwest::Response, Error> {
+        self.send(&request.path, &request.params, method, request.headers)
+            .await
+    }
+
+    // Send a request that has been assembled by one of the builders without retrying it,
+    // as the request is not idempotent (e.g. creating a snapshot).
+    async fn execute_once(
+        &self,
+        request: Request,
+        method: HttpMethod,
+    ) -> Result<reqwest::Response, Error> {
+        self.send_once(&request.path, &request.params, method, request.headers)
+            .await
+    }
+
     /// Create an [`InstantQueryBuilder`] from a PromQL query allowing you to set some query parameters
     /// (e.g. evaluation timeout) before finally sending the instant query to the server.
     ///
@@ -902,11 +2520,7 @@ impl Client {
     /// }
     /// ```
     pub fn query(&self, query: impl std::fmt::Display) -> InstantQueryBuilder {
-        InstantQueryBuilder {
-            client: self.clone(),
-            params: vec![("query", query.to_string())],
-            headers: Default::default(),
-        }
+        InstantQueryBuilder::new(self.clone(), query)
     }
 
     /// Create a [`RangeQueryBuilder`] from a PromQL query allowing you to set some query parameters
@@ -914,50 +2528,99 @@ impl Client {
     ///
     /// # Arguments
     /// * `query` - PromQL query to exeute
-    /// * `start` - Start timestamp as Unix timestamp (seconds)
-    /// * `end` - End timestamp as Unix timestamp (seconds)
-    /// * `step` - Query resolution step width as float number of seconds
+    /// * `start` - Start timestamp, e.g. as Unix timestamp (seconds), see [`Timestamp`]
+    /// * `end` - End timestamp, e.g. as Unix timestamp (seconds), see [`Timestamp`]
+    /// * `step` - Query resolution step width as float number of seconds or a duration, see [`Step`]
     ///
     /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#range-queries)
     ///
     /// ```rust
-    /// use prometheus_http_query::Client;
+    /// use prometheus_http_query::{Client, Timestamp};
+    /// use time::Duration;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let q = "prometheus_http_requests_total";
+    ///
+    ///     let response = client.query_range(q, 1648373100, 1648373300, 10.0).get().await?;
+    ///
+    ///     assert!(response.data().as_matrix().is_some());
+    ///
+    ///     // Or make a POST request.
+    ///     let response = client.query_range(q, 1648373100, 1648373300, 10.0).post().await?;
+    ///
+    ///     assert!(response.data().as_matrix().is_some());
+    ///
+    ///     // Or query the last hour using typed time parameters.
+    ///     let start = Timestamp::ago(Duration::hours(1));
+    ///     let end = Timestamp::now();
+    ///
+    ///     let response = client.query_range(q, start, end, Duration::seconds(15)).get().await?;
+    ///
+    ///     assert!(response.data().as_matrix().is_some());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn query_range(
+        &self,
+        query: impl std::fmt::Display,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+        step: impl Into<Step>,
+    ) -> RangeQueryBuilder {
+        RangeQueryBuilder::new(self.clone(), query, start.into(), end.into(), step.into())
+    }
+
+    /// Create a [`RangeQueryBuilder`] like [`Client::query_range`] does, but select the step
+    /// automatically from the time range and the desired [`Resolution`], e.g. a maximum
+    /// number of points per time series or the width of a graph in pixels.
+    /// The start timestamp is aligned to a multiple of the selected step.
+    ///
+    /// # Arguments
+    /// * `query` - PromQL query to exeute
+    /// * `start` - Start timestamp, e.g. as Unix timestamp (seconds), see [`Timestamp`]
+    /// * `end` - End timestamp, e.g. as Unix timestamp (seconds), see [`Timestamp`]
+    /// * `resolution` - Maximum number of points per time series and optional minimum step, see [`Resolution`]
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Resolution, Timestamp};
+    /// use time::Duration;
     ///
     /// #[tokio::main(flavor = "current_thread")]
     /// async fn main() -> Result<(), anyhow::Error> {
     ///     let client = Client::default();
     ///
     ///     let q = "prometheus_http_requests_total";
+    ///     let start = Timestamp::ago(Duration::hours(6));
+    ///     let end = Timestamp::now();
     ///
-    ///     let response = client.query_range(q, 1648373100, 1648373300, 10.0).get().await?;
+    ///     let response = client.query_range_auto(q, start, end, 500).get().await?;
     ///
     ///     assert!(response.data().as_matrix().is_some());
     ///
-    ///     // Or make a POST request.
-    ///     let response = client.query_range(q, 1648373100, 1648373300, 10.0).post().await?;
+    ///     // Render a graph that is 800 pixels wide and never go below the scrape interval.
+    ///     let resolution = Resolution::pixels(800).min_step(Duration::seconds(15));
+    ///
+    ///     let response = client.query_range_auto(q, start, end, resolution).get().await?;
     ///
     ///     assert!(response.data().as_matrix().is_some());
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn query_range(
+    pub fn query_range_auto(
         &self,
         query: impl std::fmt::Display,
-        start: i64,
-        end: i64,
-        step: f64,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+        resolution: impl Into<Resolution>,
     ) -> RangeQueryBuilder {
-        RangeQueryBuilder {
-            client: self.clone(),
-            params: vec![
-                ("query", query.to_string()),
-                ("start", start.to_string()),
-                ("end", end.to_string()),
-                ("step", step.to_string()),
-            ],
-            headers: Default::default(),
-        }
+        let end = end.into();
+        let (start, step) = resolution.into().resolve(start.into(), end);
+        self.query_range(query, start, end, step)
     }
 
     /// Create a [`SeriesQueryBuilder`] to apply filters to a series metadata
@@ -989,26 +2652,12 @@ impl Client {
     ///     Ok(())
     /// }
     /// ```
-    pub fn series<'a, T>(&self, selectors: T) -> Result<SeriesQueryBuilder, Error>
+    pub fn series<T>(&self, selectors: T) -> Result<SeriesQueryBuilder, Error>
     where
         T: IntoIterator,
-        T::Item: Borrow<Selector<'a>>,
+        T::Item: Borrow<Selector>,
     {
-        let selectors: Vec<(&str, String)> = selectors
-            .into_iter()
-            .map(|s| ("match[]", s.borrow().to_string()))
-            .collect();
-
-        if selectors.is_empty() {
-            Err(Error::EmptySeriesSelector)
-        } else {
-            Ok(SeriesQueryBuilder {
-                client: self.clone(),
-                selectors,
-                start: None,
-                end: None,
-            })
-        }
+        SeriesQueryBuilder::new(self.clone(), selectors)
     }
 
     /// Create a [`LabelNamesQueryBuilder`] to apply filters to a query for the label
@@ -1044,12 +2693,7 @@ impl Client {
     /// }
     /// ```
     pub fn label_names(&self) -> LabelNamesQueryBuilder {
-        LabelNamesQueryBuilder {
-            client: self.clone(),
-            selectors: vec![],
-            start: None,
-            end: None,
-        }
+        LabelNamesQueryBuilder::new(self.clone())
     }
 
     /// Create a [`LabelValuesQueryBuilder`] to apply filters to a query for the label
@@ -1084,13 +2728,100 @@ impl Client {
     /// }
     /// ```
     pub fn label_values(&self, label: impl std::fmt::Display) -> LabelValuesQueryBuilder {
-        LabelValuesQueryBuilder {
-            client: self.clone(),
-            label: label.to_string(),
-            selectors: vec![],
-            start: None,
-            end: None,
-        }
+        LabelValuesQueryBuilder::new(self.clone(), label)
+    }
+
+    /// Create an [`ExemplarsQueryBuilder`] to retrieve the exemplars of the time series
+    /// that are selected by a PromQL query.
+    ///
+    /// # Arguments
+    /// * `query` - PromQL query that selects the time series to return exemplars for
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#querying-exemplars)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let q = "prometheus_http_request_duration_seconds_bucket";
+    ///
+    ///     let response = client.exemplars(q).get().await;
+    ///
+    ///     assert!(response.is_ok());
+    ///
+    ///     // Limit the exemplars to a specific time range.
+    ///     let response = client.exemplars(q).start(1648373100).end(1648373300).get().await;
+    ///
+    ///     assert!(response.is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn exemplars(&self, query: impl std::fmt::Display) -> ExemplarsQueryBuilder {
+        ExemplarsQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`FormatQueryBuilder`] to have Prometheus format a PromQL query
+    /// in a canonical way. See [`Expr::pretty`](crate::promql::Expr::pretty) to format
+    /// queries client-side.
+    ///
+    /// # Arguments
+    /// * `query` - PromQL query to format
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#formatting-query-expressions)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let response = client.format_query("foo/bar").get().await?;
+    ///
+    ///     assert_eq!(response, "foo / bar");
+    ///
+    ///     // Or make a POST request.
+    ///     let response = client.format_query("foo/bar").post().await?;
+    ///
+    ///     assert_eq!(response, "foo / bar");
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn format_query(&self, query: impl std::fmt::Display) -> FormatQueryBuilder {
+        FormatQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`ParseQueryBuilder`] to have Prometheus parse a PromQL query into an
+    /// abstract syntax tree. Note that this endpoint is experimental and may change
+    /// in future versions of Prometheus. See [`Expr`](crate::promql::Expr) to parse
+    /// queries client-side.
+    ///
+    /// # Arguments
+    /// * `query` - PromQL query to parse
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#parsing-a-promql-expressions-into-a-abstract-syntax-tree-ast)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{response::AstNode, Client};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let response = client.parse_query("sum(rate(foo[5m]))").get().await?;
+    ///
+    ///     assert!(matches!(response, AstNode::Aggregation(_)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn parse_query(&self, query: impl std::fmt::Display) -> ParseQueryBuilder {
+        ParseQueryBuilder::new(self.clone(), query)
     }
 
     /// Query the current state of target discovery.
@@ -1154,13 +2885,7 @@ impl Client {
     /// }
     /// ```
     pub fn rules(&self) -> RulesQueryBuilder {
-        RulesQueryBuilder {
-            client: self.clone(),
-            kind: None,
-            names: vec![],
-            groups: vec![],
-            files: vec![],
-        }
+        RulesQueryBuilder::new(self.clone())
     }
 
     /// Retrieve a list of active alerts.
@@ -1378,13 +3103,8 @@ impl Client {
     ///     Ok(())
     /// }
     /// ```
-    pub fn target_metadata<'a>(&self) -> TargetMetadataQueryBuilder<'a> {
-        TargetMetadataQueryBuilder {
-            client: self.clone(),
-            match_target: None,
-            metric: None,
-            limit: None,
-        }
+    pub fn target_metadata(&self) -> TargetMetadataQueryBuilder {
+        TargetMetadataQueryBuilder::new(self.clone())
     }
 
     /// Create a [`MetricMetadataQueryBuilder`] to apply filters to a metric metadata
@@ -1423,12 +3143,169 @@ impl Client {
     /// }
     /// ```
     pub fn metric_metadata(&self) -> MetricMetadataQueryBuilder {
-        MetricMetadataQueryBuilder {
-            client: self.clone(),
-            metric: None,
-            limit: None,
-            limit_per_metric: None,
-        }
+        MetricMetadataQueryBuilder::new(self.clone())
+    }
+
+    /// Create a [`SnapshotBuilder`] to create a snapshot of all current TSDB data.
+    /// This requires the admin API to be enabled, otherwise [`Error::AdminApiDisabled`]
+    /// is returned.
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#snapshot)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Error};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let response = client.snapshot().skip_head(true).post().await;
+    ///
+    ///     assert!(response.is_ok() || matches!(response, Err(Error::AdminApiDisabled)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn snapshot(&self) -> SnapshotBuilder {
+        SnapshotBuilder::new(self.clone())
+    }
+
+    /// Create a [`DeleteSeriesBuilder`] to delete data for a selection of time series.
+    /// This requires the admin API to be enabled, otherwise [`Error::AdminApiDisabled`]
+    /// is returned.
+    ///
+    /// # Arguments
+    /// * `selectors` - Iterable container of [`Selector`]s that tells Prometheus which series to delete. Must not be empty!
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#delete-series)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Error, Selector};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let s = Selector::new()
+    ///         .metric("some_metric_that_does_not_exist")
+    ///         .eq("job", "node");
+    ///
+    ///     let response = client
+    ///         .delete_series(&[s])?
+    ///         .start(1648373100)
+    ///         .end(1648373300)
+    ///         .post()
+    ///         .await;
+    ///
+    ///     assert!(response.is_ok() || matches!(response, Err(Error::AdminApiDisabled)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn delete_series<T>(&self, selectors: T) -> Result<DeleteSeriesBuilder, Error>
+    where
+        T: IntoIterator,
+        T::Item: Borrow<Selector>,
+    {
+        DeleteSeriesBuilder::new(self.clone(), selectors)
+    }
+
+    /// Remove deleted data from disk and clean up existing tombstones.
+    /// This requires the admin API to be enabled, otherwise [`Error::AdminApiDisabled`]
+    /// is returned.
+    ///
+    /// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#clean-tombstones)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, Error};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let response = client.clean_tombstones().await;
+    ///
+    ///     assert!(response.is_ok() || matches!(response, Err(Error::AdminApiDisabled)));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn clean_tombstones(&self) -> Result<(), Error> {
+        let response = self
+            .send_once(
+                "api/v1/admin/tsdb/clean_tombstones",
+                &(),
+                HttpMethod::POST,
+                None,
+            )
+            .await?;
+        Client::deserialize_empty(response)
+            .await
+            .map_err(Client::map_admin_error)
+    }
+
+    /// Create a [`RemoteWriteBuilder`](crate::RemoteWriteBuilder) to send time series to a
+    /// remote write receiver using the base URL and the underlying [`reqwest::Client`] of
+    /// this client. Note that Prometheus itself only accepts remote write requests when it
+    /// was started with `--web.enable-remote-write-receiver`.
+    ///
+    /// See also: [Remote write specification](https://prometheus.io/docs/specs/prw/remote_write_spec/)
+    ///
+    /// ```rust,no_run
+    /// use prometheus_http_query::{Client, response::Sample};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let labels = [("__name__", "backfilled_metric"), ("job", "backfill")];
+    ///     let samples = [Sample::new(1648373100.0, 1.0), Sample::new(1648373160.0, 2.0)];
+    ///
+    ///     client
+    ///         .remote_write()
+    ///         .series(labels, samples)
+    ///         .max_samples_per_request(500)
+    ///         .send()
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "remote-write")]
+    pub fn remote_write(&self) -> crate::RemoteWriteBuilder {
+        crate::RemoteWriteBuilder::new(self.clone())
+    }
+
+    /// Create a [`RemoteReadBuilder`](crate::RemoteReadBuilder) to read raw samples from a
+    /// remote read endpoint using the base URL and the underlying [`reqwest::Client`] of this
+    /// client. Samples are returned as they are stored, i.e. without PromQL evaluation.
+    ///
+    /// See also: [Remote read API](https://prometheus.io/docs/prometheus/latest/querying/remote_read_api/)
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{Client, RemoteReadResponseType, Selector};
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let select = Selector::new().metric("up");
+    ///
+    ///     let response = client
+    ///         .remote_read()
+    ///         .query(&select, 1648373100, 1648376700)?
+    ///         .response_type(RemoteReadResponseType::StreamedXorChunks)
+    ///         .post()
+    ///         .await;
+    ///
+    ///     assert!(response.is_ok());
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "remote-read")]
+    pub fn remote_read(&self) -> crate::RemoteReadBuilder {
+        crate::RemoteReadBuilder::new(self.clone())
     }
 
     /// Check Prometheus server health.
@@ -1447,8 +3324,7 @@ impl Client {
     /// ```
     pub async fn is_server_healthy(&self) -> Result<bool, Error> {
         let url = build_final_url(self.base_url.clone(), "-/healthy");
-        self.client
-            .get(url)
+        self.request(HttpMethod::GET, url)?
             .send()
             .await
             .map_err(|source| {
@@ -1488,9 +3364,38 @@ impl Client {
     /// }
     /// ```
     pub async fn is_server_ready(&self) -> Result<bool, Error> {
-        let url = build_final_url(self.base_url.clone(), "-/ready");
-        self.client
-            .get(url)
+        self.check_ready(self.base_url.clone()).await
+    }
+
+    /// Return the replicas of this client and their health as observed by the client,
+    /// see [`ha`](crate::ha). The list is empty unless replicas have been added via
+    /// [`ClientBuilder::replica`].
+    pub fn replicas(&self) -> Vec<ReplicaStatus> {
+        self.replicas
+            .as_ref()
+            .map(|r| r.status())
+            .unwrap_or_default()
+    }
+
+    /// Check the readiness of all replicas concurrently (see [`Client::is_server_ready`]).
+    /// Replicas that are not ready are marked as unhealthy, replicas that are ready
+    /// are re-admitted. Returns the resulting health of all replicas.
+    pub async fn check_replicas(&self) -> Vec<ReplicaStatus> {
+        if let Some(replicas) = &self.replicas {
+            let checks = replicas
+                .base_urls()
+                .map(|base_url| self.check_ready(base_url.clone()));
+            let results = futures_util::future::join_all(checks).await;
+            for (index, result) in results.into_iter().enumerate() {
+                replicas.set_ready(index, result.is_ok());
+            }
+        }
+        self.replicas()
+    }
+
+    async fn check_ready(&self, base_url: Url) -> Result<bool, Error> {
+        let url = build_final_url(base_url, "-/ready");
+        self.request(HttpMethod::GET, url)?
             .send()
             .await
             .map_err(|source| {
@@ -1520,6 +3425,41 @@ impl Client {
     // Internally, the response is deserialized into the [`ApiResponse`] type first.
     // On success, the data is returned as is. On failure, the error is mapped to the appropriate [`Error`] type.
     async fn deserialize<D: DeserializeOwned>(response: reqwest::Response) -> Result<D, Error> {
+        Client::parse(response).await?.into_data()
+    }
+
+    // Like `deserialize`, but keep the warnings and infos of the response.
+    async fn deserialize_annotated<D: DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<Annotated<D>, Error> {
+        Client::parse(response).await?.into_annotated()
+    }
+
+    // Handle responses of endpoints that reply with HTTP 204 (No Content) on success.
+    // Any other response is expected to be a regular JSON-encoded API response.
+    async fn deserialize_empty(response: reqwest::Response) -> Result<(), Error> {
+        if response.status() == StatusCode::NO_CONTENT {
+            return Ok(());
+        }
+        Client::deserialize::<IgnoredAny>(response)
+            .await
+            .map(|_| ())
+    }
+
+    // Map the error that Prometheus returns when the admin API is disabled
+    // to the more specific [`Error::AdminApiDisabled`].
+    pub(crate) fn map_admin_error(error: Error) -> Error {
+        match error {
+            Error::Prometheus(e) if e.is_admin_api_disabled() => Error::AdminApiDisabled,
+            e => e,
+        }
+    }
+
+    // Parse the raw reqwest response into an [`ApiResponse`] after making sure that
+    // the server actually sent a JSON-encoded body.
+    async fn parse<D: DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<ApiResponse<D>, Error> {
         let header = CONTENT_TYPE;
         if !util::is_json(response.headers().get(header)) {
             return Err(Error::Client(ClientError {
@@ -1527,15 +3467,165 @@ impl Client {
                 source: response.error_for_status().err(),
             }));
         }
-        let response = response.json::<ApiResponse<D>>().await.map_err(|source| {
+        response.json::<ApiResponse<D>>().await.map_err(|source| {
             Error::Client(ClientError {
                 message: "failed to parse JSON response from server",
                 source: Some(source),
             })
-        })?;
-        match response {
-            ApiResponse::Success { data } => Ok(data),
+        })
+    }
+
+    // Check the raw reqwest response and deserialize its body incrementally. Error responses
+    // are small enough to be deserialized completely before the stream is created.
+    async fn deserialize_stream<T: DeserializeOwned + Send + 'static>(
+        response: reqwest::Response,
+        layout: Layout,
+        strict: bool,
+    ) -> Result<SeriesStream<T>, Error> {
+        if !util::is_json(response.headers().get(CONTENT_TYPE)) {
+            return Err(Error::Client(ClientError {
+                message: "failed to parse response from server due to invalid media type",
+                source: response.error_for_status().err(),
+            }));
+        }
+
+        if let Err(source) = response.error_for_status_ref() {
+            return match Client::parse::<IgnoredAny>(response).await? {
+                ApiResponse::Error(e) => Err(Error::Prometheus(e)),
+                ApiResponse::Success { .. } => Err(Error::Client(ClientError {
+                    message: "request failed with an unexpected status code",
+                    source: Some(source),
+                })),
+            };
+        }
+
+        Ok(SeriesStream::from_response(response, layout, strict))
+    }
+
+    // Deserialize the raw reqwest response into a [`PromqlResult`] and attach the warnings
+    // and infos that are part of the [`ApiResponse`]. In strict mode the presence of any
+    // warnings is turned into an error.
+    async fn deserialize_promql(
+        response: reqwest::Response,
+        strict: bool,
+    ) -> Result<PromqlResult, Error> {
+        Client::parse::<PromqlResult>(response)
+            .await?
+            .into_promql(strict)
+    }
+}
+
+impl<D> ApiResponse<D> {
+    // Return the data of a successful response or map the error sent by Prometheus
+    // to the appropriate [`Error`] type.
+    pub(crate) fn into_data(self) -> Result<D, Error> {
+        match self {
+            ApiResponse::Success { data, .. } => Ok(data),
+            ApiResponse::Error(e) => Err(Error::Prometheus(e)),
+        }
+    }
+
+    // Return the data of a successful response along with its warnings and infos.
+    pub(crate) fn into_annotated(self) -> Result<Annotated<D>, Error> {
+        match self {
+            ApiResponse::Success {
+                data,
+                warnings,
+                infos,
+            } => Ok(Annotated {
+                data,
+                warnings,
+                infos,
+            }),
+            ApiResponse::Error(e) => Err(Error::Prometheus(e)),
+        }
+    }
+}
+
+impl ApiResponse<PromqlResult> {
+    // Attach the warnings and infos of a successful response to the [`PromqlResult`].
+    // In strict mode the presence of any warnings is turned into an error.
+    pub(crate) fn into_promql(self, strict: bool) -> Result<PromqlResult, Error> {
+        match self {
+            ApiResponse::Success {
+                mut data,
+                warnings,
+                infos,
+            } => {
+                if strict && !warnings.is_empty() {
+                    return Err(Error::Warnings(WarningsError { warnings }));
+                }
+                data.warnings = warnings;
+                data.infos = infos;
+                Ok(data)
+            }
             ApiResponse::Error(e) => Err(Error::Prometheus(e)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(start: i64, end: i64, step: f64, max_points: usize) -> Vec<(i64, i64)> {
+        split_range(start.into(), end.into(), step.into(), max_points)
+            .into_iter()
+            .map(|(start, end)| (start.unix_millis() / 1000, end.unix_millis() / 1000))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_range() {
+        assert_eq!(ranges(0, 100, 10.0, 4), vec![(0, 30), (40, 70), (80, 100)]);
+        assert_eq!(ranges(0, 95, 10.0, 5), vec![(0, 40), (50, 90)]);
+        assert_eq!(ranges(0, 30, 10.0, 4), vec![(0, 30)]);
+        assert_eq!(
+            ranges(0, 30, 10.0, 1),
+            vec![(0, 0), (10, 10), (20, 20), (30, 30)]
+        );
+        assert_eq!(ranges(50, 50, 10.0, 4), vec![(50, 50)]);
+        assert_eq!(ranges(0, 100, 0.0, 4), vec![(0, 100)]);
+    }
+
+    #[test]
+    fn test_selectors_are_validated() {
+        let valid = Selector::new().metric("http.requests").eq("job", "api");
+        let invalid = Selector::new().eq("", "api");
+        let client = Client::default();
+
+        let (_, request) = client
+            .label_names()
+            .selectors([&valid])
+            .into_request()
+            .unwrap();
+        assert_eq!(
+            request.params,
+            vec![(
+                "match[]",
+                String::from(r#"{__name__="http.requests",job="api"}"#)
+            )]
+        );
+
+        let result = client
+            .label_names()
+            .selectors([&valid, &invalid])
+            .into_request();
+        assert!(matches!(result, Err(Error::InvalidName(_))));
+
+        let result = client
+            .label_values("job")
+            .selectors([&invalid])
+            .into_request();
+        assert!(matches!(result, Err(Error::InvalidName(_))));
+
+        let result = client
+            .target_metadata()
+            .match_target(&invalid)
+            .into_request();
+        assert!(matches!(result, Err(Error::InvalidName(_))));
+
+        let result = client.series([&valid, &invalid]);
+        assert!(matches!(result, Err(Error::InvalidName(_))));
+    }
+}
@@ -0,0 +1,379 @@
+// Decodes the labels and samples of a time series into user-defined types via serde.
+use crate::error::{DecodeError, DecodeErrorKind};
+use crate::response::Sample;
+use serde::de::{
+    self, value::SeqDeserializer, DeserializeOwned, DeserializeSeed, Deserializer,
+    IntoDeserializer, MapAccess, Visitor,
+};
+use std::collections::HashMap;
+
+/// The name of the field that receives the sample value of an instant vector
+/// or a scalar when a result is decoded, see [`PromqlResult::decode`](crate::response::PromqlResult::decode).
+pub const VALUE_FIELD: &str = "__value__";
+
+/// The name of the field that receives the sample timestamp (Unix timestamp in seconds)
+/// of an instant vector or a scalar when a result is decoded, see [`PromqlResult::decode`](crate::response::PromqlResult::decode).
+pub const TIMESTAMP_FIELD: &str = "__timestamp__";
+
+/// The name of the field that receives the samples of a range vector as a sequence of
+/// `(timestamp, value)` pairs when a result is decoded, see [`PromqlResult::decode`](crate::response::PromqlResult::decode).
+pub const SAMPLES_FIELD: &str = "__samples__";
+
+// A value that is presented to the target type, either a label value or one
+// of the designated fields.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Value<'a> {
+    Label(&'a str),
+    Float(f64),
+    Samples(&'a [Sample]),
+}
+
+// Decode the labels of a time series and the given designated fields into `T`.
+pub(crate) fn decode<'a, T: DeserializeOwned>(
+    labels: Option<&'a HashMap<String, String>>,
+    fields: impl IntoIterator<Item = (&'a str, Value<'a>)>,
+) -> Result<T, DecodeError> {
+    let entries: Vec<(&'a str, Value<'a>)> = labels
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| (name.as_str(), Value::Label(value.as_str())))
+        .chain(fields)
+        .collect();
+
+    T::deserialize(SeriesDeserializer { entries })
+}
+
+struct SeriesDeserializer<'a> {
+    entries: Vec<(&'a str, Value<'a>)>,
+}
+
+impl<'de, 'a> Deserializer<'de> for SeriesDeserializer<'a> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_map(Entries {
+            iter: self.entries.into_iter(),
+            current: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Entries<'a> {
+    iter: std::vec::IntoIter<(&'a str, Value<'a>)>,
+    current: Option<(&'a str, Value<'a>)>,
+}
+
+impl<'de, 'a> MapAccess<'de> for Entries<'a> {
+    type Error = DecodeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DecodeError> {
+        match self.iter.next() {
+            Some((name, value)) => {
+                self.current = Some((name, value));
+                seed.deserialize(IntoDeserializer::<DecodeError>::into_deserializer(name))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DecodeError> {
+        let (name, value) = self
+            .current
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|error| match (error.kind, value) {
+                (DecodeErrorKind::Other(message), Value::Label(value)) => DecodeError {
+                    kind: DecodeErrorKind::InvalidLabel {
+                        label: name.to_string(),
+                        value: value.to_string(),
+                        message,
+                    },
+                },
+                (DecodeErrorKind::Other(message), _) => DecodeError {
+                    kind: DecodeErrorKind::Other(format!("field '{}': {}", name, message)),
+                },
+                (kind, _) => DecodeError { kind },
+            })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct ValueDeserializer<'a>(Value<'a>);
+
+// Label values are strings, so they are parsed if the target field has another type.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+                match self.0 {
+                    Value::Label(value) => {
+                        visitor.$visit(value.parse().map_err(de::Error::custom)?)
+                    }
+                    _ => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        match self.0 {
+            Value::Label(value) => visitor.visit_str(value),
+            Value::Float(value) => visitor.visit_f64(value),
+            Value::Samples(samples) => {
+                SeqDeserializer::new(samples.iter().copied().map(SampleDeserializer))
+                    .deserialize_any(visitor)
+            }
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DecodeError> {
+        match self.0 {
+            Value::Label(value) => {
+                visitor.visit_enum(IntoDeserializer::<DecodeError>::into_deserializer(value))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+// Presents a sample as a `(timestamp, value)` pair.
+struct SampleDeserializer(Sample);
+
+impl<'de> IntoDeserializer<'de, DecodeError> for SampleDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for SampleDeserializer {
+    type Error = DecodeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DecodeError> {
+        SeqDeserializer::new([self.0.timestamp, self.0.value].into_iter()).deserialize_any(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{DecodeErrorKind, Error};
+    use crate::response::PromqlResult;
+    use serde::Deserialize;
+
+    const VECTOR: &str = r#"
+{
+  "resultType": "vector",
+  "result": [
+    {
+      "metric": { "__name__": "http_requests_total", "code": "200", "method": "get", "canary": "true" },
+      "value": [ 1659268100, "15" ]
+    },
+    {
+      "metric": { "__name__": "http_requests_total", "code": "500", "method": "post" },
+      "value": [ 1659268100.5, "+Inf" ]
+    }
+  ]
+}
+"#;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Method {
+        Get,
+        Post,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Requests {
+        #[serde(rename = "__name__")]
+        name: String,
+        code: u16,
+        method: Method,
+        canary: Option<bool>,
+        #[serde(rename = "__value__")]
+        value: f64,
+        #[serde(rename = "__timestamp__")]
+        timestamp: f64,
+    }
+
+    #[test]
+    fn test_decode_vector() -> Result<(), anyhow::Error> {
+        let result = serde_json::from_str::<PromqlResult>(VECTOR)?;
+        let decoded: Vec<Requests> = result.decode()?;
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].name, "http_requests_total");
+        assert_eq!(decoded[0].code, 200);
+        assert_eq!(decoded[0].method, Method::Get);
+        assert_eq!(decoded[0].canary, Some(true));
+        assert_eq!(decoded[0].value, 15.0);
+        assert_eq!(decoded[0].timestamp, 1659268100.0);
+        assert_eq!(decoded[1].code, 500);
+        assert_eq!(decoded[1].canary, None);
+        assert_eq!(decoded[1].value, f64::INFINITY);
+        assert_eq!(decoded[1].timestamp, 1659268100.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() -> Result<(), anyhow::Error> {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Missing {
+            instance: String,
+        }
+
+        let result = serde_json::from_str::<PromqlResult>(VECTOR)?;
+        match result.decode::<Missing>() {
+            Err(Error::Decode(e)) => {
+                assert_eq!(
+                    e.kind(),
+                    &DecodeErrorKind::MissingLabel("instance".to_string())
+                );
+                assert_eq!(e.to_string(), "missing label 'instance'");
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Invalid {
+            method: u8,
+        }
+
+        match result.decode::<Invalid>() {
+            Err(Error::Decode(e)) => {
+                assert!(matches!(
+                    e.kind(),
+                    DecodeErrorKind::InvalidLabel { label, value, .. } if label == "method" && value == "get"
+                ));
+                assert_eq!(
+                    e.to_string(),
+                    "invalid value 'get' of label 'method': invalid digit found in string"
+                );
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_matrix() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "resultType": "matrix",
+  "result": [
+    {
+      "metric": { "job": "prometheus", "instance": "localhost:9090" },
+      "values": [ [ 1659268100, "1" ], [ 1659268160, "0" ] ]
+    }
+  ]
+}
+"#;
+        #[derive(Debug, Deserialize)]
+        struct Sample {
+            timestamp: f64,
+            value: f64,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Up {
+            job: String,
+            #[serde(rename = "__samples__")]
+            samples: Vec<Sample>,
+        }
+
+        let result = serde_json::from_str::<PromqlResult>(data)?;
+        let decoded: Vec<Up> = result.decode()?;
+        assert_eq!(decoded[0].job, "prometheus");
+        assert_eq!(decoded[0].samples.len(), 2);
+        assert_eq!(decoded[0].samples[1].timestamp, 1659268160.0);
+        assert_eq!(decoded[0].samples[1].value, 0.0);
+
+        let decoded: Vec<std::collections::HashMap<String, serde_json::Value>> = result.decode()?;
+        assert_eq!(decoded[0]["__samples__"][0][1], 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_scalar() -> Result<(), anyhow::Error> {
+        let data = r#"{ "resultType": "scalar", "result": [ 1659268100, "42" ] }"#;
+
+        #[derive(Debug, Deserialize)]
+        struct Scalar {
+            #[serde(rename = "__value__")]
+            value: f64,
+        }
+
+        let result = serde_json::from_str::<PromqlResult>(data)?;
+        let decoded: Vec<Scalar> = result.decode()?;
+        assert_eq!(decoded[0].value, 42.0);
+        Ok(())
+    }
+}
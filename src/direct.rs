@@ -2,7 +2,7 @@ use crate::client::*;
 use crate::error::Error;
 use crate::response::*;
 use crate::selector::Selector;
-use crate::util::TargetState;
+use crate::util::{Step, TargetState, Timestamp};
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -60,9 +60,9 @@ pub fn query(host: &str, query: impl std::fmt::Display) -> Result<InstantQueryBu
 pub fn query_range(
     host: &str,
     query: impl std::fmt::Display,
-    start: i64,
-    end: i64,
-    step: f64,
+    start: impl Into<Timestamp>,
+    end: impl Into<Timestamp>,
+    step: impl Into<Step>,
 ) -> Result<RangeQueryBuilder, Error> {
     Client::from_str(host).map(|c| c.query_range(query, start, end, step))
 }
@@ -87,10 +87,10 @@ pub fn query_range(
 ///     Ok(())
 /// }
 /// ```
-pub fn series<'a, T>(host: &str, selectors: T) -> Result<SeriesQueryBuilder, Error>
+pub fn series<T>(host: &str, selectors: T) -> Result<SeriesQueryBuilder, Error>
 where
     T: IntoIterator,
-    T::Item: Borrow<Selector<'a>>,
+    T::Item: Borrow<Selector>,
 {
     Client::from_str(host).and_then(|c| c.series(selectors))
 }
@@ -137,6 +137,32 @@ pub fn label_values(host: &str, label: &str) -> Result<LabelValuesQueryBuilder,
     Client::from_str(host).map(|c| c.label_values(label))
 }
 
+/// Create an [`ExemplarsQueryBuilder`] to retrieve the exemplars of the time series
+/// that are selected by a PromQL query.
+///
+/// This is just a convenience function for one-off requests, see [`Client::exemplars`].
+///
+/// ```rust
+/// use prometheus_http_query::exemplars;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() -> Result<(), anyhow::Error> {
+///     let q = "prometheus_http_request_duration_seconds_bucket";
+///
+///     let response = exemplars("http://localhost:9090", q)?.get().await;
+///
+///     assert!(response.is_ok());
+///
+///     Ok(())
+/// }
+/// ```
+pub fn exemplars(
+    host: &str,
+    query: impl std::fmt::Display,
+) -> Result<ExemplarsQueryBuilder, Error> {
+    Client::from_str(host).map(|c| c.exemplars(query))
+}
+
 /// Query the current state of target discovery.
 ///
 /// This is just a convenience function for one-off requests, see [`Client::targets`].
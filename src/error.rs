@@ -1,7 +1,9 @@
 //! All error types that are returned by methods in this crate.
+use crate::response::Annotation;
 use serde::Deserialize;
 use std::error::Error as StdError;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 /// A global error enum that contains all errors that are returned by this
 /// library. Some errors are wrappers for errors from underlying libraries.
@@ -20,8 +22,40 @@ pub enum Error {
     /// series [`Selector`](crate::selector::Selector)s. According to the Prometheus API description at least one
     /// [`Selector`](crate::selector::Selector) must be provided.
     EmptySeriesSelector,
+    /// Occurs when a PromQL vector selector is built from a [`Selector`](crate::selector::Selector)
+    /// that does not contain a single matcher that does not match the empty string, e.g. `{}`
+    /// or `{job=""}`, see [`promql::vector`](crate::promql::vector). Prometheus rejects such selectors.
+    EmptyVectorSelector,
     /// Wraps errors from the [`url`] crate.
     ParseUrl(ParseUrlError),
+    /// Occurs when a [`Selector`](crate::selector::Selector) contains a label name or metric
+    /// name that is invalid, see [`Selector::validate`](crate::selector::Selector::validate).
+    InvalidName(InvalidNameError),
+    /// Occurs when one of the TSDB admin endpoints (e.g. [`Client::snapshot`](crate::Client::snapshot))
+    /// is called but Prometheus was not started with `--web.enable-admin-api`.
+    AdminApiDisabled,
+    /// Occurs when a remote write receiver rejects a write request, see
+    /// [`RemoteWriteError::is_retryable`] to decide whether the request may be retried.
+    RemoteWrite(RemoteWriteError),
+    /// Occurs when a remote read endpoint responds to a read request with
+    /// a non-2xx HTTP status code.
+    RemoteRead(RemoteReadError),
+    /// Occurs when a query was executed in strict mode (see e.g. [`InstantQueryBuilder::strict`](crate::InstantQueryBuilder::strict))
+    /// and Prometheus attached warnings to an otherwise successful response.
+    Warnings(WarningsError),
+    /// Occurs when a query is validated locally before it is sent (see e.g.
+    /// [`InstantQueryBuilder::validate`](crate::InstantQueryBuilder::validate))
+    /// and it is not a valid PromQL expression.
+    Parse(ParseError),
+    /// Occurs when a query result cannot be decoded into a user-defined type, see
+    /// [`PromqlResult::decode`](crate::response::PromqlResult::decode).
+    Decode(DecodeError),
+    /// Occurs when the credentials that were configured via the [`ClientBuilder`](crate::ClientBuilder)
+    /// cannot be used, e.g. because the bearer token file cannot be read.
+    Auth(AuthError),
+    /// Occurs when a tenant ID that was configured via [`ClientBuilder::tenant`](crate::ClientBuilder::tenant)
+    /// or [`Client::with_tenant`](crate::Client::with_tenant) is empty or contains invalid characters.
+    InvalidTenant(InvalidTenantError),
 }
 
 impl fmt::Display for Error {
@@ -30,7 +64,17 @@ impl fmt::Display for Error {
             Self::Client(e) => e.fmt(f),
             Self::Prometheus(e) => e.fmt(f),
             Self::EmptySeriesSelector => f.write_str("at least one series selector must be provided in order to query the series endpoint"),
+            Self::EmptyVectorSelector => f.write_str("vector selector must contain at least one non-empty matcher"),
             Self::ParseUrl(e) => e.fmt(f),
+            Self::InvalidName(e) => e.fmt(f),
+            Self::AdminApiDisabled => f.write_str("the Prometheus admin API is disabled, it is enabled via the flag '--web.enable-admin-api'"),
+            Self::RemoteWrite(e) => e.fmt(f),
+            Self::RemoteRead(e) => e.fmt(f),
+            Self::Warnings(e) => e.fmt(f),
+            Self::Parse(e) => e.fmt(f),
+            Self::Decode(e) => e.fmt(f),
+            Self::Auth(e) => e.fmt(f),
+            Self::InvalidTenant(e) => e.fmt(f),
         }
     }
 }
@@ -41,7 +85,17 @@ impl StdError for Error {
             Self::Client(e) => e.source(),
             Self::Prometheus(_) => None,
             Self::EmptySeriesSelector => None,
+            Self::EmptyVectorSelector => None,
             Self::ParseUrl(e) => e.source(),
+            Self::InvalidName(_) => None,
+            Self::AdminApiDisabled => None,
+            Self::RemoteWrite(_) => None,
+            Self::RemoteRead(_) => None,
+            Self::Warnings(_) => None,
+            Self::Parse(_) => None,
+            Self::Decode(_) => None,
+            Self::Auth(e) => e.source(),
+            Self::InvalidTenant(_) => None,
         }
     }
 }
@@ -102,6 +156,12 @@ impl PrometheusError {
     pub fn is_not_found(&self) -> bool {
         self.error_type == PrometheusErrorType::NotFound
     }
+
+    // Prometheus responds to requests to the TSDB admin endpoints with this
+    // specific error if the admin API is not enabled.
+    pub(crate) fn is_admin_api_disabled(&self) -> bool {
+        self.error_type == PrometheusErrorType::Unavailable && self.message == "admin APIs disabled"
+    }
 }
 
 /// The parsed error type as returned by the Prometheus API.
@@ -148,7 +208,7 @@ pub struct ClientError {
 
 impl fmt::Display for ClientError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&self.message)
+        f.write_str(self.message)
     }
 }
 
@@ -177,7 +237,7 @@ pub struct ParseUrlError {
 
 impl fmt::Display for ParseUrlError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&self.message)
+        f.write_str(self.message)
     }
 }
 
@@ -193,3 +253,314 @@ impl ParseUrlError {
         &self.source
     }
 }
+
+/// Is thrown when the credentials of a [`Client`](crate::Client) cannot be used,
+/// e.g. because the bearer token file cannot be read or contains invalid characters.
+#[derive(Debug)]
+pub struct AuthError {
+    pub(crate) message: &'static str,
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) source: Option<std::io::Error>,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", self.message, path.display()),
+            None => f.write_str(self.message),
+        }
+    }
+}
+
+impl StdError for AuthError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.source.as_ref().map(|e| e as &dyn StdError)
+    }
+}
+
+impl AuthError {
+    /// Returns the path of the bearer token file if the error relates to it.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Obtain the [`std::io::Error`] that is the actual cause of this error, if any.
+    pub fn inner(&self) -> Option<&std::io::Error> {
+        self.source.as_ref()
+    }
+}
+
+/// Is thrown when a tenant ID is empty or contains characters that are not allowed,
+/// i.e. the separator `|` of federated tenants or characters that are not valid
+/// in an HTTP header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidTenantError {
+    pub(crate) tenant: String,
+}
+
+impl fmt::Display for InvalidTenantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid tenant ID '{}'", self.tenant)
+    }
+}
+
+impl StdError for InvalidTenantError {}
+
+impl InvalidTenantError {
+    /// Returns the invalid tenant ID.
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+}
+
+/// Is thrown when a query was executed in strict mode and the Prometheus
+/// API response contained one or more warnings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarningsError {
+    pub(crate) warnings: Vec<Annotation>,
+}
+
+impl fmt::Display for WarningsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let warnings: Vec<String> = self.warnings.iter().map(|w| w.to_string()).collect();
+        write!(f, "query returned warnings: {}", warnings.join("; "))
+    }
+}
+
+impl StdError for WarningsError {}
+
+impl WarningsError {
+    /// Returns the warnings that were returned by the Prometheus API.
+    pub fn warnings(&self) -> &[Annotation] {
+        &self.warnings
+    }
+}
+
+/// Is thrown when a label name or metric name is invalid according to the
+/// [`NameValidationScheme`](crate::NameValidationScheme) it was validated against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidNameError {
+    pub(crate) name: String,
+    pub(crate) is_metric_name: bool,
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.is_metric_name {
+            "metric"
+        } else {
+            "label"
+        };
+        write!(f, "invalid {} name '{}'", kind, self.name)
+    }
+}
+
+impl StdError for InvalidNameError {}
+
+impl InvalidNameError {
+    /// Returns the invalid name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Check if the invalid name is a metric name (as opposed to a label name).
+    pub fn is_metric_name(&self) -> bool {
+        self.is_metric_name
+    }
+}
+
+/// Is thrown when a string cannot be parsed, e.g. when parsing a [`Selector`](crate::selector::Selector)
+/// from a string. The error contains the position in the input string where parsing failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub(crate) message: String,
+    pub(crate) position: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: parse error: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl StdError for ParseError {}
+
+impl ParseError {
+    // Create a new error at the given byte offset of the input and derive
+    // the line and column (both starting at 1) from it.
+    pub(crate) fn new(input: &str, position: usize, message: impl Into<String>) -> Self {
+        let before = &input[..position.min(input.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        ParseError {
+            message: message.into(),
+            position,
+            line,
+            column,
+        }
+    }
+
+    /// Returns a description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the byte offset in the input string where the error occurred.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the line (starting at 1) where the error occurred.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns the column (starting at 1, counted in characters) where the error occurred.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+/// Is thrown when the time series of a query result cannot be decoded into a
+/// user-defined type, see [`PromqlResult::decode`](crate::response::PromqlResult::decode).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub(crate) kind: DecodeErrorKind,
+}
+
+/// The reason why a time series cannot be decoded, see [`DecodeError`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    /// The target type requires a label (or designated field, e.g.
+    /// [`VALUE_FIELD`](crate::response::VALUE_FIELD)) that the time series does not have.
+    MissingLabel(String),
+    /// The value of a label cannot be parsed as the type of the corresponding field.
+    InvalidLabel {
+        label: String,
+        value: String,
+        message: String,
+    },
+    /// Any other error that serde reported while decoding, e.g. a type mismatch.
+    Other(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            DecodeErrorKind::MissingLabel(label) => write!(f, "missing label '{}'", label),
+            DecodeErrorKind::InvalidLabel {
+                label,
+                value,
+                message,
+            } => write!(
+                f,
+                "invalid value '{}' of label '{}': {}",
+                value, label, message
+            ),
+            DecodeErrorKind::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl StdError for DecodeError {}
+
+impl serde::de::Error for DecodeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DecodeError {
+            kind: DecodeErrorKind::Other(msg.to_string()),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        DecodeError {
+            kind: DecodeErrorKind::MissingLabel(field.to_string()),
+        }
+    }
+}
+
+impl DecodeError {
+    /// Returns the reason why the time series cannot be decoded.
+    pub fn kind(&self) -> &DecodeErrorKind {
+        &self.kind
+    }
+}
+
+/// Is thrown when a remote write receiver responds to a write request with
+/// a non-2xx HTTP status code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteWriteError {
+    pub(crate) status: reqwest::StatusCode,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for RemoteWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "remote write request failed with status {}: {}",
+            self.status, self.message
+        )
+    }
+}
+
+impl StdError for RemoteWriteError {}
+
+impl RemoteWriteError {
+    /// Returns the HTTP status code that was returned by the receiver.
+    pub fn status(&self) -> reqwest::StatusCode {
+        self.status
+    }
+
+    /// Returns the error message that was returned by the receiver.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Check if the request may be retried. According to the remote write
+    /// specification this is the case for HTTP 5xx and HTTP 429 responses,
+    /// all other responses indicate that the data was rejected permanently.
+    pub fn is_retryable(&self) -> bool {
+        self.status.is_server_error() || self.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    }
+}
+
+/// Is thrown when a remote read endpoint responds to a read request with
+/// a non-2xx HTTP status code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteReadError {
+    pub(crate) status: reqwest::StatusCode,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for RemoteReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "remote read request failed with status {}: {}",
+            self.status, self.message
+        )
+    }
+}
+
+impl StdError for RemoteReadError {}
+
+impl RemoteReadError {
+    /// Returns the HTTP status code that was returned by the server.
+    pub fn status(&self) -> reqwest::StatusCode {
+        self.status
+    }
+
+    /// Returns the error message that was returned by the server.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
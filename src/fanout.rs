@@ -0,0 +1,411 @@
+//! Send the same query to several Prometheus servers and merge the results.
+//!
+//! A [`FanOutClient`] holds a number of named [`Client`]s, e.g. one per cluster or region.
+//! Instant and range queries are built in the same way as with a single client, but they
+//! are sent to all sources concurrently. The resulting time series are merged into a single
+//! [`PromqlResult`], each series is tagged with a label (`source` by default, see
+//! [`FanOutClient::source_label`]) that holds the name of the source it was returned by.
+//!
+//! A source that fails to answer a query (e.g. because it cannot be reached) does not fail
+//! the whole query. Instead, a warning that contains the name of the source and the error
+//! is added to the result, i.e. the result is partial. Warnings and infos sent by the
+//! sources themselves are prefixed with the name of the source, too. Only if all sources
+//! fail, the error of the first source is returned. In strict mode (see
+//! [`InstantQueryBuilder::strict`](crate::InstantQueryBuilder::strict)) any warning,
+//! including partial results, is turned into an error.
+//!
+//! Scalar and string results cannot be tagged with a label, for these the result of the
+//! first source that answered the query is returned. Query statistics are aggregated over
+//! all sources.
+//!
+//! ```rust
+//! use prometheus_http_query::{fanout::FanOutClient, Client};
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() -> Result<(), anyhow::Error> {
+//!     let client = FanOutClient::new()
+//!         .source("eu-1", Client::try_from("http://prometheus.eu-1.example.com:9090")?)
+//!         .source("us-1", Client::try_from("http://prometheus.us-1.example.com:9090")?)
+//!         .source_label("cluster");
+//!
+//!     let response = client.query("up").get().await?;
+//!
+//!     for series in response.data().as_vector().into_iter().flatten() {
+//!         println!("{:?}: {:?}", series.metric().get("cluster"), series.sample());
+//!     }
+//!
+//!     println!("partial results: {:?}", response.warnings());
+//!
+//!     Ok(())
+//! }
+//! ```
+use crate::client::{validate_query, Client};
+use crate::error::{Error, WarningsError};
+use crate::response::{Annotation, Data, PromqlResult, Stats};
+use crate::util::{Step, Timestamp};
+use futures_util::future::join_all;
+use std::collections::HashMap;
+use std::future::Future;
+
+/// A builder for instant queries that are sent by a [`FanOutClient`],
+/// see [`crate::InstantQueryBuilder`].
+pub type InstantQueryBuilder = crate::InstantQueryBuilder<FanOutClient>;
+
+/// A builder for range queries that are sent by a [`FanOutClient`],
+/// see [`crate::RangeQueryBuilder`].
+pub type RangeQueryBuilder = crate::RangeQueryBuilder<FanOutClient>;
+
+/// The label that holds the name of the source of a time series by default.
+pub const DEFAULT_SOURCE_LABEL: &str = "source";
+
+/// A client that sends instant and range queries to a number of named [`Client`]s
+/// concurrently and merges the results, see the [module documentation](self).
+#[derive(Clone)]
+pub struct FanOutClient {
+    sources: Vec<(String, Client)>,
+    source_label: String,
+}
+
+impl Default for FanOutClient {
+    fn default() -> Self {
+        FanOutClient {
+            sources: vec![],
+            source_label: DEFAULT_SOURCE_LABEL.to_string(),
+        }
+    }
+}
+
+impl FanOutClient {
+    /// Create a client without any sources.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::fanout::FanOutClient;
+    ///
+    /// let client = FanOutClient::new();
+    ///
+    /// assert_eq!(client.sources().count(), 0);
+    /// ```
+    pub fn new() -> Self {
+        FanOutClient::default()
+    }
+
+    /// Add a source that queries are sent to. The name is used as the value of the
+    /// source label of every time series that is returned by this source.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{fanout::FanOutClient, Client};
+    ///
+    /// let client = FanOutClient::new()
+    ///     .source("eu-1", Client::default())
+    ///     .source("us-1", Client::default());
+    ///
+    /// assert_eq!(client.sources().collect::<Vec<_>>(), vec!["eu-1", "us-1"]);
+    /// ```
+    pub fn source(mut self, name: impl Into<String>, client: Client) -> Self {
+        self.sources.push((name.into(), client));
+        self
+    }
+
+    /// Set the name of the label that holds the name of the source of a time series
+    /// (default: `source`). If a time series already has a label of this name, its value
+    /// is replaced.
+    pub fn source_label(mut self, label: impl Into<String>) -> Self {
+        self.source_label = label.into();
+        self
+    }
+
+    /// Returns the names of the sources in the order they were added.
+    pub fn sources(&self) -> impl Iterator<Item = &str> {
+        self.sources.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Create an [`InstantQueryBuilder`] from a PromQL query that is sent to all sources,
+    /// see [`Client::query`].
+    pub fn query(&self, query: impl std::fmt::Display) -> InstantQueryBuilder {
+        InstantQueryBuilder::new(self.clone(), query)
+    }
+
+    /// Create a [`RangeQueryBuilder`] from a PromQL query that is sent to all sources,
+    /// see [`Client::query_range`].
+    pub fn query_range(
+        &self,
+        query: impl std::fmt::Display,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+        step: impl Into<Step>,
+    ) -> RangeQueryBuilder {
+        RangeQueryBuilder::new(self.clone(), query, start.into(), end.into(), step.into())
+    }
+
+    // Send a query to all sources concurrently and merge the results. `query` sends the
+    // query to a single source, `empty` is the result if no source returned any data.
+    async fn send<F, Fut>(&self, query: F, empty: Data, strict: bool) -> Result<PromqlResult, Error>
+    where
+        F: Fn(&Client) -> Fut,
+        Fut: Future<Output = Result<PromqlResult, Error>>,
+    {
+        let queries = self.sources.iter().map(|(_, client)| query(client));
+        let results = join_all(queries).await;
+        self.merge(results, empty, strict)
+    }
+
+    // Merge the results of all sources, see the module documentation.
+    fn merge(
+        &self,
+        results: Vec<Result<PromqlResult, Error>>,
+        empty: Data,
+        strict: bool,
+    ) -> Result<PromqlResult, Error> {
+        let mut data: Option<Data> = None;
+        let mut stats: Option<Stats> = None;
+        let mut warnings: Vec<Annotation> = vec![];
+        let mut infos: Vec<Annotation> = vec![];
+        let mut first_error = None;
+        let mut succeeded = false;
+
+        for ((name, _), result) in self.sources.iter().zip(results) {
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    warnings.push(Annotation::from(e.to_string()).with_source(name));
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
+            succeeded = true;
+
+            match (&mut data, result.data) {
+                (None, new) => data = Some(self.tag(name, new)),
+                (Some(Data::Vector(vector)), new @ Data::Vector(_)) => {
+                    if let Data::Vector(new) = self.tag(name, new) {
+                        vector.extend(new);
+                    }
+                }
+                (Some(Data::Matrix(matrix)), new @ Data::Matrix(_)) => {
+                    if let Data::Matrix(new) = self.tag(name, new) {
+                        matrix.extend(new);
+                    }
+                }
+                (Some(Data::Scalar(_)), Data::Scalar(_)) => {}
+                (Some(Data::String(_)), Data::String(_)) => {}
+                (Some(_), new) => warnings.push(
+                    Annotation::from(format!(
+                        "result of type {} cannot be merged with the results of other sources",
+                        result_type(&new)
+                    ))
+                    .with_source(name),
+                ),
+            }
+
+            stats = match (stats, result.stats) {
+                (Some(a), Some(b)) => Some(a.merge(b)),
+                (a, b) => a.or(b),
+            };
+
+            warnings.extend(result.warnings.into_iter().map(|w| w.with_source(name)));
+            infos.extend(result.infos.into_iter().map(|i| i.with_source(name)));
+        }
+
+        if !succeeded {
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+        }
+
+        if strict && !warnings.is_empty() {
+            return Err(Error::Warnings(WarningsError { warnings }));
+        }
+
+        Ok(PromqlResult {
+            data: data.unwrap_or(empty),
+            stats,
+            warnings,
+            infos,
+        })
+    }
+
+    // Set the source label of every time series of a vector or matrix result.
+    fn tag(&self, name: &str, mut data: Data) -> Data {
+        let set_label = |metric: &mut HashMap<String, String>| {
+            metric.insert(self.source_label.clone(), name.to_string());
+        };
+        match &mut data {
+            Data::Vector(vector) => vector.iter_mut().for_each(|s| set_label(&mut s.metric)),
+            Data::Matrix(matrix) => matrix.iter_mut().for_each(|s| set_label(&mut s.metric)),
+            Data::Scalar(_) | Data::String(_) => {}
+        }
+        data
+    }
+}
+
+fn result_type(data: &Data) -> &'static str {
+    match data {
+        Data::Vector(_) => "vector",
+        Data::Matrix(_) => "matrix",
+        Data::Scalar(_) => "scalar",
+        Data::String(_) => "string",
+    }
+}
+
+impl InstantQueryBuilder {
+    /// Execute the instant query (using HTTP GET) on all sources and return the merged results.
+    pub async fn get(self) -> Result<PromqlResult, Error> {
+        self.send(false).await
+    }
+
+    /// Execute the instant query (using HTTP POST) on all sources and return the merged results.
+    pub async fn post(self) -> Result<PromqlResult, Error> {
+        self.send(true).await
+    }
+
+    async fn send(self, post: bool) -> Result<PromqlResult, Error> {
+        validate_query(self.validate, &self.params)?;
+
+        let query = |client: &Client| {
+            let mut query = self.with_client(client.clone());
+            query.strict = false;
+            query.validate = false;
+            async move {
+                match post {
+                    true => query.post().await,
+                    false => query.get().await,
+                }
+            }
+        };
+
+        self.client
+            .send(query, Data::Vector(vec![]), self.strict)
+            .await
+    }
+}
+
+impl RangeQueryBuilder {
+    /// Execute the range query (using HTTP GET) on all sources and return the merged results.
+    pub async fn get(self) -> Result<PromqlResult, Error> {
+        self.send(false).await
+    }
+
+    /// Execute the range query (using HTTP POST) on all sources and return the merged results.
+    pub async fn post(self) -> Result<PromqlResult, Error> {
+        self.send(true).await
+    }
+
+    async fn send(self, post: bool) -> Result<PromqlResult, Error> {
+        validate_query(self.validate, &self.params)?;
+
+        let query = |client: &Client| {
+            let mut query = self.with_client(client.clone());
+            query.strict = false;
+            query.validate = false;
+            async move {
+                match post {
+                    true => query.post().await,
+                    false => query.get().await,
+                }
+            }
+        };
+
+        self.client
+            .send(query, Data::Matrix(vec![]), self.strict)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ClientError;
+
+    fn client() -> FanOutClient {
+        FanOutClient::new()
+            .source("a", Client::default())
+            .source("b", Client::default())
+            .source_label("cluster")
+    }
+
+    fn result(json: &str) -> Result<PromqlResult, Error> {
+        Ok(serde_json::from_str(json).unwrap())
+    }
+
+    fn transport_error() -> Result<PromqlResult, Error> {
+        Err(Error::Client(ClientError {
+            message: "failed to send request to server",
+            source: None,
+        }))
+    }
+
+    const VECTOR: &str = r#"{"resultType": "vector", "result": [{"metric": {"__name__": "up", "cluster": "x"}, "value": [1, "1"]}]}"#;
+
+    #[test]
+    fn test_merge_tags_series() {
+        let merged = client()
+            .merge(
+                vec![result(VECTOR), result(VECTOR)],
+                Data::Vector(vec![]),
+                false,
+            )
+            .unwrap();
+
+        let clusters: Vec<&str> = merged
+            .data()
+            .as_vector()
+            .unwrap()
+            .iter()
+            .map(|s| s.metric()["cluster"].as_str())
+            .collect();
+        assert_eq!(clusters, vec!["a", "b"]);
+        assert!(merged.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_merge_partial_result() {
+        let merged = client()
+            .merge(
+                vec![transport_error(), result(VECTOR)],
+                Data::Vector(vec![]),
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(merged.data().as_vector().unwrap().len(), 1);
+        let warning = &merged.warnings()[0];
+        assert_eq!(merged.warnings().len(), 1);
+        assert_eq!(warning.source(), Some("a"));
+        assert_eq!(warning.as_str(), "failed to send request to server");
+
+        let strict = client().merge(
+            vec![transport_error(), result(VECTOR)],
+            Data::Vector(vec![]),
+            true,
+        );
+        assert!(matches!(strict, Err(Error::Warnings(_))));
+    }
+
+    #[test]
+    fn test_merge_all_failed() {
+        let merged = client().merge(
+            vec![transport_error(), transport_error()],
+            Data::Vector(vec![]),
+            false,
+        );
+        assert!(matches!(merged, Err(Error::Client(_))));
+    }
+
+    #[test]
+    fn test_merge_scalar() {
+        let scalar = r#"{"resultType": "scalar", "result": [1, "2"]}"#;
+        let merged = client()
+            .merge(
+                vec![result(scalar), result(VECTOR)],
+                Data::Vector(vec![]),
+                false,
+            )
+            .unwrap();
+
+        assert!(merged.data().as_scalar().is_some());
+        assert_eq!(merged.warnings().len(), 1);
+        assert!(merged.warnings()[0]
+            .to_string()
+            .starts_with("b: result of type vector"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Send requests to several replicas of a Prometheus server, e.g. an HA pair.
+//!
+//! A [`Client`](crate::Client) that knows about more than one replica (see
+//! [`ClientBuilder::replica`](crate::ClientBuilder::replica)) distributes the requests to the
+//! query and metadata endpoints among the replicas according to a [`Strategy`]. All builders
+//! and methods are used in the same way as with a single server.
+//!
+//! A replica that cannot be reached (i.e. the request fails with a transport error like
+//! a refused connection or a timeout) is marked as unhealthy and the request is sent to
+//! the next replica. Unhealthy replicas are only used as a last resort until they are
+//! re-admitted after a cooldown period (see
+//! [`ClientBuilder::replica_cooldown`](crate::ClientBuilder::replica_cooldown)) or after
+//! they passed a readiness check (see [`Client::check_replicas`](crate::Client::check_replicas)).
+//! Error responses sent by a replica (e.g. due to a syntax error in a query) are returned
+//! as they are.
+//!
+//! Note that requests to the remote write, remote read, health and readiness endpoints
+//! are always sent to the primary replica, i.e. the base URL of the client.
+//!
+//! ```rust
+//! use prometheus_http_query::{ha::Strategy, Client};
+//! use std::time::Duration;
+//!
+//! fn main() -> Result<(), prometheus_http_query::Error> {
+//!     let client = Client::builder()
+//!         .base_url("http://prometheus-0.example.com:9090")
+//!         .replica("http://prometheus-1.example.com:9090")
+//!         .strategy(Strategy::Hedged(Duration::from_millis(200)))
+//!         .build()?;
+//!
+//!     assert_eq!(client.replicas().len(), 2);
+//!     Ok(())
+//! }
+//! ```
+use crate::error::Error;
+use futures_util::future::{self, Either};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// The default period that a replica is considered unhealthy after a failed request.
+pub(crate) const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Determines how requests are distributed among the replicas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    /// Send every request to the primary replica and fail over to the other replicas
+    /// (in the order they were added) when it cannot be reached.
+    #[default]
+    Failover,
+    /// Distribute the requests evenly among all healthy replicas, failing over to the
+    /// next replica when one cannot be reached.
+    RoundRobin,
+    /// Send a request to the primary replica and send the same request to the next
+    /// replica if no response has been received within the given delay. The first
+    /// response wins, the other request is cancelled. This reduces tail latencies at
+    /// the expense of some additional load.
+    ///
+    /// The blocking client does not send requests concurrently and falls back to
+    /// [`Strategy::Failover`].
+    Hedged(Duration),
+}
+
+/// The health of a replica as observed by the client, see [`Client::replicas`](crate::Client::replicas).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaStatus {
+    pub(crate) base_url: Url,
+    pub(crate) healthy: bool,
+}
+
+impl ReplicaStatus {
+    /// Returns the base URL of the replica.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Check if the replica is considered healthy, i.e. it is used to answer requests.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+}
+
+struct Replica {
+    base_url: Url,
+    unhealthy_until: Mutex<Option<Instant>>,
+}
+
+impl Replica {
+    fn unhealthy_until(&self) -> Option<Instant> {
+        *self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn is_healthy(&self, now: Instant) -> bool {
+        !matches!(self.unhealthy_until(), Some(until) if until > now)
+    }
+
+    fn set_unhealthy_until(&self, until: Option<Instant>) {
+        *self
+            .unhealthy_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = until;
+    }
+}
+
+// The replicas of a client and their health, shared by all clones of the client.
+pub(crate) struct ReplicaSet {
+    replicas: Vec<Replica>,
+    strategy: Strategy,
+    cooldown: Duration,
+    next: AtomicUsize,
+}
+
+impl ReplicaSet {
+    pub(crate) fn new(base_urls: Vec<Url>, strategy: Strategy, cooldown: Duration) -> Self {
+        let replicas = base_urls
+            .into_iter()
+            .map(|base_url| Replica {
+                base_url,
+                unhealthy_until: Mutex::new(None),
+            })
+            .collect();
+
+        ReplicaSet {
+            replicas,
+            strategy,
+            cooldown,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn status(&self) -> Vec<ReplicaStatus> {
+        let now = Instant::now();
+        self.replicas
+            .iter()
+            .map(|r| ReplicaStatus {
+                base_url: r.base_url.clone(),
+                healthy: r.is_healthy(now),
+            })
+            .collect()
+    }
+
+    pub(crate) fn base_urls(&self) -> impl Iterator<Item = &Url> {
+        self.replicas.iter().map(|r| &r.base_url)
+    }
+
+    // Record the outcome of a readiness check of the replica at the given position.
+    pub(crate) fn set_ready(&self, index: usize, ready: bool) {
+        let until = (!ready).then(|| Instant::now() + self.cooldown);
+        self.replicas[index].set_unhealthy_until(until);
+    }
+
+    // The order in which the replicas are tried for a single request: healthy replicas
+    // first (rotated for round robin), followed by unhealthy replicas in the order
+    // they are due to be re-admitted.
+    fn order(&self) -> Vec<usize> {
+        let n = self.replicas.len();
+        let start = match self.strategy {
+            Strategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % n,
+            _ => 0,
+        };
+        let now = Instant::now();
+
+        let (mut healthy, mut unhealthy): (Vec<usize>, Vec<usize>) = (0..n)
+            .map(|i| (start + i) % n)
+            .partition(|&i| self.replicas[i].is_healthy(now));
+
+        unhealthy.sort_by_key(|&i| self.replicas[i].unhealthy_until());
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    // Only transport errors indicate that a replica is unavailable. These are the
+    // only client errors that may occur before a response has been received.
+    fn record<T>(&self, index: usize, result: &Result<T, Error>) {
+        match result {
+            Ok(_) => self.replicas[index].set_unhealthy_until(None),
+            Err(Error::Client(_)) => self.set_ready(index, false),
+            Err(_) => {}
+        }
+    }
+
+    async fn attempt<F, Fut, T>(&self, index: usize, send: &F) -> Result<T, Error>
+    where
+        F: Fn(&Url) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let result = send(&self.replicas[index].base_url).await;
+        self.record(index, &result);
+        result
+    }
+
+    // Send a request using the configured strategy. `send` is called with the base URL
+    // of each replica that the request is sent to.
+    pub(crate) async fn send<F, Fut, T>(&self, send: F) -> Result<T, Error>
+    where
+        F: Fn(&Url) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let delay = match self.strategy {
+            Strategy::Hedged(delay) => Some(delay),
+            _ => None,
+        };
+
+        let mut pending = self.order().into_iter();
+        let mut in_flight = FuturesUnordered::new();
+        let mut last_error = None;
+
+        loop {
+            if in_flight.is_empty() {
+                match pending.next() {
+                    Some(index) => in_flight.push(self.attempt(index, &send)),
+                    None => return Err(last_error.expect("at least one replica")),
+                }
+            }
+
+            let completed = match delay {
+                Some(delay) if pending.len() > 0 => {
+                    let timer = pin!(tokio::time::sleep(delay));
+                    match future::select(in_flight.next(), timer).await {
+                        Either::Left((result, _)) => result,
+                        Either::Right(_) => None,
+                    }
+                }
+                _ => in_flight.next().await,
+            };
+
+            match completed {
+                // The hedging delay elapsed, send the request to the next replica as well.
+                None => {
+                    if let Some(index) = pending.next() {
+                        in_flight.push(self.attempt(index, &send));
+                    }
+                }
+                Some(Err(e @ Error::Client(_))) => last_error = Some(e),
+                Some(result) => return result,
+            }
+        }
+    }
+
+    // Send a request to one replica after another until one of them responds.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn send_blocking<F, T>(&self, send: F) -> Result<T, Error>
+    where
+        F: Fn(&Url) -> Result<T, Error>,
+    {
+        let mut last_error = None;
+
+        for index in self.order() {
+            let result = send(&self.replicas[index].base_url);
+            self.record(index, &result);
+            match result {
+                Err(e @ Error::Client(_)) => last_error = Some(e),
+                result => return result,
+            }
+        }
+
+        Err(last_error.expect("at least one replica"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ClientError;
+    use std::cell::RefCell;
+
+    fn replica_set(strategy: Strategy) -> ReplicaSet {
+        let urls = ["http://a:9090", "http://b:9090", "http://c:9090"]
+            .into_iter()
+            .map(|u| Url::parse(u).unwrap())
+            .collect();
+        ReplicaSet::new(urls, strategy, Duration::from_secs(60))
+    }
+
+    fn transport_error() -> Error {
+        Error::Client(ClientError {
+            message: "failed to send request to server",
+            source: None,
+        })
+    }
+
+    #[test]
+    fn test_round_robin_order() {
+        let set = replica_set(Strategy::RoundRobin);
+        assert_eq!(set.order(), vec![0, 1, 2]);
+        assert_eq!(set.order(), vec![1, 2, 0]);
+        assert_eq!(set.order(), vec![2, 0, 1]);
+        assert_eq!(set.order(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_unhealthy_replicas_last() {
+        let set = replica_set(Strategy::Failover);
+        set.set_ready(0, false);
+        assert_eq!(set.order(), vec![1, 2, 0]);
+        assert!(!set.status()[0].is_healthy());
+
+        set.set_ready(0, true);
+        assert_eq!(set.order(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_readmission_after_cooldown() {
+        let set = ReplicaSet::new(
+            vec![Url::parse("http://a:9090").unwrap()],
+            Strategy::Failover,
+            Duration::ZERO,
+        );
+        set.set_ready(0, false);
+        assert!(set.status()[0].is_healthy());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_failover() {
+        let set = replica_set(Strategy::Failover);
+        let attempts = RefCell::new(vec![]);
+
+        let result = set
+            .send(|url| {
+                attempts
+                    .borrow_mut()
+                    .push(url.host_str().unwrap().to_string());
+                let result = match url.host_str() {
+                    Some("a") => Err(transport_error()),
+                    _ => Ok(url.host_str().unwrap().to_string()),
+                };
+                async move { result }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), "b");
+        assert_eq!(*attempts.borrow(), vec!["a", "b"]);
+        assert_eq!(set.order(), vec![1, 2, 0]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_no_failover_on_api_error() {
+        let set = replica_set(Strategy::Failover);
+
+        let result: Result<(), Error> = set
+            .send(|_| async { Err(Error::EmptySeriesSelector) })
+            .await;
+
+        assert!(matches!(result, Err(Error::EmptySeriesSelector)));
+        assert_eq!(set.order(), vec![0, 1, 2]);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_all_replicas_fail() {
+        let set = replica_set(Strategy::RoundRobin);
+
+        let result: Result<(), Error> = set.send(|_| async { Err(transport_error()) }).await;
+
+        assert!(matches!(result, Err(Error::Client(_))));
+        assert!(set.status().iter().all(|r| !r.is_healthy()));
+    }
+}
@@ -0,0 +1,510 @@
+//! A lexer for the PromQL syntax.
+use crate::error::ParseError;
+use std::fmt;
+use time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// A metric name, label name, function name or keyword.
+    Identifier(String),
+    /// A string literal with all escape sequences resolved.
+    String(String),
+    Number(f64),
+    Duration(Duration),
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    /// Only lexed inside brackets, i.e. in subqueries.
+    Colon,
+    At,
+    Eq,
+    Neq,
+    EqRegex,
+    NeqRegex,
+    EqEq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Identifier(i) => write!(f, "identifier \"{}\"", i),
+            Self::String(s) => write!(f, "string {:?}", s),
+            Self::Number(n) => write!(f, "number {}", n),
+            Self::Duration(_) => f.write_str("duration"),
+            Self::LeftBrace => f.write_str("\"{\""),
+            Self::RightBrace => f.write_str("\"}\""),
+            Self::LeftParen => f.write_str("\"(\""),
+            Self::RightParen => f.write_str("\")\""),
+            Self::LeftBracket => f.write_str("\"[\""),
+            Self::RightBracket => f.write_str("\"]\""),
+            Self::Comma => f.write_str("\",\""),
+            Self::Colon => f.write_str("\":\""),
+            Self::At => f.write_str("\"@\""),
+            Self::Eq => f.write_str("\"=\""),
+            Self::Neq => f.write_str("\"!=\""),
+            Self::EqRegex => f.write_str("\"=~\""),
+            Self::NeqRegex => f.write_str("\"!~\""),
+            Self::EqEq => f.write_str("\"==\""),
+            Self::Gt => f.write_str("\">\""),
+            Self::Gte => f.write_str("\">=\""),
+            Self::Lt => f.write_str("\"<\""),
+            Self::Lte => f.write_str("\"<=\""),
+            Self::Add => f.write_str("\"+\""),
+            Self::Sub => f.write_str("\"-\""),
+            Self::Mul => f.write_str("\"*\""),
+            Self::Div => f.write_str("\"/\""),
+            Self::Mod => f.write_str("\"%\""),
+            Self::Pow => f.write_str("\"^\""),
+            Self::Eof => f.write_str("end of input"),
+        }
+    }
+}
+
+/// A token and the byte offset in the input where it starts.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Spanned {
+    pub(crate) token: Token,
+    pub(crate) start: usize,
+}
+
+pub(crate) struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    peeked: Option<Spanned>,
+    // Colons are part of identifiers (metric names) except inside brackets.
+    in_brackets: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Lexer {
+            input,
+            pos: 0,
+            peeked: None,
+            in_brackets: false,
+        }
+    }
+
+    pub(crate) fn error(&self, position: usize, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.input, position, message)
+    }
+
+    pub(crate) fn peek(&mut self) -> Result<&Spanned, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lex()?);
+        }
+        Ok(self.peeked.as_ref().unwrap())
+    }
+
+    pub(crate) fn next(&mut self) -> Result<Spanned, ParseError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lex(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with('#') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn lex(&mut self) -> Result<Spanned, ParseError> {
+        self.skip_whitespace_and_comments();
+
+        let start = self.pos;
+        let mut chars = self.rest().chars();
+
+        let c = match chars.next() {
+            Some(c) => c,
+            None => {
+                return Ok(Spanned {
+                    token: Token::Eof,
+                    start,
+                })
+            }
+        };
+
+        let next = chars.next();
+
+        let (token, len) = match (c, next) {
+            ('{', _) => (Token::LeftBrace, 1),
+            ('}', _) => (Token::RightBrace, 1),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            ('[', _) => {
+                self.in_brackets = true;
+                (Token::LeftBracket, 1)
+            }
+            (']', _) => {
+                self.in_brackets = false;
+                (Token::RightBracket, 1)
+            }
+            (',', _) => (Token::Comma, 1),
+            (':', _) if self.in_brackets => (Token::Colon, 1),
+            ('@', _) => (Token::At, 1),
+            ('=', Some('~')) => (Token::EqRegex, 2),
+            ('=', Some('=')) => (Token::EqEq, 2),
+            ('=', _) => (Token::Eq, 1),
+            ('!', Some('=')) => (Token::Neq, 2),
+            ('!', Some('~')) => (Token::NeqRegex, 2),
+            ('!', _) => return Err(self.error(start, "unexpected character after '!'")),
+            ('>', Some('=')) => (Token::Gte, 2),
+            ('>', _) => (Token::Gt, 1),
+            ('<', Some('=')) => (Token::Lte, 2),
+            ('<', _) => (Token::Lt, 1),
+            ('+', _) => (Token::Add, 1),
+            ('-', _) => (Token::Sub, 1),
+            ('*', _) => (Token::Mul, 1),
+            ('/', _) => (Token::Div, 1),
+            ('%', _) => (Token::Mod, 1),
+            ('^', _) => (Token::Pow, 1),
+            ('"' | '\'' | '`', _) => return self.lex_string(c),
+            ('0'..='9', _) => return self.lex_number_or_duration(),
+            ('.', Some('0'..='9')) => return self.lex_number_or_duration(),
+            (c, _) if is_identifier_start(c) => return Ok(self.lex_identifier()),
+            (c, _) => return Err(self.error(start, format!("unexpected character '{}'", c))),
+        };
+
+        self.pos += len;
+        Ok(Spanned { token, start })
+    }
+
+    fn lex_identifier(&mut self) -> Spanned {
+        let start = self.pos;
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(rest.len());
+        self.pos += len;
+
+        Spanned {
+            token: Token::Identifier(rest[..len].to_string()),
+            start,
+        }
+    }
+
+    // Lex a number (decimal, hexadecimal or in scientific notation) or a duration
+    // like `1h30m`. A duration is a sequence of integers followed by units ordered
+    // from the largest to the smallest unit.
+    fn lex_number_or_duration(&mut self) -> Result<Spanned, ParseError> {
+        let start = self.pos;
+        let rest = self.rest();
+        let bytes = rest.as_bytes();
+        let digits = |from: usize, radix: u32| {
+            from + rest[from..]
+                .find(|c: char| !c.is_digit(radix))
+                .unwrap_or(rest.len() - from)
+        };
+
+        let bad_syntax = |lexer: &Self| lexer.error(start, "bad number or duration syntax");
+
+        let (token, len) = if rest.starts_with("0x") || rest.starts_with("0X") {
+            let len = digits(2, 16);
+            let value = i64::from_str_radix(&rest[2..len], 16).map_err(|_| bad_syntax(self))?;
+            (Token::Number(value as f64), len)
+        } else {
+            let integer = digits(0, 10);
+
+            match duration_unit(&rest[integer..]) {
+                Some(_) if integer > 0 => {
+                    let (duration, len) = lex_duration(rest).ok_or_else(|| bad_syntax(self))?;
+                    (Token::Duration(duration), len)
+                }
+                _ => {
+                    let mut len = integer;
+                    if bytes.get(len) == Some(&b'.') {
+                        len = digits(len + 1, 10);
+                    }
+                    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+                        let mut exp = len + 1;
+                        if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+                            exp += 1;
+                        }
+                        let end = digits(exp, 10);
+                        if end == exp {
+                            return Err(bad_syntax(self));
+                        }
+                        len = end;
+                    }
+                    let value = rest[..len].parse().map_err(|_| bad_syntax(self))?;
+                    (Token::Number(value), len)
+                }
+            }
+        };
+
+        let in_brackets = self.in_brackets;
+        if rest[len..].starts_with(|c: char| {
+            (is_identifier_char(c) && !(c == ':' && in_brackets)) || c == '.'
+        }) {
+            return Err(bad_syntax(self));
+        }
+
+        self.pos += len;
+        Ok(Spanned { token, start })
+    }
+
+    // Lex a string literal enclosed in double quotes, single quotes or backticks.
+    // Escape sequences are resolved like in Go (which PromQL is based on), except
+    // for raw strings enclosed in backticks that may not contain escape sequences.
+    fn lex_string(&mut self, quote: char) -> Result<Spanned, ParseError> {
+        let start = self.pos;
+        let body_start = start + 1;
+        let mut chars = self.input[body_start..].char_indices();
+        let mut buf: Vec<u8> = vec![];
+
+        let unterminated = |lexer: &Self| lexer.error(start, "unterminated quoted string");
+
+        loop {
+            let (i, c) = chars.next().ok_or_else(|| unterminated(self))?;
+            let at = body_start + i;
+
+            match c {
+                c if c == quote => {
+                    self.pos = at + 1;
+                    break;
+                }
+                '\n' if quote != '`' => return Err(unterminated(self)),
+                '\\' if quote != '`' => {
+                    let (_, e) = chars.next().ok_or_else(|| unterminated(self))?;
+
+                    let byte = match e {
+                        'a' => 0x07,
+                        'b' => 0x08,
+                        'f' => 0x0c,
+                        'n' => b'\n',
+                        'r' => b'\r',
+                        't' => b'\t',
+                        'v' => 0x0b,
+                        '\\' => b'\\',
+                        '"' | '\'' if e == quote => e as u8,
+                        'x' => self.lex_escaped_number(&mut chars, at, 2, 16)? as u8,
+                        '0'..='7' => {
+                            let rest = self.lex_escaped_number(&mut chars, at, 2, 8)?;
+                            let value = (e as u32 - '0' as u32) * 64 + rest;
+                            u8::try_from(value).map_err(|_| {
+                                self.error(at, "octal escape value must not exceed 255")
+                            })?
+                        }
+                        'u' | 'U' => {
+                            let digits = if e == 'u' { 4 } else { 8 };
+                            let value = self.lex_escaped_number(&mut chars, at, digits, 16)?;
+                            let c = char::from_u32(value).ok_or_else(|| {
+                                self.error(at, "escape sequence is an invalid Unicode code point")
+                            })?;
+                            buf.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+                            continue;
+                        }
+                        e => {
+                            return Err(self.error(at, format!("unknown escape sequence '\\{}'", e)))
+                        }
+                    };
+                    buf.push(byte);
+                }
+                c => buf.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+
+        let value = String::from_utf8(buf)
+            .map_err(|_| self.error(start, "quoted string is not valid UTF-8"))?;
+
+        Ok(Spanned {
+            token: Token::String(value),
+            start,
+        })
+    }
+
+    fn lex_escaped_number(
+        &self,
+        chars: &mut std::str::CharIndices,
+        at: usize,
+        digits: usize,
+        radix: u32,
+    ) -> Result<u32, ParseError> {
+        let mut value = 0;
+        for _ in 0..digits {
+            let digit = chars
+                .next()
+                .and_then(|(_, c)| c.to_digit(radix))
+                .ok_or_else(|| self.error(at, "invalid escape sequence"))?;
+            value = value * radix + digit;
+        }
+        Ok(value)
+    }
+}
+
+// Returns the duration unit at the start of the input and its length in milliseconds.
+fn duration_unit(input: &str) -> Option<(&'static str, i64)> {
+    const UNITS: [(&str, i64); 7] = [
+        ("ms", 1),
+        ("s", 1000),
+        ("m", 60 * 1000),
+        ("h", 60 * 60 * 1000),
+        ("d", 24 * 60 * 60 * 1000),
+        ("w", 7 * 24 * 60 * 60 * 1000),
+        ("y", 365 * 24 * 60 * 60 * 1000),
+    ];
+
+    UNITS.into_iter().find(|(unit, _)| input.starts_with(unit))
+}
+
+// Lex a duration like `1h30m` and return it along with its length in bytes.
+fn lex_duration(input: &str) -> Option<(Duration, usize)> {
+    let mut millis: i64 = 0;
+    let mut len = 0;
+    let mut last_unit = i64::MAX;
+
+    loop {
+        let rest = &input[len..];
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            break;
+        }
+
+        let (unit, unit_millis) = duration_unit(&rest[digits..])?;
+        if unit_millis >= last_unit {
+            return None;
+        }
+        last_unit = unit_millis;
+
+        let value: i64 = rest[..digits].parse().ok()?;
+        millis = value
+            .checked_mul(unit_millis)
+            .and_then(|v| millis.checked_add(v))?;
+        len += digits + unit.len();
+    }
+
+    Some((Duration::milliseconds(millis), len))
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == ':'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == ':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Result<Vec<Token>, ParseError> {
+        let mut lexer = Lexer::new(input);
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next()?.token;
+            if token == Token::Eof {
+                return Ok(tokens);
+            }
+            tokens.push(token);
+        }
+    }
+
+    #[test]
+    fn test_lex_selector() {
+        let result = tokens("up{job!~\"a.*\", # comment\n\t'x' = `raw\\n`}").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Token::Identifier(String::from("up")),
+                Token::LeftBrace,
+                Token::Identifier(String::from("job")),
+                Token::NeqRegex,
+                Token::String(String::from("a.*")),
+                Token::Comma,
+                Token::String(String::from("x")),
+                Token::Eq,
+                Token::String(String::from("raw\\n")),
+                Token::RightBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_escape_sequences() {
+        let result = tokens(r#""\a\b\f\n\r\t\v\\\"\x41\101ä\U0001F600""#).unwrap();
+        assert_eq!(
+            result,
+            vec![Token::String(String::from(
+                "\u{7}\u{8}\u{c}\n\r\t\u{b}\\\"AAä😀"
+            ))]
+        );
+
+        // Multi-byte UTF-8 characters may also be given as escaped bytes.
+        let result = tokens(r#"'\xc3\xa4\''"#).unwrap();
+        assert_eq!(result, vec![Token::String(String::from("ä'"))]);
+    }
+
+    #[test]
+    fn test_lex_errors() {
+        let err = tokens("{job=\"node}").unwrap_err();
+        assert_eq!(
+            (err.position(), err.message()),
+            (5, "unterminated quoted string")
+        );
+
+        let err = tokens("{job=\"a\nb\"}").unwrap_err();
+        assert_eq!(err.message(), "unterminated quoted string");
+
+        let err = tokens(r#""\'""#).unwrap_err();
+        assert_eq!(
+            (err.position(), err.message()),
+            (1, "unknown escape sequence '\\''")
+        );
+
+        let err = tokens(r#""\xZZ""#).unwrap_err();
+        assert_eq!(err.message(), "invalid escape sequence");
+
+        let err = tokens(r#""\777""#).unwrap_err();
+        assert_eq!(err.message(), "octal escape value must not exceed 255");
+
+        let err = tokens(r#""\uD800""#).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "escape sequence is an invalid Unicode code point"
+        );
+
+        let err = tokens(r#""\xff""#).unwrap_err();
+        assert_eq!(err.message(), "quoted string is not valid UTF-8");
+
+        let err = tokens("up\n  {job ! \"x\"}").unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 8));
+
+        let err = tokens("{ä=\"x\"}").unwrap_err();
+        assert_eq!((err.position(), err.column()), (1, 2));
+        assert_eq!(
+            err.to_string(),
+            "1:2: parse error: unexpected character 'ä'"
+        );
+    }
+}
@@ -23,6 +23,16 @@
 //! use std::convert::TryFrom;
 //! let client = Client::try_from("https://prometheus.example.com").unwrap();
 //!
+//! // Use the ClientBuilder to configure credentials, timeouts, proxies or TLS settings.
+//! // Tokens that are rotated (e.g. Kubernetes service account tokens) can be read from
+//! // a file via `ClientBuilder::bearer_auth_file`.
+//! let client = Client::builder()
+//!     .base_url("https://prometheus.example.com")
+//!     .bearer_auth("secret-token")
+//!     .timeout(std::time::Duration::from_secs(30))
+//!     .build()
+//!     .unwrap();
+//!
 //! // The greatest flexibility is offered by initializing a reqwest::Client first with
 //! // all needed customizations and passing it along.
 //! let client = {
@@ -49,9 +59,8 @@
 //!     let response = client.query(q).get().await?;
 //!     let result = response.data().as_vector().expect("Expected result of type vector");
 //!
-//!     if !result.is_empty() {
-//!         let first = result.first().unwrap();
-//!         println!("Received a total of {} HTTP requests", first.sample().value());
+//!     if let Some(sample) = result.first().and_then(|v| v.sample()) {
+//!         println!("Received a total of {} HTTP requests", sample.value());
 //!     }
 //!
 //!     // HTTP POST is also supported.
@@ -141,7 +150,7 @@
 //!
 //! # Features
 //!
-//! At this point all available feature flags pertain to the [`Client`]s TLS configuration. They enable feature flags of
+//! Most of the available feature flags pertain to the [`Client`]s TLS configuration. They enable feature flags of
 //! the `reqwest` crate by the same name.<br>
 //! See the [reqwest documentation](https://docs.rs/reqwest/0.11.14/reqwest/index.html#optional-features) for details on
 //! these feature flags.<br>
@@ -150,6 +159,12 @@
 //!
 //! `prometheus-http-query = { version = "0.7", default-features = false, features = ["rustls-tls"] }`
 //!
+//! Apart from that the following feature flags enable optional functionality:
+//!
+//! - `remote-write`: Send samples to remote write receivers using [`Client::remote_write`] (remote write 1.0 and 2.0).
+//! - `remote-read`: Read raw samples from remote read endpoints using [`Client::remote_read`] (sampled and streamed responses).
+//! - `blocking`: A synchronous client with the same API and response types, see [`blocking`].
+//!
 //! # Compatibility
 //!
 //! The crate is generally compatible with Prometheus server >=2.30. However individual [`Client`] methods might
@@ -166,20 +181,26 @@
 //! may fail at different stages. In general the following approach is taken to return the most significant
 //! error to the caller:
 //! - When the server's response contains header `Content-Type: application/json` (or variants thereof) the
-//! JSON body is parsed to the target type, regardless of the HTTP status code, since Prometheus returns elaborate
-//! error messages within the HTTP body in any case.
-//! A JSON response having `"status": "success"` is deserialized to the target type of this function and returned
-//! within `Result::Ok`. A response with `"status": "error"` is instead deserialized to a [`error::PrometheusError`]
-//! and returned within `Result::Err`.
+//!   JSON body is parsed to the target type, regardless of the HTTP status code, since Prometheus returns elaborate
+//!   error messages within the HTTP body in any case.
+//!   A JSON response having `"status": "success"` is deserialized to the target type of this function and returned
+//!   within `Result::Ok`. A response with `"status": "error"` is instead deserialized to a [`error::PrometheusError`]
+//!   and returned within `Result::Err`.
 //! - Any other server HTTP 4xx/5xx responses without the proper header indicating a JSON-encoded body are
-//! returned as [`Error::Client`] within `Result::Err`. For example, this may happen when an intermediate proxy server
-//! fails to handle a request and subsequently return a plain text error message and a non-2xx HTTP status code.
+//!   returned as [`Error::Client`] within `Result::Err`. For example, this may happen when an intermediate proxy server
+//!   fails to handle a request and subsequently return a plain text error message and a non-2xx HTTP status code.
+//! - Warnings and infos that Prometheus attaches to a successful query response are available via
+//!   [`response::PromqlResult::warnings`] and [`response::PromqlResult::infos`]. Use [`InstantQueryBuilder::strict`]
+//!   or [`RangeQueryBuilder::strict`] to return [`Error::Warnings`] within `Result::Err` instead. The results of the
+//!   metadata queries (e.g. series, label names and rules) carry them as well, see [`response::Annotated`].
 //!
 //! # Supported operations
 //!
-//! - [x] Execute instant and range queries (GET or POST) and properly parse the results (vector/matrix/scalar)
+//! - [x] Execute instant and range queries (GET or POST) and properly parse the results (vector/matrix/scalar/string)
+//! - [x] Parse native histograms in query results and estimate quantiles/fractions client-side
 //! - [x] Execute series metadata queries
 //! - [x] Execute label metadata queries (names/values)
+//! - [x] Execute exemplar queries
 //! - [x] Retrieve target discovery status
 //! - [x] Retrieve alerting + recording rules
 //! - [x] Retrieve active alerts
@@ -191,27 +212,61 @@
 //! - [x] Prometheus server flags
 //! - [x] Prometheus server build information
 //! - [x] Prometheus server runtime information
+//! - [x] Remote write (requires feature `remote-write`)
+//! - [x] Remote read (requires feature `remote-read`)
+//! - [x] TSDB admin APIs (snapshot, delete series, clean tombstones)
+//! - [x] Build, parse and pretty-print PromQL expressions (see [`promql`])
+//! - [x] Format and parse PromQL expressions server-side
+//! - [x] Stream large query results series by series (see [`SeriesStream`])
+//! - [x] Blocking client (requires feature `blocking`)
+//! - [x] Basic auth, bearer tokens, timeouts, proxies and custom TLS settings (see [`ClientBuilder`])
+//! - [x] Multi-tenancy for Cortex, Mimir and Thanos, including federated queries (see [`ClientBuilder::tenant`])
+//! - [x] Thanos query extensions like deduplication and partial responses (see [`thanos`])
+//! - [x] Failover, round robin and hedged requests across HA replicas (see [`ha`])
+//! - [x] Fan-out queries across many Prometheus servers with merged results (see [`fanout`])
+//! - [x] Retries with exponential backoff that classify Prometheus errors (see [`retry`])
 //! - [ ] Prometheus server config
 //!
 //! # Limitations
 //!
 //! * Some [`Client`] methods may not work with older versions of the Prometheus server.
-//! * The [String](https://prometheus.io/docs/prometheus/latest/querying/api/#strings) result type is not supported
-//! as it is currently not used by Prometheus.
-//! * Warnings contained in an API response will be ignored.
+mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
+mod decode;
 mod direct;
 pub mod error;
+pub mod fanout;
+pub mod ha;
+mod lexer;
+#[cfg(any(feature = "remote-write", feature = "remote-read"))]
+mod prompb;
+pub mod promql;
+#[cfg(feature = "remote-read")]
+mod remote_read;
+#[cfg(feature = "remote-write")]
+mod remote_write;
 pub mod response;
+pub mod retry;
 mod selector;
+mod stream;
+pub mod thanos;
 mod util;
 pub use self::client::{
-    Client, InstantQueryBuilder, LabelNamesQueryBuilder, LabelValuesQueryBuilder,
-    MetricMetadataQueryBuilder, RangeQueryBuilder, RulesQueryBuilder, SeriesQueryBuilder,
-    TargetMetadataQueryBuilder,
+    Client, ClientBuilder, DeleteSeriesBuilder, ExemplarsQueryBuilder, FormatQueryBuilder,
+    InstantQueryBuilder, LabelNamesQueryBuilder, LabelValuesQueryBuilder,
+    MetricMetadataQueryBuilder, ParseQueryBuilder, RangeQueryBuilder, RulesQueryBuilder,
+    SeriesQueryBuilder, SnapshotBuilder, TargetMetadataQueryBuilder,
 };
 pub use self::direct::*;
 pub use self::error::Error;
+#[cfg(feature = "remote-read")]
+pub use self::remote_read::{RemoteReadBuilder, RemoteReadResponseType};
+#[cfg(feature = "remote-write")]
+pub use self::remote_write::{RemoteWriteBuilder, RemoteWriteVersion};
 pub use self::selector::Selector;
+pub use self::stream::SeriesStream;
 pub use self::util::RuleKind;
 pub use self::util::TargetState;
+pub use self::util::{NameValidationScheme, Resolution, Step, Timeout, Timestamp};
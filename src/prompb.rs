@@ -0,0 +1,179 @@
+//! Protocol buffer messages of the Prometheus remote storage protocols.
+//! The definitions are taken from the `prompb` package of the Prometheus
+//! repository and only contain the fields that are used in this crate.
+
+/// `prometheus.WriteRequest` (remote write 1.0).
+#[cfg(feature = "remote-write")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+/// `prometheus.TimeSeries`, native histograms are kept in their encoded form
+/// as they are not supported by the remote storage clients of this crate.
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    pub samples: Vec<Sample>,
+    #[prost(bytes = "vec", repeated, tag = "4")]
+    pub histograms: Vec<Vec<u8>>,
+}
+
+/// `prometheus.Label`
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Label {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// `prometheus.Sample`, the timestamp is given in milliseconds.
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub(crate) struct Sample {
+    #[prost(double, tag = "1")]
+    pub value: f64,
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+}
+
+/// `prometheus.LabelMatcher`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct LabelMatcher {
+    #[prost(enumeration = "MatcherType", tag = "1")]
+    pub r#type: i32,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(string, tag = "3")]
+    pub value: String,
+}
+
+/// `prometheus.LabelMatcher.Type`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum MatcherType {
+    Eq = 0,
+    Neq = 1,
+    Re = 2,
+    Nre = 3,
+}
+
+/// `prometheus.ReadRequest`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ReadRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub queries: Vec<Query>,
+    #[prost(enumeration = "ResponseType", repeated, tag = "2")]
+    pub accepted_response_types: Vec<i32>,
+}
+
+/// `prometheus.ReadRequest.ResponseType`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum ResponseType {
+    Samples = 0,
+    StreamedXorChunks = 1,
+}
+
+/// `prometheus.Query`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Query {
+    #[prost(int64, tag = "1")]
+    pub start_timestamp_ms: i64,
+    #[prost(int64, tag = "2")]
+    pub end_timestamp_ms: i64,
+    #[prost(message, repeated, tag = "3")]
+    pub matchers: Vec<LabelMatcher>,
+}
+
+/// `prometheus.ReadResponse`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ReadResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub results: Vec<QueryResult>,
+}
+
+/// `prometheus.QueryResult`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct QueryResult {
+    #[prost(message, repeated, tag = "1")]
+    pub timeseries: Vec<TimeSeries>,
+}
+
+/// `prometheus.ChunkedReadResponse`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ChunkedReadResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub chunked_series: Vec<ChunkedSeries>,
+    #[prost(int64, tag = "2")]
+    pub query_index: i64,
+}
+
+/// `prometheus.ChunkedSeries`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct ChunkedSeries {
+    #[prost(message, repeated, tag = "1")]
+    pub labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    pub chunks: Vec<Chunk>,
+}
+
+/// `prometheus.Chunk`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, PartialEq, prost::Message)]
+pub(crate) struct Chunk {
+    #[prost(int64, tag = "1")]
+    pub min_time_ms: i64,
+    #[prost(int64, tag = "2")]
+    pub max_time_ms: i64,
+    #[prost(enumeration = "ChunkEncoding", tag = "3")]
+    pub r#type: i32,
+    #[prost(bytes = "vec", tag = "4")]
+    pub data: Vec<u8>,
+}
+
+/// `prometheus.Chunk.Encoding`
+#[cfg(feature = "remote-read")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub(crate) enum ChunkEncoding {
+    Unknown = 0,
+    Xor = 1,
+    Histogram = 2,
+    FloatHistogram = 3,
+}
+
+/// Messages of the `io.prometheus.write.v2` package (remote write 2.0).
+#[cfg(feature = "remote-write")]
+pub(crate) mod v2 {
+    /// `io.prometheus.write.v2.Request`
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(crate) struct Request {
+        #[prost(string, repeated, tag = "4")]
+        pub symbols: Vec<String>,
+        #[prost(message, repeated, tag = "5")]
+        pub timeseries: Vec<TimeSeries>,
+    }
+
+    /// `io.prometheus.write.v2.TimeSeries`, labels are references into
+    /// the symbols table of the request.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(crate) struct TimeSeries {
+        #[prost(uint32, repeated, tag = "1")]
+        pub labels_refs: Vec<u32>,
+        #[prost(message, repeated, tag = "2")]
+        pub samples: Vec<super::Sample>,
+    }
+}
@@ -0,0 +1,1832 @@
+//! A typed representation of PromQL expressions.
+//!
+//! Expressions are built from [`Selector`]s, number literals and the builder functions
+//! of this module and can be passed to e.g. [`Client::query`](crate::Client::query)
+//! as they implement [`std::fmt::Display`]. Formatting an expression always yields
+//! syntactically valid PromQL: string literals are escaped and parentheses are added
+//! wherever the precedence of operators requires them.
+//!
+//! ```rust
+//! use prometheus_http_query::{promql::{self, BinaryOp, Function}, Selector};
+//! use time::Duration;
+//!
+//! let requests = Selector::new()
+//!     .metric("http_requests_total")
+//!     .regex_eq("job", "api.*");
+//!
+//! // topk by (code) (5, sum by (code) (rate(http_requests_total{job=~"api.*"}[5m])))
+//! let rate = promql::call(Function::Rate, [promql::vector(requests)?.range(Duration::minutes(5))]);
+//! let query = promql::topk(5.0, promql::sum(rate).by(["code"])).by(["code"]);
+//!
+//! assert_eq!(
+//!     query.to_string(),
+//!     r#"topk by (code) (5, sum by (code) (rate(http_requests_total{job=~"api.*"}[5m])))"#
+//! );
+//!
+//! // Operators are parenthesized as needed.
+//! let a = promql::Expr::try_from(Selector::new().metric("a"))?;
+//! let b = promql::Expr::try_from(Selector::new().metric("b"))?;
+//! let expr = (a.clone() + b.clone()) * a.binary(BinaryOp::Gt, b).bool();
+//!
+//! assert_eq!(expr.to_string(), "(a + b) * (a > bool b)");
+//!
+//! // Selectors that Prometheus would reject are refused.
+//! assert!(promql::vector(Selector::new()).is_err());
+//! # Ok::<(), prometheus_http_query::Error>(())
+//! ```
+use crate::error::{Error, ParseError};
+use crate::selector::{is_non_empty_matcher, Selector};
+use crate::util::{is_legacy_label_name, is_legacy_metric_name, Label, Quoted, Timestamp};
+use std::fmt;
+use std::str::FromStr;
+use time::Duration;
+
+mod parser;
+
+/// A PromQL expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// A number literal, e.g. `5` or `Inf`.
+    Number(f64),
+    /// A string literal, e.g. `"code"`.
+    String(String),
+    /// An instant vector selector, e.g. `http_requests_total{job="api"}`.
+    Vector(VectorSelector),
+    /// A range vector selector, e.g. `http_requests_total[5m]`.
+    Matrix(MatrixSelector),
+    /// A subquery, e.g. `rate(http_requests_total[5m])[1h:1m]`.
+    Subquery(Subquery),
+    /// A function call, e.g. `rate(http_requests_total[5m])`.
+    Call(Call),
+    /// An aggregation, e.g. `sum by (code) (http_requests_total)`.
+    Aggregate(Aggregate),
+    /// A unary expression, e.g. `-http_requests_total`.
+    Unary(Unary),
+    /// A binary expression, e.g. `a / on (job) b`.
+    Binary(Binary),
+    /// An expression enclosed in parentheses.
+    Paren(Box<Expr>),
+}
+
+impl Expr {
+    /// Create a string literal.
+    pub fn string(value: impl fmt::Display) -> Self {
+        Expr::String(value.to_string())
+    }
+
+    /// Enclose this expression in parentheses.
+    pub fn paren(self) -> Self {
+        Expr::Paren(Box::new(self))
+    }
+
+    /// Combine this expression with another one using a binary operator. Use the methods of
+    /// [`Binary`] to add modifiers like `bool`, `on` or `group_left`.<br>
+    /// Comparisons between two scalars always use the `bool` modifier as Prometheus
+    /// rejects them otherwise.
+    pub fn binary(self, op: BinaryOp, rhs: impl Into<Expr>) -> Binary {
+        let rhs = rhs.into();
+        let return_bool = op.is_comparison()
+            && self.value_type() == ValueType::Scalar
+            && rhs.value_type() == ValueType::Scalar;
+
+        Binary {
+            op,
+            lhs: Box::new(self),
+            rhs: Box::new(rhs),
+            return_bool,
+            matching: None,
+        }
+    }
+
+    /// Create a subquery that evaluates this expression over the given range
+    /// and (optional) resolution step, e.g. `<expr>[1h:1m]`.
+    pub fn subquery(self, range: Duration, step: Option<Duration>) -> Subquery {
+        Subquery {
+            expr: Box::new(self),
+            range,
+            step,
+            offset: None,
+            at: None,
+        }
+    }
+
+    /// Returns the type of the value that this expression evaluates to.
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Self::Number(_) => ValueType::Scalar,
+            Self::String(_) => ValueType::String,
+            Self::Vector(_) => ValueType::Vector,
+            Self::Matrix(_) | Self::Subquery(_) => ValueType::Matrix,
+            Self::Call(c) => c.function.return_type(),
+            Self::Aggregate(_) => ValueType::Vector,
+            Self::Unary(u) => u.expr.value_type(),
+            Self::Binary(b) => match (b.lhs.value_type(), b.rhs.value_type()) {
+                (ValueType::Scalar, ValueType::Scalar) => ValueType::Scalar,
+                _ => ValueType::Vector,
+            },
+            Self::Paren(e) => e.value_type(),
+        }
+    }
+
+    // The precedence of this expression as operand of a binary expression.
+    // Expressions that never need parentheses have the highest precedence.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary(b) => b.op.precedence(),
+            _ if self.is_unary() => UNARY_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+
+    // Negative numbers are printed like unary expressions.
+    fn is_unary(&self) -> bool {
+        match self {
+            Self::Unary(_) => true,
+            Self::Number(n) => n.is_sign_negative() && !n.is_nan(),
+            _ => false,
+        }
+    }
+
+    // Check if a subquery or modifier may directly follow this expression
+    // without enclosing it in parentheses.
+    fn is_atomic(&self) -> bool {
+        match self {
+            Self::Vector(v) => v.offset.is_none() && v.at.is_none(),
+            Self::Call(_) | Self::Aggregate(_) | Self::Paren(_) => true,
+            _ => false,
+        }
+    }
+}
+
+const UNARY_PRECEDENCE: u8 = 5;
+
+/// Parses a PromQL expression and checks the types of all operands like Prometheus does.
+/// Syntax and type errors are returned as [`ParseError`], which contains the position
+/// of the error.
+///
+/// ```rust
+/// use prometheus_http_query::promql::{Expr, ValueType};
+///
+/// let expr: Expr = "sum by (job) (rate(http_requests_total[5m]))".parse()?;
+/// assert_eq!(expr.value_type(), ValueType::Vector);
+///
+/// let err = "rate(http_requests_total)".parse::<Expr>().unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "1:6: parse error: expected type range vector in call to function \"rate\", got instant vector"
+/// );
+/// # Ok::<(), prometheus_http_query::error::ParseError>(())
+/// ```
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parser::Parser::new(s).parse()
+    }
+}
+
+impl From<f64> for Expr {
+    fn from(value: f64) -> Self {
+        Expr::Number(value)
+    }
+}
+
+/// Fails with [`Error::EmptyVectorSelector`] if the [`Selector`] does not contain any
+/// non-empty matcher, see [`vector`].
+impl TryFrom<Selector> for Expr {
+    type Error = Error;
+
+    fn try_from(selector: Selector) -> Result<Self, Self::Error> {
+        vector(selector).map(Expr::Vector)
+    }
+}
+
+macro_rules! impl_from_node {
+    ($($node:ident => $variant:ident),*) => {
+        $(
+            impl From<$node> for Expr {
+                fn from(node: $node) -> Self {
+                    Expr::$variant(node)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_node!(
+    VectorSelector => Vector,
+    MatrixSelector => Matrix,
+    Subquery => Subquery,
+    Call => Call,
+    Aggregate => Aggregate,
+    Unary => Unary,
+    Binary => Binary
+);
+
+macro_rules! impl_arithmetic {
+    ($($trait:ident, $method:ident => $op:ident),*) => {
+        $(
+            impl<T: Into<Expr>> std::ops::$trait<T> for Expr {
+                type Output = Expr;
+
+                fn $method(self, rhs: T) -> Expr {
+                    Expr::Binary(self.binary(BinaryOp::$op, rhs))
+                }
+            }
+        )*
+    };
+}
+
+impl_arithmetic!(
+    Add, add => Add,
+    Sub, sub => Sub,
+    Mul, mul => Mul,
+    Div, div => Div,
+    Rem, rem => Mod
+);
+
+impl std::ops::Neg for Expr {
+    type Output = Expr;
+
+    fn neg(self) -> Expr {
+        Expr::Unary(Unary {
+            op: UnaryOp::Neg,
+            expr: Box::new(self),
+        })
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => fmt_number(*n, f),
+            Self::String(s) => write!(f, "{}", Quoted(s)),
+            Self::Vector(v) => v.fmt(f),
+            Self::Matrix(m) => m.fmt(f),
+            Self::Subquery(s) => s.fmt(f),
+            Self::Call(c) => c.fmt(f),
+            Self::Aggregate(a) => a.fmt(f),
+            Self::Unary(u) => u.fmt(f),
+            Self::Binary(b) => b.fmt(f),
+            Self::Paren(e) => write!(f, "({})", e),
+        }
+    }
+}
+
+/// The type of the value that an expression evaluates to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ValueType {
+    Scalar,
+    String,
+    /// An instant vector.
+    Vector,
+    /// A range vector.
+    Matrix,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Scalar => f.write_str("scalar"),
+            Self::String => f.write_str("string"),
+            Self::Vector => f.write_str("instant vector"),
+            Self::Matrix => f.write_str("range vector"),
+        }
+    }
+}
+
+/// The `@` modifier that sets the evaluation time of a selector or subquery.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum At {
+    /// A fixed point in time, e.g. `@ 1609746000.000`.
+    Timestamp(Timestamp),
+    /// The start of the range query (`@ start()`).
+    Start,
+    /// The end of the range query (`@ end()`).
+    End,
+}
+
+impl fmt::Display for At {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timestamp(t) => {
+                let millis = t.unix_millis();
+                let sign = if millis < 0 { "-" } else { "" };
+                let abs = millis.unsigned_abs();
+                write!(f, "@ {}{}.{:03}", sign, abs / 1000, abs % 1000)
+            }
+            Self::Start => f.write_str("@ start()"),
+            Self::End => f.write_str("@ end()"),
+        }
+    }
+}
+
+impl<T: Into<Timestamp>> From<T> for At {
+    fn from(time: T) -> Self {
+        At::Timestamp(time.into())
+    }
+}
+
+fn fmt_modifiers(
+    at: &Option<At>,
+    offset: &Option<Duration>,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    if let Some(at) = at {
+        write!(f, " {}", at)?;
+    }
+    if let Some(offset) = offset {
+        write!(f, " offset {}", PromqlDuration(*offset))?;
+    }
+    Ok(())
+}
+
+/// An instant vector selector, i.e. a [`Selector`] with optional `offset` and `@` modifiers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorSelector {
+    pub(crate) selector: Selector,
+    pub(crate) offset: Option<Duration>,
+    pub(crate) at: Option<At>,
+}
+
+impl VectorSelector {
+    // The caller must make sure that the selector contains a non-empty matcher.
+    pub(crate) fn new(selector: Selector) -> Self {
+        VectorSelector {
+            selector,
+            offset: None,
+            at: None,
+        }
+    }
+
+    /// Returns the underlying [`Selector`].
+    pub fn selector(&self) -> &Selector {
+        &self.selector
+    }
+
+    /// Returns the offset modifier, if any.
+    pub fn get_offset(&self) -> Option<Duration> {
+        self.offset
+    }
+
+    /// Returns the `@` modifier, if any.
+    pub fn get_at(&self) -> Option<At> {
+        self.at
+    }
+
+    /// Shift the evaluation time by the given duration into the past
+    /// (or into the future if the duration is negative).
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Set the evaluation time using the `@` modifier, e.g. a [`Timestamp`] or [`At::End`].
+    pub fn at(mut self, at: impl Into<At>) -> Self {
+        self.at = Some(at.into());
+        self
+    }
+
+    /// Turn this instant vector selector into a range vector selector
+    /// that selects the given range, e.g. `[5m]`.
+    pub fn range(self, range: Duration) -> MatrixSelector {
+        MatrixSelector {
+            vector: self,
+            range,
+        }
+    }
+}
+
+impl fmt::Display for VectorSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_selector(&self.selector, f)?;
+        fmt_modifiers(&self.at, &self.offset, f)
+    }
+}
+
+// Put the metric name in front of the braces where possible, e.g. `up{job="node"}`
+// instead of `{__name__="up",job="node"}`.
+fn fmt_selector(selector: &Selector, f: &mut fmt::Formatter) -> fmt::Result {
+    let (metric, rest) = match selector.labels.split_first() {
+        Some((Label::Equal((name, value)), rest))
+            if name == "__name__"
+                && is_legacy_metric_name(value)
+                && !is_keyword(value)
+                && rest.iter().all(|l| l.name() != "__name__") =>
+        {
+            (value, rest)
+        }
+        _ => return write!(f, "{}", selector),
+    };
+
+    f.write_str(metric)?;
+
+    if !rest.is_empty() {
+        let matchers: Vec<String> = rest.iter().map(|l| l.to_string()).collect();
+        write!(f, "{{{}}}", matchers.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// A range vector selector, e.g. `http_requests_total[5m]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixSelector {
+    pub(crate) vector: VectorSelector,
+    pub(crate) range: Duration,
+}
+
+impl MatrixSelector {
+    /// Returns the underlying instant vector selector including its modifiers.
+    pub fn vector(&self) -> &VectorSelector {
+        &self.vector
+    }
+
+    /// Returns the selected range.
+    pub fn range(&self) -> Duration {
+        self.range
+    }
+
+    /// Shift the evaluation time by the given duration into the past
+    /// (or into the future if the duration is negative).
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.vector.offset = Some(offset);
+        self
+    }
+
+    /// Set the evaluation time using the `@` modifier, e.g. a [`Timestamp`] or [`At::End`].
+    pub fn at(mut self, at: impl Into<At>) -> Self {
+        self.vector.at = Some(at.into());
+        self
+    }
+}
+
+impl fmt::Display for MatrixSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_selector(&self.vector.selector, f)?;
+        write!(f, "[{}]", PromqlDuration(self.range))?;
+        fmt_modifiers(&self.vector.at, &self.vector.offset, f)
+    }
+}
+
+/// A subquery, e.g. `rate(http_requests_total[5m])[1h:1m]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subquery {
+    pub(crate) expr: Box<Expr>,
+    pub(crate) range: Duration,
+    pub(crate) step: Option<Duration>,
+    pub(crate) offset: Option<Duration>,
+    pub(crate) at: Option<At>,
+}
+
+impl Subquery {
+    /// Returns the expression that is evaluated.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Returns the range of the subquery.
+    pub fn range(&self) -> Duration {
+        self.range
+    }
+
+    /// Returns the resolution step, if any.
+    pub fn step(&self) -> Option<Duration> {
+        self.step
+    }
+
+    /// Returns the offset modifier, if any.
+    pub fn get_offset(&self) -> Option<Duration> {
+        self.offset
+    }
+
+    /// Returns the `@` modifier, if any.
+    pub fn get_at(&self) -> Option<At> {
+        self.at
+    }
+
+    /// Shift the evaluation time by the given duration into the past
+    /// (or into the future if the duration is negative).
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Set the evaluation time using the `@` modifier, e.g. a [`Timestamp`] or [`At::End`].
+    pub fn at(mut self, at: impl Into<At>) -> Self {
+        self.at = Some(at.into());
+        self
+    }
+}
+
+impl Subquery {
+    // Format everything that follows the inner expression, e.g. `[1h:1m] offset 5m`.
+    fn fmt_suffix(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}:", PromqlDuration(self.range))?;
+        if let Some(step) = self.step {
+            write!(f, "{}", PromqlDuration(step))?;
+        }
+        f.write_str("]")?;
+
+        fmt_modifiers(&self.at, &self.offset, f)
+    }
+}
+
+impl fmt::Display for Subquery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_operand(&self.expr, !self.expr.is_atomic(), f)?;
+        self.fmt_suffix(f)
+    }
+}
+
+/// A function call, e.g. `rate(http_requests_total[5m])`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub(crate) function: Function,
+    pub(crate) args: Vec<Expr>,
+}
+
+impl Call {
+    /// Returns the function that is called.
+    pub fn function(&self) -> Function {
+        self.function
+    }
+
+    /// Returns the arguments of the function call.
+    pub fn args(&self) -> &[Expr] {
+        &self.args
+    }
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args: Vec<String> = self.args.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}({})", self.function, args.join(", "))
+    }
+}
+
+/// The grouping clause of an aggregation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Grouping {
+    /// Only keep the given labels (`by`).
+    By(Vec<String>),
+    /// Drop the given labels (`without`).
+    Without(Vec<String>),
+}
+
+/// An aggregation, e.g. `sum by (code) (http_requests_total)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub(crate) op: AggregateOp,
+    pub(crate) expr: Box<Expr>,
+    pub(crate) param: Option<Box<Expr>>,
+    pub(crate) grouping: Option<Grouping>,
+}
+
+impl Aggregate {
+    /// Create an aggregation of the given expression. Note that some aggregation operators
+    /// require a parameter, see [`Aggregate::param`] or use e.g. [`topk`].
+    pub fn new(op: AggregateOp, expr: impl Into<Expr>) -> Self {
+        Aggregate {
+            op,
+            expr: Box::new(expr.into()),
+            param: None,
+            grouping: None,
+        }
+    }
+
+    /// Returns the aggregation operator.
+    pub fn op(&self) -> AggregateOp {
+        self.op
+    }
+
+    /// Returns the aggregated expression.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Returns the parameter of the aggregation (e.g. `k` of `topk`), if any.
+    pub fn get_param(&self) -> Option<&Expr> {
+        self.param.as_deref()
+    }
+
+    /// Returns the grouping clause, if any.
+    pub fn grouping(&self) -> Option<&Grouping> {
+        self.grouping.as_ref()
+    }
+
+    /// Set the parameter of the aggregation, e.g. `k` of `topk` or `φ` of `quantile`.
+    pub fn param(mut self, param: impl Into<Expr>) -> Self {
+        self.param = Some(Box::new(param.into()));
+        self
+    }
+
+    /// Aggregate by the given labels. Calling this repeatedly will replace the current grouping.
+    pub fn by<T>(mut self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.grouping = Some(Grouping::By(to_strings(labels)));
+        self
+    }
+
+    /// Aggregate over all labels except the given ones. Calling this repeatedly will replace
+    /// the current grouping.
+    pub fn without<T>(mut self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.grouping = Some(Grouping::Without(to_strings(labels)));
+        self
+    }
+}
+
+impl Aggregate {
+    // Format the operator and the grouping clause, e.g. `sum by (job) `.
+    fn fmt_op(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+
+        match &self.grouping {
+            Some(Grouping::By(labels)) if !labels.is_empty() => {
+                write!(f, " by ({}) ", LabelList(labels))
+            }
+            Some(Grouping::Without(labels)) => write!(f, " without ({}) ", LabelList(labels)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_op(f)?;
+
+        match &self.param {
+            Some(param) => write!(f, "({}, {})", param, self.expr),
+            None => write!(f, "({})", self.expr),
+        }
+    }
+}
+
+/// A unary operator.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnaryOp {
+    Neg,
+    Pos,
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Neg => f.write_str("-"),
+            Self::Pos => f.write_str("+"),
+        }
+    }
+}
+
+/// A unary expression, e.g. `-http_requests_total`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unary {
+    pub(crate) op: UnaryOp,
+    pub(crate) expr: Box<Expr>,
+}
+
+impl Unary {
+    /// Returns the unary operator.
+    pub fn op(&self) -> UnaryOp {
+        self.op
+    }
+
+    /// Returns the operand.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
+impl Unary {
+    // Unary operators bind weaker than `^` but stronger than all other binary operators.
+    fn operand_paren(&self) -> bool {
+        self.expr.precedence() <= UNARY_PRECEDENCE
+    }
+}
+
+impl fmt::Display for Unary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+        fmt_operand(&self.expr, self.operand_paren(), f)
+    }
+}
+
+/// A binary operator.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Atan2,
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    And,
+    Or,
+    Unless,
+}
+
+impl BinaryOp {
+    /// Check if this is a comparison operator that supports the `bool` modifier.
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Self::Eq | Self::Ne | Self::Gt | Self::Lt | Self::Ge | Self::Le
+        )
+    }
+
+    /// Check if this is a set operator (`and`, `or`, `unless`).
+    pub fn is_set_operator(&self) -> bool {
+        matches!(self, Self::And | Self::Or | Self::Unless)
+    }
+
+    pub(crate) fn precedence(&self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And | Self::Unless => 2,
+            Self::Eq | Self::Ne | Self::Gt | Self::Lt | Self::Ge | Self::Le => 3,
+            Self::Add | Self::Sub => 4,
+            Self::Mul | Self::Div | Self::Mod | Self::Atan2 => 5,
+            Self::Pow => 6,
+        }
+    }
+
+    pub(crate) fn is_right_associative(&self) -> bool {
+        *self == Self::Pow
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Pow => "^",
+            Self::Atan2 => "atan2",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Gt => ">",
+            Self::Lt => "<",
+            Self::Ge => ">=",
+            Self::Le => "<=",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Unless => "unless",
+        };
+        f.write_str(op)
+    }
+}
+
+/// The labels that are used to match series of both sides of a binary expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Matching {
+    /// Only match on the given labels (`on`).
+    On(Vec<String>),
+    /// Match on all labels except the given ones (`ignoring`).
+    Ignoring(Vec<String>),
+}
+
+/// Allows many-to-one and one-to-many matches of a binary expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Group {
+    /// The left-hand side has the higher cardinality (`group_left`).
+    Left(Vec<String>),
+    /// The right-hand side has the higher cardinality (`group_right`).
+    Right(Vec<String>),
+}
+
+/// The vector matching of a binary expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VectorMatching {
+    pub(crate) matching: Matching,
+    pub(crate) group: Option<Group>,
+}
+
+impl VectorMatching {
+    /// Returns the labels to match on.
+    pub fn matching(&self) -> &Matching {
+        &self.matching
+    }
+
+    /// Returns the group modifier, if any.
+    pub fn group(&self) -> Option<&Group> {
+        self.group.as_ref()
+    }
+}
+
+/// A binary expression, e.g. `a / on (job) group_left (version) b`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binary {
+    pub(crate) op: BinaryOp,
+    pub(crate) lhs: Box<Expr>,
+    pub(crate) rhs: Box<Expr>,
+    pub(crate) return_bool: bool,
+    pub(crate) matching: Option<VectorMatching>,
+}
+
+impl Binary {
+    /// Returns the binary operator.
+    pub fn op(&self) -> BinaryOp {
+        self.op
+    }
+
+    /// Returns the left-hand side of the expression.
+    pub fn lhs(&self) -> &Expr {
+        &self.lhs
+    }
+
+    /// Returns the right-hand side of the expression.
+    pub fn rhs(&self) -> &Expr {
+        &self.rhs
+    }
+
+    /// Check if the `bool` modifier is set.
+    pub fn returns_bool(&self) -> bool {
+        self.return_bool
+    }
+
+    /// Returns the vector matching, if any.
+    pub fn matching(&self) -> Option<&VectorMatching> {
+        self.matching.as_ref()
+    }
+
+    /// Set the `bool` modifier so that a comparison returns 0 or 1 instead of filtering.
+    /// This has no effect if the operator is not a comparison operator. Comparisons between
+    /// two scalars always use the `bool` modifier, see [`Expr::binary`].
+    pub fn bool(mut self) -> Self {
+        self.return_bool = self.op.is_comparison();
+        self
+    }
+
+    /// Only match series on the given labels.
+    /// Calling this repeatedly will replace the current matching.
+    pub fn on<T>(self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.with_matching(Matching::On(to_strings(labels)))
+    }
+
+    /// Match series on all labels except the given ones.
+    /// Calling this repeatedly will replace the current matching.
+    pub fn ignoring<T>(self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.with_matching(Matching::Ignoring(to_strings(labels)))
+    }
+
+    /// Allow many-to-one matches and copy the given labels from the right-hand side.
+    /// This has no effect for set operators.
+    pub fn group_left<T>(self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.with_group(Group::Left(to_strings(labels)))
+    }
+
+    /// Allow one-to-many matches and copy the given labels from the left-hand side.
+    /// This has no effect for set operators.
+    pub fn group_right<T>(self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        self.with_group(Group::Right(to_strings(labels)))
+    }
+
+    fn with_matching(mut self, matching: Matching) -> Self {
+        let group = self.matching.and_then(|m| m.group);
+        self.matching = Some(VectorMatching { matching, group });
+        self
+    }
+
+    // A group modifier requires `on` or `ignoring`, which defaults to `ignoring ()`.
+    fn with_group(mut self, group: Group) -> Self {
+        if !self.op.is_set_operator() {
+            let matching = self
+                .matching
+                .map_or(Matching::Ignoring(vec![]), |m| m.matching);
+            self.matching = Some(VectorMatching {
+                matching,
+                group: Some(group),
+            });
+        }
+        self
+    }
+}
+
+impl Binary {
+    // Check which operands must be enclosed in parentheses.
+    fn operand_parens(&self) -> (bool, bool) {
+        let precedence = self.op.precedence();
+        let right_assoc = self.op.is_right_associative();
+
+        let lhs_paren = self.lhs.precedence() < precedence
+            || (self.lhs.precedence() == precedence && right_assoc)
+            || (right_assoc && self.lhs.is_unary());
+        // A unary expression on the right-hand side extends as far as possible anyway.
+        let rhs_paren = !self.rhs.is_unary()
+            && (self.rhs.precedence() < precedence
+                || (self.rhs.precedence() == precedence && !right_assoc));
+
+        (lhs_paren, rhs_paren)
+    }
+
+    // Format the operator and its modifiers, e.g. `/ on (job) group_left (version)`.
+    fn fmt_op(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op)?;
+
+        if self.return_bool {
+            f.write_str(" bool")?;
+        }
+
+        if let Some(vm) = &self.matching {
+            match &vm.matching {
+                Matching::On(labels) => write!(f, " on ({})", LabelList(labels))?,
+                Matching::Ignoring(labels) => write!(f, " ignoring ({})", LabelList(labels))?,
+            }
+            match &vm.group {
+                Some(Group::Left(labels)) => write!(f, " group_left ({})", LabelList(labels))?,
+                Some(Group::Right(labels)) => write!(f, " group_right ({})", LabelList(labels))?,
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (lhs_paren, rhs_paren) = self.operand_parens();
+
+        fmt_operand(&self.lhs, lhs_paren, f)?;
+        f.write_str(" ")?;
+        self.fmt_op(f)?;
+        f.write_str(" ")?;
+        fmt_operand(&self.rhs, rhs_paren, f)
+    }
+}
+
+const MAX_CHARACTERS_PER_LINE: usize = 100;
+const INDENT: &str = "  ";
+
+impl Expr {
+    /// Format the expression like Prometheus' `format_query` endpoint does: expressions
+    /// that are longer than 100 characters are split across multiple lines, each nested
+    /// level is indented by two spaces.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::promql::Expr;
+    ///
+    /// let expr: Expr = r#"sum by (job) (rate(http_requests_total{job="api-server", handler=~"/api/v1/.*"}[5m])) / ignoring (code) sum by (job) (rate(http_requests_total[5m]))"#.parse()?;
+    ///
+    /// assert_eq!(
+    ///     expr.pretty(),
+    ///     r#"  sum by (job) (rate(http_requests_total{job="api-server",handler=~"/api/v1/.*"}[5m]))
+    /// / ignoring (code)
+    ///   sum by (job) (rate(http_requests_total[5m]))"#
+    /// );
+    /// # Ok::<(), prometheus_http_query::error::ParseError>(())
+    /// ```
+    pub fn pretty(&self) -> String {
+        self.pretty_at(0)
+    }
+
+    fn pretty_at(&self, level: usize) -> String {
+        let indent = INDENT.repeat(level);
+        let line = self.to_string();
+
+        if line.len() <= MAX_CHARACTERS_PER_LINE {
+            return indent + &line;
+        }
+
+        match self {
+            Self::Aggregate(a) => {
+                let mut s = format!("{}{}(\n", indent, DisplayWith(a, Aggregate::fmt_op));
+                if let Some(param) = &a.param {
+                    s += &format!("{},\n", param.pretty_at(level + 1));
+                }
+                s + &format!("{}\n{})", a.expr.pretty_at(level + 1), indent)
+            }
+            Self::Binary(b) => {
+                let (lhs_paren, rhs_paren) = b.operand_parens();
+                format!(
+                    "{}\n{}{}\n{}",
+                    pretty_operand(&b.lhs, lhs_paren, level + 1),
+                    indent,
+                    DisplayWith(b, Binary::fmt_op),
+                    pretty_operand(&b.rhs, rhs_paren, level + 1)
+                )
+            }
+            Self::Call(c) => {
+                let args: Vec<String> = c.args.iter().map(|a| a.pretty_at(level + 1)).collect();
+                format!(
+                    "{}{}(\n{}\n{})",
+                    indent,
+                    c.function,
+                    args.join(",\n"),
+                    indent
+                )
+            }
+            Self::Paren(e) => pretty_operand(e, true, level),
+            Self::Subquery(sq) => format!(
+                "{}{}",
+                pretty_operand(&sq.expr, !sq.expr.is_atomic(), level),
+                DisplayWith(sq, Subquery::fmt_suffix)
+            ),
+            Self::Unary(u) => {
+                let operand = pretty_operand(&u.expr, u.operand_paren(), level);
+                format!("{}{}{}", indent, u.op, operand.trim_start())
+            }
+            _ => indent + &line,
+        }
+    }
+}
+
+fn pretty_operand(expr: &Expr, paren: bool, level: usize) -> String {
+    if !paren {
+        return expr.pretty_at(level);
+    }
+
+    let indent = INDENT.repeat(level);
+    let line = format!("({})", expr);
+
+    if line.len() <= MAX_CHARACTERS_PER_LINE {
+        indent + &line
+    } else {
+        format!("{}(\n{}\n{})", indent, expr.pretty_at(level + 1), indent)
+    }
+}
+
+// Formats a part of a node with the given function.
+struct DisplayWith<'a, T>(&'a T, fn(&T, &mut fmt::Formatter) -> fmt::Result);
+
+impl<'a, T> fmt::Display for DisplayWith<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.1)(self.0, f)
+    }
+}
+
+fn fmt_operand(expr: &Expr, paren: bool, f: &mut fmt::Formatter) -> fmt::Result {
+    if paren {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+/// An aggregation operator.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AggregateOp {
+    Sum,
+    Avg,
+    Count,
+    Min,
+    Max,
+    Group,
+    Stddev,
+    Stdvar,
+    Topk,
+    Bottomk,
+    CountValues,
+    Quantile,
+    Limitk,
+    LimitRatio,
+}
+
+impl AggregateOp {
+    /// Returns the operator that corresponds to the given name, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let op = match name {
+            "sum" => Self::Sum,
+            "avg" => Self::Avg,
+            "count" => Self::Count,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "group" => Self::Group,
+            "stddev" => Self::Stddev,
+            "stdvar" => Self::Stdvar,
+            "topk" => Self::Topk,
+            "bottomk" => Self::Bottomk,
+            "count_values" => Self::CountValues,
+            "quantile" => Self::Quantile,
+            "limitk" => Self::Limitk,
+            "limit_ratio" => Self::LimitRatio,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Returns the name of the operator.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Count => "count",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Group => "group",
+            Self::Stddev => "stddev",
+            Self::Stdvar => "stdvar",
+            Self::Topk => "topk",
+            Self::Bottomk => "bottomk",
+            Self::CountValues => "count_values",
+            Self::Quantile => "quantile",
+            Self::Limitk => "limitk",
+            Self::LimitRatio => "limit_ratio",
+        }
+    }
+
+    /// Returns the type of the parameter if the operator requires one.
+    pub fn param_type(&self) -> Option<ValueType> {
+        match self {
+            Self::Topk | Self::Bottomk | Self::Quantile | Self::Limitk | Self::LimitRatio => {
+                Some(ValueType::Scalar)
+            }
+            Self::CountValues => Some(ValueType::String),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AggregateOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+macro_rules! functions {
+    ($($variant:ident => $name:literal ($($arg:ident),*) $(+ $variadic:literal)? -> $ret:ident,)*) => {
+        /// A PromQL function, see the
+        /// [Prometheus documentation](https://prometheus.io/docs/prometheus/latest/querying/functions/)
+        /// for details.
+        #[non_exhaustive]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        pub enum Function {
+            $($variant,)*
+        }
+
+        impl Function {
+            /// Returns the function that corresponds to the given name, if any.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $($name => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Returns the name of the function.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            /// Returns the types of the arguments of the function. Trailing arguments may
+            /// be optional or repeated, see [`Function::variadic`].
+            pub fn arg_types(&self) -> &'static [ValueType] {
+                match self {
+                    $(Self::$variant => &[$(ValueType::$arg),*],)*
+                }
+            }
+
+            /// Returns 0 if the function takes exactly the arguments given by [`Function::arg_types`].
+            /// Otherwise the last argument is optional and may be given up to `variadic` times
+            /// (any number of times if this is negative).
+            pub fn variadic(&self) -> i32 {
+                match self {
+                    $(Self::$variant => 0 $(+ $variadic)?,)*
+                }
+            }
+
+            /// Returns the type of the value that the function returns.
+            pub fn return_type(&self) -> ValueType {
+                match self {
+                    $(Self::$variant => ValueType::$ret,)*
+                }
+            }
+        }
+    };
+}
+
+functions! {
+    Abs => "abs"(Vector) -> Vector,
+    Absent => "absent"(Vector) -> Vector,
+    AbsentOverTime => "absent_over_time"(Matrix) -> Vector,
+    Acos => "acos"(Vector) -> Vector,
+    Acosh => "acosh"(Vector) -> Vector,
+    Asin => "asin"(Vector) -> Vector,
+    Asinh => "asinh"(Vector) -> Vector,
+    Atan => "atan"(Vector) -> Vector,
+    Atanh => "atanh"(Vector) -> Vector,
+    AvgOverTime => "avg_over_time"(Matrix) -> Vector,
+    Ceil => "ceil"(Vector) -> Vector,
+    Changes => "changes"(Matrix) -> Vector,
+    Clamp => "clamp"(Vector, Scalar, Scalar) -> Vector,
+    ClampMax => "clamp_max"(Vector, Scalar) -> Vector,
+    ClampMin => "clamp_min"(Vector, Scalar) -> Vector,
+    Cos => "cos"(Vector) -> Vector,
+    Cosh => "cosh"(Vector) -> Vector,
+    CountOverTime => "count_over_time"(Matrix) -> Vector,
+    DaysInMonth => "days_in_month"(Vector) + 1 -> Vector,
+    DayOfMonth => "day_of_month"(Vector) + 1 -> Vector,
+    DayOfWeek => "day_of_week"(Vector) + 1 -> Vector,
+    DayOfYear => "day_of_year"(Vector) + 1 -> Vector,
+    Deg => "deg"(Vector) -> Vector,
+    Delta => "delta"(Matrix) -> Vector,
+    Deriv => "deriv"(Matrix) -> Vector,
+    DoubleExponentialSmoothing => "double_exponential_smoothing"(Matrix, Scalar, Scalar) -> Vector,
+    Exp => "exp"(Vector) -> Vector,
+    Floor => "floor"(Vector) -> Vector,
+    HistogramAvg => "histogram_avg"(Vector) -> Vector,
+    HistogramCount => "histogram_count"(Vector) -> Vector,
+    HistogramFraction => "histogram_fraction"(Scalar, Scalar, Vector) -> Vector,
+    HistogramQuantile => "histogram_quantile"(Scalar, Vector) -> Vector,
+    HistogramStddev => "histogram_stddev"(Vector) -> Vector,
+    HistogramStdvar => "histogram_stdvar"(Vector) -> Vector,
+    HistogramSum => "histogram_sum"(Vector) -> Vector,
+    HoltWinters => "holt_winters"(Matrix, Scalar, Scalar) -> Vector,
+    Hour => "hour"(Vector) + 1 -> Vector,
+    Idelta => "idelta"(Matrix) -> Vector,
+    Increase => "increase"(Matrix) -> Vector,
+    Info => "info"(Vector, Vector) + 1 -> Vector,
+    Irate => "irate"(Matrix) -> Vector,
+    LabelJoin => "label_join"(Vector, String, String, String) + -1 -> Vector,
+    LabelReplace => "label_replace"(Vector, String, String, String, String) -> Vector,
+    LastOverTime => "last_over_time"(Matrix) -> Vector,
+    Ln => "ln"(Vector) -> Vector,
+    Log10 => "log10"(Vector) -> Vector,
+    Log2 => "log2"(Vector) -> Vector,
+    MadOverTime => "mad_over_time"(Matrix) -> Vector,
+    MaxOverTime => "max_over_time"(Matrix) -> Vector,
+    MinOverTime => "min_over_time"(Matrix) -> Vector,
+    Minute => "minute"(Vector) + 1 -> Vector,
+    Month => "month"(Vector) + 1 -> Vector,
+    Pi => "pi"() -> Scalar,
+    PredictLinear => "predict_linear"(Matrix, Scalar) -> Vector,
+    PresentOverTime => "present_over_time"(Matrix) -> Vector,
+    QuantileOverTime => "quantile_over_time"(Scalar, Matrix) -> Vector,
+    Rad => "rad"(Vector) -> Vector,
+    Rate => "rate"(Matrix) -> Vector,
+    Resets => "resets"(Matrix) -> Vector,
+    Round => "round"(Vector, Scalar) + 1 -> Vector,
+    Scalar => "scalar"(Vector) -> Scalar,
+    Sgn => "sgn"(Vector) -> Vector,
+    Sin => "sin"(Vector) -> Vector,
+    Sinh => "sinh"(Vector) -> Vector,
+    Sort => "sort"(Vector) -> Vector,
+    SortByLabel => "sort_by_label"(Vector, String) + -1 -> Vector,
+    SortByLabelDesc => "sort_by_label_desc"(Vector, String) + -1 -> Vector,
+    SortDesc => "sort_desc"(Vector) -> Vector,
+    Sqrt => "sqrt"(Vector) -> Vector,
+    StddevOverTime => "stddev_over_time"(Matrix) -> Vector,
+    StdvarOverTime => "stdvar_over_time"(Matrix) -> Vector,
+    SumOverTime => "sum_over_time"(Matrix) -> Vector,
+    Tan => "tan"(Vector) -> Vector,
+    Tanh => "tanh"(Vector) -> Vector,
+    Time => "time"() -> Scalar,
+    Timestamp => "timestamp"(Vector) -> Vector,
+    Vector => "vector"(Scalar) -> Vector,
+    Year => "year"(Vector) + 1 -> Vector,
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Create an instant vector selector from a [`Selector`].<br>
+/// Returns [`Error::EmptyVectorSelector`] if the [`Selector`] does not contain a single
+/// matcher that does not match the empty string (e.g. `{}` or `{job=""}`), as Prometheus
+/// rejects such selectors. Note that only a few trivial regular expressions like `.*` are
+/// recognized as matching the empty string.
+pub fn vector(selector: Selector) -> Result<VectorSelector, Error> {
+    if selector.labels.iter().any(is_non_empty_matcher) {
+        Ok(VectorSelector::new(selector))
+    } else {
+        Err(Error::EmptyVectorSelector)
+    }
+}
+
+/// Create a function call, e.g. `call(Function::Rate, [expr])`.
+pub fn call<T>(function: Function, args: T) -> Call
+where
+    T: IntoIterator,
+    T::Item: Into<Expr>,
+{
+    Call {
+        function,
+        args: args.into_iter().map(Into::into).collect(),
+    }
+}
+
+macro_rules! aggregations {
+    ($($name:ident => $op:ident),*) => {
+        $(
+            #[doc = concat!("Create a `", stringify!($name), "` aggregation.")]
+            pub fn $name(expr: impl Into<Expr>) -> Aggregate {
+                Aggregate::new(AggregateOp::$op, expr)
+            }
+        )*
+    };
+    ($($name:ident($param:ident) => $op:ident),*) => {
+        $(
+            #[doc = concat!("Create a `", stringify!($name), "` aggregation with the given parameter.")]
+            pub fn $name($param: impl Into<Expr>, expr: impl Into<Expr>) -> Aggregate {
+                Aggregate::new(AggregateOp::$op, expr).param($param)
+            }
+        )*
+    };
+}
+
+aggregations!(
+    sum => Sum,
+    avg => Avg,
+    count => Count,
+    min => Min,
+    max => Max,
+    group => Group,
+    stddev => Stddev,
+    stdvar => Stdvar
+);
+
+aggregations!(
+    topk(k) => Topk,
+    bottomk(k) => Bottomk,
+    quantile(phi) => Quantile,
+    limitk(k) => Limitk,
+    limit_ratio(ratio) => LimitRatio
+);
+
+/// Create a `count_values` aggregation that stores the values in the given label.
+pub fn count_values(label: impl fmt::Display, expr: impl Into<Expr>) -> Aggregate {
+    Aggregate::new(AggregateOp::CountValues, expr).param(Expr::string(label))
+}
+
+fn to_strings<T>(labels: T) -> Vec<String>
+where
+    T: IntoIterator,
+    T::Item: fmt::Display,
+{
+    labels.into_iter().map(|l| l.to_string()).collect()
+}
+
+// A comma-separated list of label names, names that are not valid according
+// to the legacy naming rules are quoted.
+struct LabelList<'a>(&'a [String]);
+
+impl<'a> fmt::Display for LabelList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, label) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            if is_legacy_label_name(label) {
+                f.write_str(label)?;
+            } else {
+                write!(f, "{}", Quoted(label))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Identifiers that cannot be used as metric names in front of braces.
+pub(crate) fn is_keyword(s: &str) -> bool {
+    let s = s.to_ascii_lowercase();
+    AggregateOp::from_name(&s).is_some()
+        || matches!(
+            s.as_str(),
+            "and"
+                | "or"
+                | "unless"
+                | "atan2"
+                | "by"
+                | "without"
+                | "on"
+                | "ignoring"
+                | "group_left"
+                | "group_right"
+                | "bool"
+                | "offset"
+                | "start"
+                | "end"
+                | "inf"
+                | "nan"
+        )
+}
+
+fn fmt_number(n: f64, f: &mut fmt::Formatter) -> fmt::Result {
+    if n.is_nan() {
+        f.write_str("NaN")
+    } else if n.is_infinite() {
+        f.write_str(if n > 0.0 { "Inf" } else { "-Inf" })
+    } else {
+        write!(f, "{}", n)
+    }
+}
+
+/// Formats a duration like Prometheus does, e.g. `1h30m` or `1s500ms`.
+pub(crate) struct PromqlDuration(pub(crate) Duration);
+
+impl fmt::Display for PromqlDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let millis = self.0.whole_milliseconds();
+
+        if millis == 0 {
+            return f.write_str("0s");
+        }
+
+        if millis < 0 {
+            f.write_str("-")?;
+        }
+
+        let mut ms = millis.unsigned_abs();
+
+        // Years and weeks are only used if the duration is a multiple of them
+        // as e.g. 90d is easier to read than 12w6d.
+        let units: [(&str, u128, bool); 7] = [
+            ("y", 1000 * 60 * 60 * 24 * 365, true),
+            ("w", 1000 * 60 * 60 * 24 * 7, true),
+            ("d", 1000 * 60 * 60 * 24, false),
+            ("h", 1000 * 60 * 60, false),
+            ("m", 1000 * 60, false),
+            ("s", 1000, false),
+            ("ms", 1, false),
+        ];
+
+        for (unit, mult, exact) in units {
+            if exact && !ms.is_multiple_of(mult) {
+                continue;
+            }
+            let v = ms / mult;
+            if v > 0 {
+                write!(f, "{}{}", v, unit)?;
+                ms -= v * mult;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metric(name: &str) -> Expr {
+        Expr::try_from(Selector::new().metric(name)).unwrap()
+    }
+
+    #[test]
+    fn test_duration_display() {
+        let cases = [
+            (Duration::ZERO, "0s"),
+            (Duration::minutes(5), "5m"),
+            (Duration::minutes(90), "1h30m"),
+            (Duration::milliseconds(1500), "1s500ms"),
+            (Duration::days(90), "90d"),
+            (Duration::weeks(2), "2w"),
+            (Duration::days(365), "1y"),
+            (Duration::minutes(-5), "-5m"),
+        ];
+
+        for (duration, expected) in cases {
+            assert_eq!(PromqlDuration(duration).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_vector_selector_display() {
+        let cases = [
+            (Selector::new().metric("up"), "up"),
+            (
+                Selector::new().metric("up").eq("job", "node"),
+                r#"up{job="node"}"#,
+            ),
+            (
+                Selector::new().eq("job", "node").metric("up"),
+                r#"{job="node",__name__="up"}"#,
+            ),
+            (
+                Selector::new().metric("http.requests"),
+                r#"{__name__="http.requests"}"#,
+            ),
+            (Selector::new().metric("sum"), r#"{__name__="sum"}"#),
+            (Selector::new().metric("Inf"), r#"{__name__="Inf"}"#),
+            (
+                Selector::new().metric("a").eq("__name__", "b"),
+                r#"{__name__="a",__name__="b"}"#,
+            ),
+        ];
+
+        for (selector, expected) in cases {
+            assert_eq!(vector(selector).unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_empty_vector_selector() {
+        let selectors = [
+            Selector::new(),
+            Selector::new().eq("job", ""),
+            Selector::new().regex_eq("job", ".*").ne("instance", "x"),
+            Selector::new().regex_ne("job", "foo"),
+        ];
+        for selector in selectors {
+            assert!(matches!(
+                vector(selector.clone()),
+                Err(Error::EmptyVectorSelector)
+            ));
+            assert!(Expr::try_from(selector).is_err());
+        }
+
+        assert!(vector(Selector::new().eq("job", "").ne("instance", "")).is_ok());
+        assert!(vector(Selector::new().regex_eq("job", ".+")).is_ok());
+        assert!(vector(Selector::new().regex_ne("job", "")).is_ok());
+    }
+
+    #[test]
+    fn test_modifiers_display() {
+        let v = vector(Selector::new().metric("up")).unwrap();
+
+        assert_eq!(
+            v.clone().offset(Duration::minutes(5)).to_string(),
+            "up offset 5m"
+        );
+        assert_eq!(
+            v.clone().offset(Duration::minutes(-5)).to_string(),
+            "up offset -5m"
+        );
+        assert_eq!(
+            v.clone()
+                .range(Duration::minutes(5))
+                .at(1609746000)
+                .offset(Duration::hours(1))
+                .to_string(),
+            "up[5m] @ 1609746000.000 offset 1h"
+        );
+        assert_eq!(v.clone().at(At::Start).to_string(), "up @ start()");
+
+        let subquery = Expr::from(call(
+            Function::Rate,
+            [v.clone().range(Duration::minutes(5))],
+        ))
+        .subquery(Duration::hours(1), Some(Duration::minutes(1)))
+        .at(At::End);
+        assert_eq!(subquery.to_string(), "rate(up[5m])[1h:1m] @ end()");
+
+        let subquery = (metric("a") + metric("b")).subquery(Duration::hours(1), None);
+        assert_eq!(subquery.to_string(), "(a + b)[1h:]");
+
+        let subquery =
+            Expr::from(v.offset(Duration::minutes(1))).subquery(Duration::hours(1), None);
+        assert_eq!(subquery.to_string(), "(up offset 1m)[1h:]");
+    }
+
+    #[test]
+    fn test_aggregate_display() {
+        assert_eq!(sum(metric("up")).to_string(), "sum(up)");
+        assert_eq!(
+            sum(metric("up")).by(["job", "instance"]).to_string(),
+            "sum by (job, instance) (up)"
+        );
+        assert_eq!(
+            sum(metric("up")).by(Vec::<String>::new()).to_string(),
+            "sum(up)"
+        );
+        assert_eq!(
+            avg(metric("up")).without(["service.name"]).to_string(),
+            r#"avg without ("service.name") (up)"#
+        );
+        assert_eq!(
+            topk(5.0, metric("up")).by(["code"]).to_string(),
+            "topk by (code) (5, up)"
+        );
+        assert_eq!(
+            count_values("value", metric("up")).to_string(),
+            r#"count_values("value", up)"#
+        );
+        assert_eq!(quantile(0.9, metric("up")).to_string(), "quantile(0.9, up)");
+    }
+
+    #[test]
+    fn test_binary_display() {
+        let (a, b, c) = (metric("a"), metric("b"), metric("c"));
+
+        let cases = [
+            (a.clone() + b.clone() * c.clone(), "a + b * c"),
+            ((a.clone() + b.clone()) * c.clone(), "(a + b) * c"),
+            (a.clone() - (b.clone() - c.clone()), "a - (b - c)"),
+            ((a.clone() - b.clone()) - c.clone(), "a - b - c"),
+            (
+                Expr::from(
+                    a.clone()
+                        .binary(BinaryOp::Pow, b.clone().binary(BinaryOp::Pow, c.clone())),
+                ),
+                "a ^ b ^ c",
+            ),
+            (
+                Expr::from(
+                    Expr::from(a.clone().binary(BinaryOp::Pow, b.clone()))
+                        .binary(BinaryOp::Pow, c.clone()),
+                ),
+                "(a ^ b) ^ c",
+            ),
+            (
+                Expr::from((-a.clone()).binary(BinaryOp::Pow, b.clone())),
+                "(-a) ^ b",
+            ),
+            (
+                Expr::from(Expr::from(-2.0).binary(BinaryOp::Pow, 2.0)),
+                "(-2) ^ 2",
+            ),
+            (-(a.clone() + b.clone()), "-(a + b)"),
+            (-a.clone() * b.clone(), "-a * b"),
+            (a.clone() * -b.clone(), "a * -b"),
+            (
+                Expr::from(
+                    a.clone()
+                        .binary(BinaryOp::Or, b.clone().binary(BinaryOp::And, c.clone())),
+                ),
+                "a or b and c",
+            ),
+            (
+                Expr::from(
+                    Expr::from(a.clone().binary(BinaryOp::Or, b.clone()))
+                        .binary(BinaryOp::And, c.clone()),
+                ),
+                "(a or b) and c",
+            ),
+            (
+                Expr::from(a.clone().binary(BinaryOp::Atan2, b.clone())),
+                "a atan2 b",
+            ),
+            ((a.clone() + b.clone()).paren() * c.clone(), "(a + b) * c"),
+            (a.clone() - -1.0, "a - -1"),
+            (a.clone() / (b.clone() * c.clone()), "a / (b * c)"),
+            (Expr::from(f64::INFINITY) + f64::NAN, "Inf + NaN"),
+        ];
+
+        for (expr, expected) in cases {
+            assert_eq!(expr.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_binary_modifiers_display() {
+        let (a, b) = (metric("a"), metric("b"));
+
+        assert_eq!(
+            a.clone().binary(BinaryOp::Gt, b.clone()).bool().to_string(),
+            "a > bool b"
+        );
+        // The bool modifier is only allowed for comparisons.
+        assert_eq!(
+            a.clone()
+                .binary(BinaryOp::Add, b.clone())
+                .bool()
+                .to_string(),
+            "a + b"
+        );
+        assert_eq!(
+            a.clone()
+                .binary(BinaryOp::Div, b.clone())
+                .on(["job"])
+                .group_left(["version"])
+                .to_string(),
+            "a / on (job) group_left (version) b"
+        );
+        assert_eq!(
+            a.clone()
+                .binary(BinaryOp::Mul, b.clone())
+                .group_right(Vec::<String>::new())
+                .to_string(),
+            "a * ignoring () group_right () b"
+        );
+        assert_eq!(
+            a.clone()
+                .binary(BinaryOp::And, b.clone())
+                .ignoring(["instance"])
+                .group_left(["x"])
+                .to_string(),
+            "a and ignoring (instance) b"
+        );
+        // Comparisons between scalars require the bool modifier.
+        let scalar_cmp = Expr::from(1.0).binary(BinaryOp::Gt, 2.0);
+        assert!(scalar_cmp.returns_bool());
+        assert_eq!(scalar_cmp.to_string(), "1 > bool 2");
+        assert_eq!(
+            scalar_cmp.to_string().parse::<Expr>().unwrap().to_string(),
+            "1 > bool 2"
+        );
+        assert_eq!(a.clone().binary(BinaryOp::Gt, 2.0).to_string(), "a > 2");
+    }
+
+    #[test]
+    fn test_call_display() {
+        let expr = call(
+            Function::LabelReplace,
+            [
+                metric("up"),
+                Expr::string("dst"),
+                Expr::string("$1"),
+                Expr::string("src"),
+                Expr::string("(.*)\"\\"),
+            ],
+        );
+        assert_eq!(
+            expr.to_string(),
+            r#"label_replace(up, "dst", "$1", "src", "(.*)\"\\")"#
+        );
+        assert_eq!(
+            call(Function::Time, Vec::<Expr>::new()).to_string(),
+            "time()"
+        );
+    }
+
+    #[test]
+    fn test_function_catalog() {
+        assert_eq!(Function::from_name("rate"), Some(Function::Rate));
+        assert_eq!(Function::from_name("unknown"), None);
+        assert_eq!(Function::HistogramQuantile.name(), "histogram_quantile");
+        assert_eq!(
+            Function::HistogramQuantile.arg_types(),
+            &[ValueType::Scalar, ValueType::Vector]
+        );
+        assert_eq!(Function::Round.variadic(), 1);
+        assert_eq!(Function::LabelJoin.variadic(), -1);
+        assert_eq!(Function::Time.return_type(), ValueType::Scalar);
+    }
+
+    #[test]
+    fn test_pretty() {
+        let cases = [
+            ("sum by (job) (up)", "sum by (job) (up)"),
+            (
+                r#"histogram_quantile(0.99, sum by (le, job) (rate(http_request_duration_seconds_bucket{job="api-server", handler="/api/v1/query"}[5m])))"#,
+                r#"histogram_quantile(
+  0.99,
+  sum by (le, job) (
+    rate(http_request_duration_seconds_bucket{job="api-server",handler="/api/v1/query"}[5m])
+  )
+)"#,
+            ),
+            (
+                "-(a_very_long_metric_name_number_one_with_more_text + a_very_long_metric_name_number_two_with_more_text_too)",
+                "-(
+    a_very_long_metric_name_number_one_with_more_text
+  +
+    a_very_long_metric_name_number_two_with_more_text_too
+)",
+            ),
+            (
+                "max_over_time((a_very_long_metric_name_number_one_with_more_text - a_very_long_metric_name_number_two_with_more)[1h:1m])",
+                "max_over_time(
+  (a_very_long_metric_name_number_one_with_more_text - a_very_long_metric_name_number_two_with_more)[1h:1m]
+)",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let expr: Expr = input.parse().unwrap();
+            assert_eq!(expr.pretty(), expected);
+            // Pretty-printed expressions are still valid PromQL.
+            assert_eq!(expected.parse::<Expr>().unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_value_type() {
+        let up = vector(Selector::new().metric("up")).unwrap();
+        assert_eq!(Expr::from(up.clone()).value_type(), ValueType::Vector);
+        assert_eq!(
+            Expr::from(up.clone().range(Duration::minutes(1))).value_type(),
+            ValueType::Matrix
+        );
+        assert_eq!((Expr::from(1.0) + 2.0).value_type(), ValueType::Scalar);
+        assert_eq!((Expr::from(1.0) + up).value_type(), ValueType::Vector);
+    }
+}
@@ -0,0 +1,1087 @@
+//! A parser for PromQL expressions that mirrors the grammar and the type checks of
+//! the Prometheus parser.
+use super::{
+    is_keyword, Aggregate, AggregateOp, At, Binary, BinaryOp, Call, Expr, Function, Group,
+    Grouping, Matching, MatrixSelector, Unary, UnaryOp, ValueType, VectorMatching, VectorSelector,
+};
+use crate::error::ParseError;
+use crate::lexer::{Lexer, Spanned, Token};
+use crate::selector::{is_non_empty_matcher, parse_matchers, Selector};
+use crate::util::Timestamp;
+use time::Duration;
+
+pub(crate) struct Parser<'a> {
+    lexer: Lexer<'a>,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Lexer::new(input),
+        }
+    }
+
+    /// Parse the complete input as a single expression.
+    pub(crate) fn parse(mut self) -> Result<Expr, ParseError> {
+        let first = self.lexer.peek()?;
+        if first.token == Token::Eof {
+            let start = first.start;
+            return Err(self.lexer.error(start, "no expression found in input"));
+        }
+
+        let (expr, _) = self.parse_expr(0)?;
+
+        let next = self.lexer.next()?;
+        if next.token != Token::Eof {
+            return Err(self.unexpected(next, None));
+        }
+
+        Ok(expr)
+    }
+
+    fn unexpected(&self, spanned: Spanned, expected: Option<&str>) -> ParseError {
+        let message = match expected {
+            Some(expected) => format!("unexpected {}, expected {}", spanned.token, expected),
+            None => format!("unexpected {}", spanned.token),
+        };
+        self.lexer.error(spanned.start, message)
+    }
+
+    fn expect(&mut self, token: Token, context: &str) -> Result<usize, ParseError> {
+        let next = self.lexer.next()?;
+        if next.token == token {
+            Ok(next.start)
+        } else {
+            let expected = format!("{} {}", token, context);
+            Err(self.unexpected(next, Some(&expected)))
+        }
+    }
+
+    // Check if the next token is the given keyword (case-insensitive) and consume it if so.
+    fn accept_keyword(&mut self, keyword: &str) -> Result<bool, ParseError> {
+        match &self.lexer.peek()?.token {
+            Token::Identifier(i) if i.eq_ignore_ascii_case(keyword) => {
+                self.lexer.next()?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    // Parse a binary expression whose operators have at least the given precedence
+    // using precedence climbing. Returns the expression and its start position.
+    fn parse_expr(&mut self, min_precedence: u8) -> Result<(Expr, usize), ParseError> {
+        let (mut lhs, start) = self.parse_unary()?;
+
+        while let Some(op) = self.peek_binary_op()? {
+            if op.precedence() < min_precedence {
+                break;
+            }
+
+            let op_start = self.lexer.next()?.start;
+
+            let return_bool = self.accept_keyword("bool")?;
+            if return_bool && !op.is_comparison() {
+                return Err(self.lexer.error(
+                    op_start,
+                    "bool modifier can only be used on comparison operators",
+                ));
+            }
+
+            let matching = self.parse_vector_matching()?;
+
+            let next_precedence = if op.is_right_associative() {
+                op.precedence()
+            } else {
+                op.precedence() + 1
+            };
+            let (rhs, _) = self.parse_expr(next_precedence)?;
+
+            let binary = Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                return_bool,
+                matching,
+            };
+            self.check_binary(&binary, op_start)?;
+            lhs = Expr::Binary(binary);
+        }
+
+        Ok((lhs, start))
+    }
+
+    fn peek_binary_op(&mut self) -> Result<Option<BinaryOp>, ParseError> {
+        let op = match &self.lexer.peek()?.token {
+            Token::Add => BinaryOp::Add,
+            Token::Sub => BinaryOp::Sub,
+            Token::Mul => BinaryOp::Mul,
+            Token::Div => BinaryOp::Div,
+            Token::Mod => BinaryOp::Mod,
+            Token::Pow => BinaryOp::Pow,
+            Token::EqEq => BinaryOp::Eq,
+            Token::Neq => BinaryOp::Ne,
+            Token::Gt => BinaryOp::Gt,
+            Token::Gte => BinaryOp::Ge,
+            Token::Lt => BinaryOp::Lt,
+            Token::Lte => BinaryOp::Le,
+            Token::Identifier(i) => match i.to_ascii_lowercase().as_str() {
+                "and" => BinaryOp::And,
+                "or" => BinaryOp::Or,
+                "unless" => BinaryOp::Unless,
+                "atan2" => BinaryOp::Atan2,
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        Ok(Some(op))
+    }
+
+    fn parse_vector_matching(&mut self) -> Result<Option<VectorMatching>, ParseError> {
+        let matching = if self.accept_keyword("on")? {
+            Matching::On(self.parse_label_list()?)
+        } else if self.accept_keyword("ignoring")? {
+            Matching::Ignoring(self.parse_label_list()?)
+        } else {
+            return Ok(None);
+        };
+
+        let group = if self.accept_keyword("group_left")? {
+            Some(Group::Left(self.parse_optional_label_list()?))
+        } else if self.accept_keyword("group_right")? {
+            Some(Group::Right(self.parse_optional_label_list()?))
+        } else {
+            None
+        };
+
+        Ok(Some(VectorMatching { matching, group }))
+    }
+
+    fn parse_optional_label_list(&mut self) -> Result<Vec<String>, ParseError> {
+        if self.lexer.peek()?.token == Token::LeftParen {
+            self.parse_label_list()
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    // Parse a parenthesized list of label names, e.g. `(job, "service.name")`.
+    fn parse_label_list(&mut self) -> Result<Vec<String>, ParseError> {
+        self.expect(Token::LeftParen, "in grouping opts")?;
+
+        let mut labels = vec![];
+
+        loop {
+            let next = self.lexer.next()?;
+            match next.token {
+                Token::RightParen => return Ok(labels),
+                Token::Identifier(ref name) if !name.contains(':') => labels.push(name.clone()),
+                Token::String(ref name) if !name.is_empty() => labels.push(name.clone()),
+                _ => return Err(self.unexpected(next, Some("label"))),
+            }
+
+            let next = self.lexer.next()?;
+            match next.token {
+                Token::Comma => continue,
+                Token::RightParen => return Ok(labels),
+                _ => return Err(self.unexpected(next, Some("\",\" or \")\""))),
+            }
+        }
+    }
+
+    fn check_binary(&self, binary: &Binary, position: usize) -> Result<(), ParseError> {
+        let lhs = binary.lhs.value_type();
+        let rhs = binary.rhs.value_type();
+        let is_operand = |t| t == ValueType::Scalar || t == ValueType::Vector;
+
+        let message = if !is_operand(lhs) || !is_operand(rhs) {
+            String::from("binary expression must contain only scalar and instant vector types")
+        } else if binary.op.is_comparison()
+            && !binary.return_bool
+            && lhs == ValueType::Scalar
+            && rhs == ValueType::Scalar
+        {
+            String::from("comparisons between scalars must use BOOL modifier")
+        } else if binary.op.is_set_operator()
+            && (lhs == ValueType::Scalar || rhs == ValueType::Scalar)
+        {
+            format!(
+                "set operator \"{}\" not allowed in binary scalar expression",
+                binary.op
+            )
+        } else if let Some(vm) = &binary.matching {
+            if lhs != ValueType::Vector || rhs != ValueType::Vector {
+                String::from("vector matching only allowed between instant vectors")
+            } else if binary.op.is_set_operator() && vm.group.is_some() {
+                format!("no grouping allowed for \"{}\" operation", binary.op)
+            } else {
+                let on = match &vm.matching {
+                    Matching::On(labels) => labels.as_slice(),
+                    Matching::Ignoring(_) => &[],
+                };
+                let include = match &vm.group {
+                    Some(Group::Left(labels) | Group::Right(labels)) => labels.as_slice(),
+                    None => &[],
+                };
+                match include.iter().find(|l| on.contains(l)) {
+                    Some(label) => format!(
+                        "label \"{}\" must not occur in ON and GROUP clause at once",
+                        label
+                    ),
+                    None => return Ok(()),
+                }
+            }
+        } else {
+            return Ok(());
+        };
+
+        Err(self.lexer.error(position, message))
+    }
+
+    fn parse_unary(&mut self) -> Result<(Expr, usize), ParseError> {
+        let op = match self.lexer.peek()?.token {
+            Token::Add => UnaryOp::Pos,
+            Token::Sub => UnaryOp::Neg,
+            _ => return self.parse_postfix(),
+        };
+
+        let start = self.lexer.next()?.start;
+
+        // Unary operators bind stronger than all binary operators except `^`.
+        let (expr, expr_start) = self.parse_expr(BinaryOp::Pow.precedence())?;
+
+        let value_type = expr.value_type();
+        if value_type != ValueType::Scalar && value_type != ValueType::Vector {
+            return Err(self.lexer.error(
+                expr_start,
+                format!(
+                    "unary expression only allowed on expressions of type scalar or instant vector, got \"{}\"",
+                    value_type
+                ),
+            ));
+        }
+
+        let expr = match (op, expr) {
+            (UnaryOp::Neg, Expr::Number(n)) => Expr::Number(-n),
+            (UnaryOp::Pos, Expr::Number(n)) => Expr::Number(n),
+            (op, expr) => Expr::Unary(Unary {
+                op,
+                expr: Box::new(expr),
+            }),
+        };
+
+        Ok((expr, start))
+    }
+
+    // Parse a primary expression followed by any number of ranges, subqueries and modifiers.
+    fn parse_postfix(&mut self) -> Result<(Expr, usize), ParseError> {
+        let (mut expr, start) = self.parse_primary()?;
+
+        loop {
+            let next = self.lexer.peek()?;
+            let position = next.start;
+
+            match &next.token {
+                Token::LeftBracket => {
+                    self.lexer.next()?;
+                    expr = self.parse_range(expr, start)?;
+                }
+                Token::Identifier(i) if i.eq_ignore_ascii_case("offset") => {
+                    self.lexer.next()?;
+                    let offset = self.parse_offset()?;
+                    let current = match &mut expr {
+                        Expr::Vector(v) => &mut v.offset,
+                        Expr::Matrix(m) => &mut m.vector.offset,
+                        Expr::Subquery(s) => &mut s.offset,
+                        _ => return Err(self.lexer.error(
+                            position,
+                            "offset modifier must be preceded by an instant vector selector or range vector selector or a subquery",
+                        )),
+                    };
+                    if current.is_some() {
+                        return Err(self
+                            .lexer
+                            .error(position, "offset may not be set multiple times"));
+                    }
+                    *current = Some(offset);
+                }
+                Token::At => {
+                    self.lexer.next()?;
+                    let at = self.parse_at()?;
+                    let current = match &mut expr {
+                        Expr::Vector(v) => &mut v.at,
+                        Expr::Matrix(m) => &mut m.vector.at,
+                        Expr::Subquery(s) => &mut s.at,
+                        _ => return Err(self.lexer.error(
+                            position,
+                            "@ modifier must be preceded by an instant vector selector or range vector selector or a subquery",
+                        )),
+                    };
+                    if current.is_some() {
+                        return Err(self
+                            .lexer
+                            .error(position, "@ <timestamp> may not be set multiple times"));
+                    }
+                    *current = Some(at);
+                }
+                _ => return Ok((expr, start)),
+            }
+        }
+    }
+
+    // Parse a range or subquery after the opening bracket.
+    fn parse_range(&mut self, expr: Expr, start: usize) -> Result<Expr, ParseError> {
+        let range = self.parse_duration()?;
+
+        let next = self.lexer.next()?;
+        let expr = match next.token {
+            Token::Colon => {
+                let step = match self.lexer.peek()?.token {
+                    Token::RightBracket => None,
+                    _ => Some(self.parse_duration()?),
+                };
+                self.expect(Token::RightBracket, "in subquery selector")?;
+
+                let value_type = expr.value_type();
+                if value_type != ValueType::Vector {
+                    return Err(self.lexer.error(
+                        start,
+                        format!(
+                            "subquery is only allowed on instant vector, got {} instead",
+                            value_type
+                        ),
+                    ));
+                }
+
+                Expr::Subquery(expr.subquery(range, step))
+            }
+            Token::RightBracket => match expr {
+                Expr::Vector(v) if v.offset.is_some() => {
+                    return Err(self
+                        .lexer
+                        .error(start, "no offset modifiers allowed before range"))
+                }
+                Expr::Vector(v) if v.at.is_some() => {
+                    return Err(self
+                        .lexer
+                        .error(start, "no @ modifiers allowed before range"))
+                }
+                Expr::Vector(vector) => Expr::Matrix(MatrixSelector { vector, range }),
+                _ => {
+                    return Err(self
+                        .lexer
+                        .error(start, "ranges only allowed for vector selectors"))
+                }
+            },
+            _ => return Err(self.unexpected(next, Some("\"]\" or \":\""))),
+        };
+
+        Ok(expr)
+    }
+
+    // Parse a duration, plain numbers are interpreted as seconds.
+    fn parse_duration(&mut self) -> Result<Duration, ParseError> {
+        let next = self.lexer.next()?;
+        match next.token {
+            Token::Duration(d) => Ok(d),
+            Token::Number(n) if n.is_finite() && n >= 0.0 => {
+                Ok(Duration::milliseconds((n * 1000.0).round() as i64))
+            }
+            _ => Err(self.unexpected(next, Some("duration"))),
+        }
+    }
+
+    fn parse_offset(&mut self) -> Result<Duration, ParseError> {
+        match self.lexer.peek()?.token {
+            Token::Sub => {
+                self.lexer.next()?;
+                Ok(-self.parse_duration()?)
+            }
+            Token::Add => {
+                self.lexer.next()?;
+                self.parse_duration()
+            }
+            _ => self.parse_duration(),
+        }
+    }
+
+    fn parse_at(&mut self) -> Result<At, ParseError> {
+        let next = self.lexer.next()?;
+        let (sign, next) = match next.token {
+            Token::Sub => (-1.0, self.lexer.next()?),
+            Token::Add => (1.0, self.lexer.next()?),
+            _ => (1.0, next),
+        };
+
+        match next.token {
+            Token::Number(n) if n.is_finite() => Ok(At::Timestamp(Timestamp::from(sign * n))),
+            Token::Number(_) => Err(self
+                .lexer
+                .error(next.start, "timestamp out of bounds for @ modifier")),
+            Token::Identifier(ref i)
+                if i.eq_ignore_ascii_case("inf") || i.eq_ignore_ascii_case("nan") =>
+            {
+                Err(self
+                    .lexer
+                    .error(next.start, "timestamp out of bounds for @ modifier"))
+            }
+            Token::Identifier(ref i)
+                if sign > 0.0
+                    && (i.eq_ignore_ascii_case("start") || i.eq_ignore_ascii_case("end")) =>
+            {
+                let at = if i.eq_ignore_ascii_case("start") {
+                    At::Start
+                } else {
+                    At::End
+                };
+                self.expect(Token::LeftParen, "in @ modifier")?;
+                self.expect(Token::RightParen, "in @ modifier")?;
+                Ok(at)
+            }
+            _ => Err(self.unexpected(next, Some("timestamp"))),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<(Expr, usize), ParseError> {
+        let next = self.lexer.next()?;
+        let start = next.start;
+
+        let expr = match next.token {
+            Token::Number(n) => Expr::Number(n),
+            Token::Duration(d) => Expr::Number(d.as_seconds_f64()),
+            Token::String(s) => Expr::String(s),
+            Token::LeftParen => {
+                let (expr, _) = self.parse_expr(0)?;
+                self.expect(Token::RightParen, "in paren expression")?;
+                Expr::Paren(Box::new(expr))
+            }
+            Token::LeftBrace => {
+                let mut selector = Selector::new();
+                parse_matchers(&mut self.lexer, &mut selector, false)?;
+                if !selector.labels.iter().any(is_non_empty_matcher) {
+                    return Err(self.lexer.error(
+                        start,
+                        "vector selector must contain at least one non-empty matcher",
+                    ));
+                }
+                Expr::Vector(VectorSelector::new(selector))
+            }
+            Token::Identifier(name) => return self.parse_identifier(name, start),
+            _ => return Err(self.unexpected(next, Some("expression"))),
+        };
+
+        Ok((expr, start))
+    }
+
+    // Parse an expression that starts with an identifier: an aggregation, a function call,
+    // a special number like `Inf` or a vector selector.
+    fn parse_identifier(
+        &mut self,
+        name: String,
+        start: usize,
+    ) -> Result<(Expr, usize), ParseError> {
+        let lower = name.to_ascii_lowercase();
+        let peek = &self.lexer.peek()?.token;
+
+        // Like Prometheus, aggregation operators cannot be used as metric names, so they
+        // must be followed by a grouping or the parameters of the aggregation.
+        if let Some(op) = AggregateOp::from_name(&lower) {
+            let aggregate = self.parse_aggregate(op, start)?;
+            return Ok((Expr::Aggregate(aggregate), start));
+        }
+
+        if lower == "inf" {
+            return Ok((Expr::Number(f64::INFINITY), start));
+        }
+        if lower == "nan" {
+            return Ok((Expr::Number(f64::NAN), start));
+        }
+
+        if *peek == Token::LeftParen {
+            let function = Function::from_name(&name).ok_or_else(|| {
+                self.lexer
+                    .error(start, format!("unknown function with name \"{}\"", name))
+            })?;
+            let call = self.parse_call(function, start)?;
+            return Ok((Expr::Call(call), start));
+        }
+
+        // Some other keywords may be used as metric names.
+        if is_keyword(&name) && !is_metric_keyword(&lower) {
+            return Err(self.lexer.error(
+                start,
+                format!("unexpected identifier \"{}\", expected expression", name),
+            ));
+        }
+
+        let mut selector = Selector::new().metric(name);
+        if self.lexer.peek()?.token == Token::LeftBrace {
+            self.lexer.next()?;
+            parse_matchers(&mut self.lexer, &mut selector, true)?;
+        }
+
+        Ok((Expr::Vector(VectorSelector::new(selector)), start))
+    }
+
+    // Parse a comma-separated list of expressions enclosed in parentheses.
+    fn parse_args(&mut self, context: &str) -> Result<Vec<(Expr, usize)>, ParseError> {
+        self.expect(Token::LeftParen, context)?;
+
+        let mut args = vec![];
+        if self.lexer.peek()?.token == Token::RightParen {
+            self.lexer.next()?;
+            return Ok(args);
+        }
+
+        loop {
+            args.push(self.parse_expr(0)?);
+
+            let next = self.lexer.next()?;
+            match next.token {
+                Token::Comma => continue,
+                Token::RightParen => return Ok(args),
+                _ => {
+                    let expected = format!("\",\" or \")\" {}", context);
+                    return Err(self.unexpected(next, Some(&expected)));
+                }
+            }
+        }
+    }
+
+    fn parse_aggregate(&mut self, op: AggregateOp, start: usize) -> Result<Aggregate, ParseError> {
+        let mut grouping = self.parse_grouping()?;
+
+        let mut args = self.parse_args("in aggregation")?;
+
+        if grouping.is_none() {
+            grouping = self.parse_grouping()?;
+        }
+
+        let expected = if op.param_type().is_some() { 2 } else { 1 };
+        if args.len() != expected {
+            return Err(self.lexer.error(
+                start,
+                format!(
+                    "wrong number of arguments for aggregate expression provided, expected {}, got {}",
+                    expected,
+                    args.len()
+                ),
+            ));
+        }
+
+        let (expr, expr_start) = args.pop().unwrap();
+        self.expect_type(
+            &expr,
+            ValueType::Vector,
+            expr_start,
+            "aggregation expression",
+        )?;
+
+        let param = match (args.pop(), op.param_type()) {
+            (Some((param, param_start)), Some(param_type)) => {
+                self.expect_type(&param, param_type, param_start, "aggregation parameter")?;
+                Some(Box::new(param))
+            }
+            _ => None,
+        };
+
+        Ok(Aggregate {
+            op,
+            expr: Box::new(expr),
+            param,
+            grouping,
+        })
+    }
+
+    fn parse_grouping(&mut self) -> Result<Option<Grouping>, ParseError> {
+        if self.accept_keyword("by")? {
+            Ok(Some(Grouping::By(self.parse_label_list()?)))
+        } else if self.accept_keyword("without")? {
+            Ok(Some(Grouping::Without(self.parse_label_list()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn parse_call(&mut self, function: Function, start: usize) -> Result<Call, ParseError> {
+        let args = self.parse_args("in call to function")?;
+
+        let arg_types = function.arg_types();
+        let variadic = function.variadic();
+        let name = function.name();
+
+        let message = if variadic == 0 {
+            (args.len() != arg_types.len()).then(|| {
+                format!(
+                    "expected {} argument(s) in call to \"{}\", got {}",
+                    arg_types.len(),
+                    name,
+                    args.len()
+                )
+            })
+        } else {
+            let min = arg_types.len() - 1;
+            let max = min + variadic.max(0) as usize;
+            if args.len() < min {
+                Some(format!(
+                    "expected at least {} argument(s) in call to \"{}\", got {}",
+                    min,
+                    name,
+                    args.len()
+                ))
+            } else if variadic > 0 && args.len() > max {
+                Some(format!(
+                    "expected at most {} argument(s) in call to \"{}\", got {}",
+                    max,
+                    name,
+                    args.len()
+                ))
+            } else {
+                None
+            }
+        };
+
+        if let Some(message) = message {
+            return Err(self.lexer.error(start, message));
+        }
+
+        let context = format!("call to function \"{}\"", name);
+        for (i, (arg, arg_start)) in args.iter().enumerate() {
+            let expected = arg_types[i.min(arg_types.len() - 1)];
+            self.expect_type(arg, expected, *arg_start, &context)?;
+        }
+
+        Ok(Call {
+            function,
+            args: args.into_iter().map(|(arg, _)| arg).collect(),
+        })
+    }
+
+    fn expect_type(
+        &self,
+        expr: &Expr,
+        expected: ValueType,
+        position: usize,
+        context: &str,
+    ) -> Result<(), ParseError> {
+        let actual = expr.value_type();
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(self.lexer.error(
+                position,
+                format!("expected type {} in {}, got {}", expected, context, actual),
+            ))
+        }
+    }
+}
+
+// Keywords that Prometheus accepts as metric names.
+fn is_metric_keyword(lower: &str) -> bool {
+    matches!(
+        lower,
+        "and" | "or" | "unless" | "by" | "without" | "offset" | "start" | "end"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Valid expressions and their canonical formatting. Most of them are taken from
+    // the test cases of the Prometheus parser.
+    const CORPUS: &[(&str, &str)] = &[
+        // Literals
+        ("1", "1"),
+        ("+Inf", "Inf"),
+        ("-Inf", "-Inf"),
+        ("nan", "NaN"),
+        (".5", "0.5"),
+        ("5.", "5"),
+        ("123.4567", "123.4567"),
+        ("5e-3", "0.005"),
+        ("5e3", "5000"),
+        ("0xc", "12"),
+        ("-0755", "-755"),
+        ("1h", "3600"),
+        ("+5", "5"),
+        ("- 1", "-1"),
+        (
+            r#""double-quoted string \" with escaped quote""#,
+            r#""double-quoted string \" with escaped quote""#,
+        ),
+        (
+            r#"'single-quoted string \' with escaped quote'"#,
+            r#""single-quoted string ' with escaped quote""#,
+        ),
+        ("`backtick-quoted string`", r#""backtick-quoted string""#),
+        // Scalar arithmetic
+        ("1 + 1", "1 + 1"),
+        ("1 - 1", "1 - 1"),
+        ("1 * 1", "1 * 1"),
+        ("1 % 1", "1 % 1"),
+        ("1 / 1", "1 / 1"),
+        ("1 == bool 1", "1 == bool 1"),
+        ("1 != bool 1", "1 != bool 1"),
+        ("1 > bool 1", "1 > bool 1"),
+        ("1 >= bool 1", "1 >= bool 1"),
+        ("1 < bool 1", "1 < bool 1"),
+        ("1 <= bool 1", "1 <= bool 1"),
+        ("-1^2", "-1 ^ 2"),
+        ("-1*2", "-1 * 2"),
+        ("-1+2", "-1 + 2"),
+        ("-1^-2", "-1 ^ -2"),
+        ("+1 + -2 * 1", "1 + -2 * 1"),
+        ("1 + 2/(3*1)", "1 + 2 / (3 * 1)"),
+        ("1 < bool 2 - 1 * 2", "1 < bool 2 - 1 * 2"),
+        ("2^3^2", "2 ^ 3 ^ 2"),
+        ("(2^3)^2", "(2 ^ 3) ^ 2"),
+        ("1 - 2 - 3", "1 - 2 - 3"),
+        ("1 - (2 - 3)", "1 - (2 - 3)"),
+        // Vector binary expressions
+        ("-some_metric", "-some_metric"),
+        ("+some_metric", "+some_metric"),
+        ("foo * bar", "foo * bar"),
+        ("foo == 1", "foo == 1"),
+        ("foo == bool 1", "foo == bool 1"),
+        ("2.5 / bar", "2.5 / bar"),
+        ("foo and bar", "foo and bar"),
+        ("foo or bar", "foo or bar"),
+        ("foo unless bar", "foo unless bar"),
+        ("foo + bar or bla and blub", "foo + bar or bla and blub"),
+        (
+            "foo and bar unless baz or qux",
+            "foo and bar unless baz or qux",
+        ),
+        (
+            "bar + on(foo) bla / on(baz, buz) group_right(test) blub",
+            "bar + on (foo) bla / on (baz, buz) group_right (test) blub",
+        ),
+        ("foo * on(test,blub) bar", "foo * on (test, blub) bar"),
+        (
+            "foo * on(test,blub) group_left bar",
+            "foo * on (test, blub) group_left () bar",
+        ),
+        ("foo and on(test,blub) bar", "foo and on (test, blub) bar"),
+        ("foo and on() bar", "foo and on () bar"),
+        (
+            "foo and ignoring(test,blub) bar",
+            "foo and ignoring (test, blub) bar",
+        ),
+        ("foo and ignoring() bar", "foo and ignoring () bar"),
+        ("foo unless on(bar) baz", "foo unless on (bar) baz"),
+        (
+            "foo / on(test,blub) group_left(bar) bar",
+            "foo / on (test, blub) group_left (bar) bar",
+        ),
+        (
+            "foo / ignoring(test,blub) group_left(blub) bar",
+            "foo / ignoring (test, blub) group_left (blub) bar",
+        ),
+        (
+            "foo - on(test,blub) group_right(bar,foo) bar",
+            "foo - on (test, blub) group_right (bar, foo) bar",
+        ),
+        (
+            "foo - ignoring(test,blub) group_right(bar,foo) bar",
+            "foo - ignoring (test, blub) group_right (bar, foo) bar",
+        ),
+        ("foo ATAN2 bar", "foo atan2 bar"),
+        ("foo AND ON (job) bar", "foo and on (job) bar"),
+        (
+            r#"foo * on("service.name") bar"#,
+            r#"foo * on ("service.name") bar"#,
+        ),
+        // Vector selectors
+        ("foo", "foo"),
+        ("foo offset 5m", "foo offset 5m"),
+        ("foo offset -7m", "foo offset -7m"),
+        ("foo OFFSET 1h30m", "foo offset 1h30m"),
+        ("foo OFFSET 1m30ms", "foo offset 1m30ms"),
+        ("foo @ 1603774568", "foo @ 1603774568.000"),
+        ("foo @ -100", "foo @ -100.000"),
+        ("foo @ .3", "foo @ 0.300"),
+        ("foo @ 3.", "foo @ 3.000"),
+        ("foo @ 3.33", "foo @ 3.330"),
+        ("foo @ start()", "foo @ start()"),
+        ("foo @ end()", "foo @ end()"),
+        ("foo offset 5m @ 10", "foo @ 10.000 offset 5m"),
+        ("foo @ 10 offset 5m", "foo @ 10.000 offset 5m"),
+        ("foo:bar{a=\"bc\"}", "foo:bar{a=\"bc\"}"),
+        (r#"foo{NaN='bc'}"#, r#"foo{NaN="bc"}"#),
+        (r#"foo{bar='}'}"#, r#"foo{bar="}"}"#),
+        (
+            r#"foo{a="b", foo!="bar", test=~"test", bar!~"baz"}"#,
+            r#"foo{a="b",foo!="bar",test=~"test",bar!~"baz"}"#,
+        ),
+        (
+            r#"foo{a="b", foo!="bar", test=~"test", bar!~"baz",}"#,
+            r#"foo{a="b",foo!="bar",test=~"test",bar!~"baz"}"#,
+        ),
+        (r#"{__name__=~"foo.+"}"#, r#"{__name__=~"foo.+"}"#),
+        (r#"{"foo"}"#, "foo"),
+        (
+            r#"{"http.requests", "service.name"="api"}"#,
+            r#"{__name__="http.requests","service.name"="api"}"#,
+        ),
+        (r#"{x!=""}"#, r#"{x!=""}"#),
+        // Range vector selectors
+        ("test[5s]", "test[5s]"),
+        ("test[5m]", "test[5m]"),
+        ("test[5m] @ end()", "test[5m] @ end()"),
+        ("test[5h] OFFSET 5m", "test[5h] offset 5m"),
+        ("test[5d] OFFSET 10s", "test[5d] offset 10s"),
+        ("test[5w] offset 2w", "test[5w] offset 2w"),
+        ("test[1y]", "test[1y]"),
+        ("test[300]", "test[5m]"),
+        ("test[1.5]", "test[1s500ms]"),
+        (r#"foo{bar="baz"}[10m:6s]"#, r#"foo{bar="baz"}[10m:6s]"#),
+        // Aggregations
+        ("sum by (foo)(some_metric)", "sum by (foo) (some_metric)"),
+        ("avg by (foo)(some_metric)", "avg by (foo) (some_metric)"),
+        ("max by (foo)(some_metric)", "max by (foo) (some_metric)"),
+        (
+            "sum without (foo) (some_metric)",
+            "sum without (foo) (some_metric)",
+        ),
+        (
+            "sum (some_metric) without (foo)",
+            "sum without (foo) (some_metric)",
+        ),
+        ("stddev(some_metric)", "stddev(some_metric)"),
+        (
+            "stdvar by (foo)(some_metric)",
+            "stdvar by (foo) (some_metric)",
+        ),
+        ("sum by ()(some_metric)", "sum(some_metric)"),
+        (
+            "sum by (foo,bar,)(some_metric)",
+            "sum by (foo, bar) (some_metric)",
+        ),
+        ("sum by (foo,)(some_metric)", "sum by (foo) (some_metric)"),
+        ("topk(5, some_metric)", "topk(5, some_metric)"),
+        (
+            "count_values(\"value\", some_metric)",
+            "count_values(\"value\", some_metric)",
+        ),
+        (
+            "sum without(and, by, avg, count, alert, annotations)(some_metric)",
+            "sum without (and, by, avg, count, alert, annotations) (some_metric)",
+        ),
+        (
+            "sum without(foo) (some_metric)",
+            "sum without (foo) (some_metric)",
+        ),
+        ("SUM BY (foo) (some_metric)", "sum by (foo) (some_metric)"),
+        (
+            "quantile(0.9, rate(foo[5m]))",
+            "quantile(0.9, rate(foo[5m]))",
+        ),
+        ("limitk(5, some_metric)", "limitk(5, some_metric)"),
+        (
+            "limit_ratio(0.5, some_metric)",
+            "limit_ratio(0.5, some_metric)",
+        ),
+        (
+            "sum by (\"service.name\") (some_metric)",
+            "sum by (\"service.name\") (some_metric)",
+        ),
+        // Function calls
+        ("time()", "time()"),
+        (
+            r#"floor(some_metric{foo!="bar"})"#,
+            r#"floor(some_metric{foo!="bar"})"#,
+        ),
+        ("rate(some_metric[5m])", "rate(some_metric[5m])"),
+        ("round(some_metric)", "round(some_metric)"),
+        ("round(some_metric, 5)", "round(some_metric, 5)"),
+        ("day_of_month()", "day_of_month()"),
+        (
+            "label_join(up, \"dst\", \",\", \"a\", \"b\", \"c\")",
+            "label_join(up, \"dst\", \",\", \"a\", \"b\", \"c\")",
+        ),
+        (
+            "sort_by_label(up, \"a\", \"b\")",
+            "sort_by_label(up, \"a\", \"b\")",
+        ),
+        (
+            "histogram_quantile(0.9, sum by (le) (rate(foo_bucket[5m])))",
+            "histogram_quantile(0.9, sum by (le) (rate(foo_bucket[5m])))",
+        ),
+        (
+            "absent(nonexistent{job=\"myjob\"})",
+            "absent(nonexistent{job=\"myjob\"})",
+        ),
+        // Subqueries
+        (
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:])[4m:3s]"#,
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:])[4m:3s]"#,
+        ),
+        (
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:] offset 4m)[4m:3s]"#,
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:] offset 4m)[4m:3s]"#,
+        ),
+        (
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:] @ 1603775091)[4m:3s]"#,
+            r#"min_over_time(rate(foo{bar="baz"}[2s])[5m:] @ 1603775091.000)[4m:3s]"#,
+        ),
+        (
+            "sum without(and, by, avg, count, alert, annotations)(some_metric) [30m:10s]",
+            "sum without (and, by, avg, count, alert, annotations) (some_metric)[30m:10s]",
+        ),
+        (
+            "some_metric OFFSET 1m [10m:5s]",
+            "(some_metric offset 1m)[10m:5s]",
+        ),
+        (
+            "some_metric @ 123 [10m:5s]",
+            "(some_metric @ 123.000)[10m:5s]",
+        ),
+        (
+            "(foo + bar{nm=\"val\"})[5m:]",
+            "(foo + bar{nm=\"val\"})[5m:]",
+        ),
+        (
+            "(foo + bar{nm=\"val\"})[5m:] offset 10m",
+            "(foo + bar{nm=\"val\"})[5m:] offset 10m",
+        ),
+        (
+            "(foo + bar{nm=\"val\"} @ 1234)[5m:] @ 1603775019",
+            "(foo + bar{nm=\"val\"} @ 1234.000)[5m:] @ 1603775019.000",
+        ),
+        // Parentheses and comments
+        ("((foo))", "((foo))"),
+        ("(1 + 2) * 3", "(1 + 2) * 3"),
+        ("# a comment\nfoo # another one\n+ bar", "foo + bar"),
+    ];
+
+    #[test]
+    fn test_parse_corpus() {
+        for (input, expected) in CORPUS {
+            let expr: Expr = input
+                .parse()
+                .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", input, e));
+            assert_eq!(expr.to_string(), *expected, "{}", input);
+
+            // The canonical form must parse to the same expression again.
+            let reparsed: Expr = expected
+                .parse()
+                .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", expected, e));
+            assert_eq!(reparsed.to_string(), *expected, "{}", expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_ast() {
+        let expr: Expr = "sum by (job) (rate(http_requests_total[5m])) > bool 10"
+            .parse()
+            .unwrap();
+
+        let binary = match expr {
+            Expr::Binary(b) => b,
+            e => panic!("unexpected expression {:?}", e),
+        };
+        assert_eq!(binary.op(), BinaryOp::Gt);
+        assert!(binary.returns_bool());
+        assert_eq!(binary.rhs(), &Expr::Number(10.0));
+
+        let aggregate = match binary.lhs() {
+            Expr::Aggregate(a) => a,
+            e => panic!("unexpected expression {:?}", e),
+        };
+        assert_eq!(aggregate.op(), AggregateOp::Sum);
+        assert_eq!(
+            aggregate.grouping(),
+            Some(&Grouping::By(vec![String::from("job")]))
+        );
+
+        let call = match aggregate.expr() {
+            Expr::Call(c) => c,
+            e => panic!("unexpected expression {:?}", e),
+        };
+        assert_eq!(call.function(), Function::Rate);
+        assert_eq!(
+            call.args(),
+            &[Expr::Matrix(
+                super::super::vector(Selector::new().metric("http_requests_total"))
+                    .unwrap()
+                    .range(Duration::minutes(5))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            ("", 0, "no expression found in input"),
+            ("1 +", 3, "unexpected end of input, expected expression"),
+            ("foo bar", 4, "unexpected identifier \"bar\""),
+            ("sum", 3, "unexpected end of input, expected \"(\" in aggregation"),
+            ("foo * sum", 9, "unexpected end of input, expected \"(\" in aggregation"),
+            ("min{job=\"x\"}", 3, "unexpected \"{\", expected \"(\" in aggregation"),
+            ("1 == 1", 2, "comparisons between scalars must use BOOL modifier"),
+            ("1 + bool 1", 2, "bool modifier can only be used on comparison operators"),
+            ("1 and 1", 2, "set operator \"and\" not allowed in binary scalar expression"),
+            ("1 or on(foo) bar", 2, "set operator \"or\" not allowed in binary scalar expression"),
+            ("1 + on(foo) 1", 2, "vector matching only allowed between instant vectors"),
+            ("foo and on(bar) group_left(baz) bar", 4, "no grouping allowed for \"and\" operation"),
+            ("foo * on(bar) group_left(bar) baz", 4, "label \"bar\" must not occur in ON and GROUP clause at once"),
+            ("foo[5m] + 1", 8, "binary expression must contain only scalar and instant vector types"),
+            ("-foo[5m]", 1, "unary expression only allowed on expressions of type scalar or instant vector, got \"range vector\""),
+            ("-\"string\"", 1, "unary expression only allowed on expressions of type scalar or instant vector, got \"string\""),
+            ("{}", 0, "vector selector must contain at least one non-empty matcher"),
+            (r#"{x=""}"#, 0, "vector selector must contain at least one non-empty matcher"),
+            (r#"{x=~".*"}"#, 0, "vector selector must contain at least one non-empty matcher"),
+            ("foo{__name__=\"bar\"}", 4, "metric name must not be set twice"),
+            ("foo[5m] offset 1m offset 2m", 18, "offset may not be set multiple times"),
+            ("foo @ 1 @ 2", 8, "@ <timestamp> may not be set multiple times"),
+            ("foo offset 1m [5m]", 0, "no offset modifiers allowed before range"),
+            ("foo @ 1 [5m]", 0, "no @ modifiers allowed before range"),
+            ("(foo)[5m]", 0, "ranges only allowed for vector selectors"),
+            ("rate(foo[5m])[1h]", 0, "ranges only allowed for vector selectors"),
+            ("foo[5m][1h:]", 0, "subquery is only allowed on instant vector, got range vector instead"),
+            ("1 offset 5m", 2, "offset modifier must be preceded by an instant vector selector or range vector selector or a subquery"),
+            ("foo @ Inf", 6, "timestamp out of bounds for @ modifier"),
+            ("foo[5mm]", 4, "bad number or duration syntax"),
+            ("foo[1.5m]", 4, "bad number or duration syntax"),
+            ("foo[5m30h]", 4, "bad number or duration syntax"),
+            ("foo[5m", 6, "unexpected end of input, expected \"]\" or \":\""),
+            ("foo[-5m]", 4, "unexpected \"-\", expected duration"),
+            ("unknown_function(foo)", 0, "unknown function with name \"unknown_function\""),
+            ("rate(foo)", 5, "expected type range vector in call to function \"rate\", got instant vector"),
+            ("rate(foo[5m], 1)", 0, "expected 1 argument(s) in call to \"rate\", got 2"),
+            ("round(foo, 1, 2)", 0, "expected at most 2 argument(s) in call to \"round\", got 3"),
+            ("label_join(foo)", 0, "expected at least 3 argument(s) in call to \"label_join\", got 1"),
+            ("label_join(foo, \"a\", \",\", 1)", 26, "expected type string in call to function \"label_join\", got scalar"),
+            ("sum(foo, bar)", 0, "wrong number of arguments for aggregate expression provided, expected 1, got 2"),
+            ("topk(foo)", 0, "wrong number of arguments for aggregate expression provided, expected 2, got 1"),
+            ("sum(rate(foo[5m])[5m])", 4, "ranges only allowed for vector selectors"),
+            ("sum(foo[5m])", 4, "expected type instant vector in aggregation expression, got range vector"),
+            ("topk(\"5\", foo)", 5, "expected type scalar in aggregation parameter, got string"),
+            ("count_values(5, foo)", 13, "expected type string in aggregation parameter, got scalar"),
+            ("sum by (foo:bar) (baz)", 8, "unexpected identifier \"foo:bar\", expected label"),
+            ("sum by foo (baz)", 7, "unexpected identifier \"foo\", expected \"(\" in grouping opts"),
+            ("sum(foo,", 8, "unexpected end of input, expected expression"),
+            ("(foo", 4, "unexpected end of input, expected \")\" in paren expression"),
+            ("foo * bool bar", 4, "bool modifier can only be used on comparison operators"),
+            ("foo group_left bar", 4, "unexpected identifier \"group_left\""),
+            ("foo @ start", 11, "unexpected end of input, expected \"(\" in @ modifier"),
+            ("foo * on bar", 9, "unexpected identifier \"bar\", expected \"(\" in grouping opts"),
+        ];
+
+        for (input, position, message) in cases {
+            let err = input.parse::<Expr>().unwrap_err();
+            assert_eq!(
+                (err.position(), err.message()),
+                (position, message),
+                "{}",
+                input
+            );
+        }
+
+        let err = "sum by (job) (\n  rate(http_requests_total)\n)"
+            .parse::<Expr>()
+            .unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 8));
+    }
+}
@@ -0,0 +1,775 @@
+//! A client for the Prometheus remote read protocol.
+use crate::client::{Client, Request};
+use crate::error::{ClientError, Error, RemoteReadError};
+use crate::prompb;
+use crate::response::{RangeVector, Sample};
+use crate::selector::Selector;
+use crate::util::{build_final_url, Label, NameValidationScheme, Timestamp};
+use prost::Message;
+use reqwest::header::{
+    HeaderMap, HeaderValue, IntoHeaderName, CONTENT_ENCODING, CONTENT_TYPE, USER_AGENT,
+};
+use std::collections::HashMap;
+
+const USER_AGENT_VALUE: &str = concat!("prometheus-http-query/", env!("CARGO_PKG_VERSION"));
+
+/// The type of response that is requested from the remote read endpoint.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RemoteReadResponseType {
+    /// The server decodes all samples and returns them in a single snappy-compressed
+    /// message. This is supported by all remote read endpoints.
+    #[default]
+    Samples,
+    /// The server streams the raw XOR-encoded chunks of each time series, which
+    /// requires considerably less memory on the server side. Requires Prometheus >= v2.13.
+    StreamedXorChunks,
+}
+
+impl RemoteReadResponseType {
+    fn to_prompb(self) -> prompb::ResponseType {
+        match self {
+            Self::Samples => prompb::ResponseType::Samples,
+            Self::StreamedXorChunks => prompb::ResponseType::StreamedXorChunks,
+        }
+    }
+}
+
+/// Provides a builder to read raw samples from a remote read endpoint.
+///
+/// Each query added via [`RemoteReadBuilder::query`] yields one set of time series in
+/// the result, in the order the queries were added. Note that the response is received
+/// as a whole before it is decoded, regardless of the [`RemoteReadResponseType`].
+#[derive(Clone)]
+pub struct RemoteReadBuilder<C = Client> {
+    pub(crate) client: C,
+    path: String,
+    response_type: RemoteReadResponseType,
+    headers: Option<HeaderMap<HeaderValue>>,
+    queries: Vec<prompb::Query>,
+}
+
+impl<C> RemoteReadBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        RemoteReadBuilder {
+            client,
+            path: String::from("api/v1/read"),
+            response_type: RemoteReadResponseType::default(),
+            headers: None,
+            queries: vec![],
+        }
+    }
+
+    /// Set the path of the remote read endpoint relative to the base URL of the [`Client`].
+    /// Defaults to `api/v1/read`.
+    pub fn path(mut self, path: impl std::fmt::Display) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Set the type of response that is requested from the server.
+    /// Defaults to [`RemoteReadResponseType::Samples`].
+    pub fn response_type(mut self, response_type: RemoteReadResponseType) -> Self {
+        self.response_type = response_type;
+        self
+    }
+
+    /// Include an additional header to the read request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    /// Add a query that selects all time series matching the [`Selector`] within the time
+    /// range from `start` to `end` (inclusive, e.g. Unix timestamps in seconds, see [`Timestamp`]).
+    /// This method can be called repeatedly to add more queries.<br>
+    /// Returns an error if the [`Selector`] is invalid according to
+    /// [`NameValidationScheme::Utf8`].
+    pub fn query(
+        mut self,
+        selector: &Selector,
+        start: impl Into<Timestamp>,
+        end: impl Into<Timestamp>,
+    ) -> Result<Self, Error> {
+        selector.validate(NameValidationScheme::Utf8)?;
+
+        let matchers = selector
+            .labels
+            .iter()
+            .map(|label| {
+                let (r#type, (name, value)) = match label {
+                    Label::Equal(l) => (prompb::MatcherType::Eq, l),
+                    Label::NotEqual(l) => (prompb::MatcherType::Neq, l),
+                    Label::RegexEqual(l) => (prompb::MatcherType::Re, l),
+                    Label::RegexNotEqual(l) => (prompb::MatcherType::Nre, l),
+                };
+                prompb::LabelMatcher {
+                    r#type: r#type as i32,
+                    name: name.to_string(),
+                    value: value.to_string(),
+                }
+            })
+            .collect();
+
+        self.queries.push(prompb::Query {
+            start_timestamp_ms: start.into().unix_millis(),
+            end_timestamp_ms: end.into().unix_millis(),
+            matchers,
+        });
+        Ok(self)
+    }
+
+    // Encode the queries into the body of the read request and collect the headers
+    // that are sent along with it.
+    pub(crate) fn to_request(&self) -> (Request, Vec<u8>) {
+        let body = prompb::ReadRequest {
+            queries: self.queries.clone(),
+            accepted_response_types: vec![self.response_type.to_prompb() as i32],
+        }
+        .encode_to_vec();
+
+        // Compressing a buffer in memory does not fail.
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&body)
+            .unwrap_or_default();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-protobuf"),
+        );
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("snappy"));
+        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_VALUE));
+        headers.insert(
+            "X-Prometheus-Remote-Read-Version",
+            HeaderValue::from_static("0.1.0"),
+        );
+
+        // Additional headers replace the default ones with the same name.
+        if let Some(extra) = &self.headers {
+            headers.extend(extra.clone());
+        }
+
+        let request = Request::new(self.path.clone(), vec![], Some(headers));
+        (request, body)
+    }
+
+    // Decode the body of a successful read response depending on its content type.
+    pub(crate) fn decode(
+        &self,
+        content_type: Option<&HeaderValue>,
+        body: &[u8],
+    ) -> Result<Vec<Vec<RangeVector>>, Error> {
+        let is_streamed = content_type
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("application/x-streamed-protobuf"));
+
+        if is_streamed {
+            decode_streamed(body, &self.queries)
+        } else {
+            decode_samples(body, self.queries.len())
+        }
+    }
+}
+
+impl RemoteReadBuilder {
+    /// Send the read request to the remote read endpoint (using HTTP POST).
+    /// Returns one set of time series per query, in the order the queries were added.
+    pub async fn post(self) -> Result<Vec<Vec<RangeVector>>, Error> {
+        let (request, body) = self.to_request();
+        let url = build_final_url(self.client.base_url.clone(), &request.path);
+
+        let response = self
+            .client
+            .request(reqwest::Method::POST, url)?
+            .headers(request.headers.unwrap_or_default())
+            .body(body)
+            .send()
+            .await
+            .map_err(|source| {
+                Error::Client(ClientError {
+                    message: "failed to send remote read request to server",
+                    #[cfg(not(feature = "middleware"))]
+                    source: Some(source),
+                    #[cfg(feature = "middleware")]
+                    source: match source {
+                        reqwest_middleware::Error::Middleware(_) => None,
+                        reqwest_middleware::Error::Reqwest(e) => Some(e),
+                    },
+                })
+            })?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(Error::RemoteRead(RemoteReadError {
+                status,
+                message: message.trim().to_string(),
+            }));
+        }
+
+        let content_type = response.headers().get(CONTENT_TYPE).cloned();
+
+        let body = response.bytes().await.map_err(|source| {
+            Error::Client(ClientError {
+                message: "failed to read remote read response body",
+                source: Some(source),
+            })
+        })?;
+
+        self.decode(content_type.as_ref(), &body)
+    }
+}
+
+fn decode_error(message: &'static str) -> Error {
+    Error::Client(ClientError {
+        message,
+        source: None,
+    })
+}
+
+fn to_metric(labels: Vec<prompb::Label>) -> HashMap<String, String> {
+    labels.into_iter().map(|l| (l.name, l.value)).collect()
+}
+
+fn to_sample(timestamp: i64, value: f64) -> Sample {
+    Sample::new(timestamp as f64 / 1000.0, value)
+}
+
+// Decode a snappy-compressed `ReadResponse`, i.e. the response type `SAMPLES`.
+fn decode_samples(body: &[u8], queries: usize) -> Result<Vec<Vec<RangeVector>>, Error> {
+    let buf = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|_| decode_error("failed to decompress remote read response"))?;
+
+    let response = prompb::ReadResponse::decode(buf.as_slice())
+        .map_err(|_| decode_error("failed to decode remote read response"))?;
+
+    if response.results.len() != queries {
+        return Err(decode_error(
+            "remote read response does not contain one result per query",
+        ));
+    }
+
+    response
+        .results
+        .into_iter()
+        .map(|result| {
+            result
+                .timeseries
+                .into_iter()
+                .map(|series| {
+                    if !series.histograms.is_empty() {
+                        return Err(decode_error(
+                            "native histograms are not supported in remote read responses",
+                        ));
+                    }
+                    Ok(RangeVector {
+                        metric: to_metric(series.labels),
+                        samples: series
+                            .samples
+                            .into_iter()
+                            .map(|s| to_sample(s.timestamp, s.value))
+                            .collect(),
+                        histograms: vec![],
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Decode a stream of `ChunkedReadResponse` frames, i.e. the response type
+// `STREAMED_XOR_CHUNKS`. Each frame consists of the uvarint-encoded size of the
+// message, the CRC32 (Castagnoli) checksum of the message and the message itself.
+fn decode_streamed(
+    mut body: &[u8],
+    queries: &[prompb::Query],
+) -> Result<Vec<Vec<RangeVector>>, Error> {
+    let mut results: Vec<Vec<RangeVector>> = vec![vec![]; queries.len()];
+
+    while !body.is_empty() {
+        let size = prost::encoding::decode_varint(&mut body)
+            .map_err(|_| decode_error("failed to decode remote read frame size"))?;
+
+        let size = usize::try_from(size)
+            .ok()
+            .filter(|size| body.len() >= 4 + size)
+            .ok_or_else(|| decode_error("remote read frame is truncated"))?;
+
+        let checksum = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+        let (message, rest) = body[4..].split_at(size);
+        body = rest;
+
+        if crc32c(message) != checksum {
+            return Err(decode_error("remote read frame checksum mismatch"));
+        }
+
+        let response = prompb::ChunkedReadResponse::decode(message)
+            .map_err(|_| decode_error("failed to decode remote read frame"))?;
+
+        let index = usize::try_from(response.query_index)
+            .ok()
+            .filter(|i| *i < queries.len())
+            .ok_or_else(|| decode_error("remote read frame refers to an unknown query"))?;
+
+        let query = &queries[index];
+
+        for series in response.chunked_series {
+            let mut samples = vec![];
+
+            for chunk in series.chunks {
+                if chunk.r#type != prompb::ChunkEncoding::Xor as i32 {
+                    return Err(decode_error(
+                        "native histograms are not supported in remote read responses",
+                    ));
+                }
+                let decoded = decode_xor_chunk(&chunk.data)
+                    .ok_or_else(|| decode_error("failed to decode XOR chunk"))?;
+
+                // Chunks may contain samples outside of the requested time range.
+                samples.extend(
+                    decoded
+                        .into_iter()
+                        .filter(|(t, _)| {
+                            *t >= query.start_timestamp_ms && *t <= query.end_timestamp_ms
+                        })
+                        .map(|(t, v)| to_sample(t, v)),
+                );
+            }
+
+            let metric = to_metric(series.labels);
+
+            // The chunks of a single time series may be spread across consecutive frames.
+            match results[index].last_mut() {
+                Some(last) if last.metric == metric => last.samples.extend(samples),
+                _ => results[index].push(RangeVector {
+                    metric,
+                    samples,
+                    histograms: vec![],
+                }),
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+const CASTAGNOLI_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x82f6_3b78
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32c(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, b| {
+        CASTAGNOLI_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read_bit(&mut self) -> Option<bool> {
+        let byte = self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 1 == 1;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    fn read_bits(&mut self, n: u32) -> Option<u64> {
+        let mut bits = 0u64;
+        for _ in 0..n {
+            bits = (bits << 1) | self.read_bit()? as u64;
+        }
+        Some(bits)
+    }
+
+    fn read_uvarint(&mut self) -> Option<u64> {
+        let mut x = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bits(8)?;
+            x |= (byte & 0x7f) << shift;
+            if byte < 0x80 {
+                return Some(x);
+            }
+        }
+        None
+    }
+
+    fn read_varint(&mut self) -> Option<i64> {
+        let ux = self.read_uvarint()?;
+        let x = (ux >> 1) as i64;
+        Some(if ux & 1 != 0 { !x } else { x })
+    }
+
+    // Read a value that was XOR-encoded against the previous value `v`.
+    fn read_xor(&mut self, v: &mut u64, leading: &mut u32, trailing: &mut u32) -> Option<()> {
+        if !self.read_bit()? {
+            return Some(());
+        }
+
+        if self.read_bit()? {
+            *leading = self.read_bits(5)? as u32;
+            // Zero significant bits means that all 64 bits are significant.
+            let significant = match self.read_bits(6)? as u32 {
+                0 => 64,
+                n => n,
+            };
+            *trailing = 64u32.checked_sub(*leading + significant)?;
+        }
+
+        let significant = 64 - *leading - *trailing;
+        *v ^= self.read_bits(significant)? << *trailing;
+        Some(())
+    }
+}
+
+// Decode a chunk in the XOR encoding of the Prometheus TSDB ("Gorilla" compression),
+// returning timestamps in milliseconds and values.
+fn decode_xor_chunk(data: &[u8]) -> Option<Vec<(i64, f64)>> {
+    let num = u16::from_be_bytes([*data.first()?, *data.get(1)?]) as usize;
+    let mut reader = BitReader {
+        data: &data[2..],
+        pos: 0,
+    };
+
+    let mut samples = Vec::with_capacity(num);
+    let (mut t, mut delta, mut v) = (0i64, 0i64, 0u64);
+    let (mut leading, mut trailing) = (0u32, 0u32);
+
+    for i in 0..num {
+        match i {
+            0 => {
+                t = reader.read_varint()?;
+                v = reader.read_bits(64)?;
+            }
+            1 => {
+                delta = reader.read_uvarint()? as i64;
+                t = t.wrapping_add(delta);
+                reader.read_xor(&mut v, &mut leading, &mut trailing)?;
+            }
+            _ => {
+                // The delta of deltas is prefixed by up to four bits denoting its size.
+                let mut prefix = 0u8;
+                for _ in 0..4 {
+                    prefix <<= 1;
+                    if !reader.read_bit()? {
+                        break;
+                    }
+                    prefix |= 1;
+                }
+
+                let dod = match prefix {
+                    0b0 => 0,
+                    0b10 => read_signed(&mut reader, 14)?,
+                    0b110 => read_signed(&mut reader, 17)?,
+                    0b1110 => read_signed(&mut reader, 20)?,
+                    _ => reader.read_bits(64)? as i64,
+                };
+
+                delta = delta.wrapping_add(dod);
+                t = t.wrapping_add(delta);
+                reader.read_xor(&mut v, &mut leading, &mut trailing)?;
+            }
+        }
+        samples.push((t, f64::from_bits(v)));
+    }
+
+    Some(samples)
+}
+
+// Negative numbers are encoded as two's complement using `size` bits.
+fn read_signed(reader: &mut BitReader, size: u32) -> Option<i64> {
+    let bits = reader.read_bits(size)? as i64;
+    if bits > 1 << (size - 1) {
+        Some(bits - (1 << size))
+    } else {
+        Some(bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Samples encoded by the Prometheus TSDB, covering all delta-of-delta sizes and
+    // both XOR control bit variants.
+    const XOR_CHUNK: [u8; 68] = [
+        0x00, 0x09, 0xd0, 0x0f, 0x3f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x98, 0x75, 0x30,
+        0x9b, 0xff, 0xd8, 0x1f, 0x48, 0x00, 0x77, 0xb5, 0x18, 0x10, 0x80, 0x15, 0xf0, 0x00, 0x00,
+        0x00, 0x00, 0x01, 0xe4, 0xa4, 0xc7, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf0, 0xbd, 0xc0, 0xe0,
+        0xfa, 0xf8, 0xab, 0x90, 0xf2, 0x20, 0x01, 0xd6, 0x7c, 0x9c, 0x40, 0x72, 0x49, 0xf0, 0x4f,
+        0xc6, 0xfc, 0x87, 0x91, 0x00, 0x0e, 0xb3, 0x80,
+    ];
+
+    fn xor_chunk_samples() -> Vec<(i64, f64)> {
+        vec![
+            (1000, 1.0),
+            (16000, 1.0),
+            (31000, 2.5),
+            (46500, 3.0),
+            (61400, -7.25),
+            (2061400, -7.25),
+            (2061401, 1e300),
+            (2101402, 1e300),
+            (2441403, 0.0),
+        ]
+    }
+
+    fn frame(response: &prompb::ChunkedReadResponse) -> Vec<u8> {
+        let message = response.encode_to_vec();
+        let mut frame = vec![];
+        prost::encoding::encode_varint(message.len() as u64, &mut frame);
+        frame.extend(crc32c(&message).to_be_bytes());
+        frame.extend(message);
+        frame
+    }
+
+    fn query(start: i64, end: i64) -> prompb::Query {
+        prompb::Query {
+            start_timestamp_ms: start,
+            end_timestamp_ms: end,
+            matchers: vec![],
+        }
+    }
+
+    fn labels(job: &str) -> Vec<prompb::Label> {
+        vec![
+            prompb::Label {
+                name: String::from("__name__"),
+                value: String::from("up"),
+            },
+            prompb::Label {
+                name: String::from("job"),
+                value: job.to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+    }
+
+    #[test]
+    fn test_decode_xor_chunk() {
+        assert_eq!(decode_xor_chunk(&XOR_CHUNK), Some(xor_chunk_samples()));
+        assert_eq!(decode_xor_chunk(&[0, 0]), Some(vec![]));
+        assert_eq!(decode_xor_chunk(&XOR_CHUNK[..40]), None);
+        assert_eq!(decode_xor_chunk(&[0]), None);
+    }
+
+    #[test]
+    fn test_query_matchers() {
+        let selector = Selector::new()
+            .metric("up")
+            .ne("job", "node")
+            .regex_eq("instance", "a.+")
+            .regex_ne("mode", "idle");
+        let builder = RemoteReadBuilder::new(Client::default())
+            .query(&selector, 10, 20)
+            .unwrap();
+        let query = &builder.queries[0];
+        assert_eq!(query.start_timestamp_ms, 10000);
+        assert_eq!(query.end_timestamp_ms, 20000);
+        let matchers: Vec<(i32, &str, &str)> = query
+            .matchers
+            .iter()
+            .map(|m| (m.r#type, m.name.as_str(), m.value.as_str()))
+            .collect();
+        assert_eq!(
+            matchers,
+            vec![
+                (0, "__name__", "up"),
+                (1, "job", "node"),
+                (2, "instance", "a.+"),
+                (3, "mode", "idle")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_validates_selector() {
+        let selector = Selector::new().metric("");
+        let result = RemoteReadBuilder::new(Client::default()).query(&selector, 10, 20);
+        assert!(matches!(result, Err(Error::InvalidName(_))));
+    }
+
+    #[test]
+    fn test_decode_samples() {
+        let response = prompb::ReadResponse {
+            results: vec![
+                prompb::QueryResult {
+                    timeseries: vec![prompb::TimeSeries {
+                        labels: labels("node"),
+                        samples: vec![
+                            prompb::Sample {
+                                value: 1.0,
+                                timestamp: 1500,
+                            },
+                            prompb::Sample {
+                                value: 0.0,
+                                timestamp: 3000,
+                            },
+                        ],
+                        histograms: vec![],
+                    }],
+                },
+                prompb::QueryResult { timeseries: vec![] },
+            ],
+        };
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&response.encode_to_vec())
+            .unwrap();
+
+        let result = decode_samples(&body, 2).unwrap();
+        assert_eq!(result.len(), 2);
+        assert!(result[1].is_empty());
+        let series = &result[0][0];
+        assert_eq!(series.metric().get("job").unwrap(), "node");
+        assert_eq!(
+            series.samples(),
+            &[Sample::new(1.5, 1.0), Sample::new(3.0, 0.0)]
+        );
+
+        assert!(decode_samples(&body, 1).is_err());
+        assert!(decode_samples(b"invalid", 2).is_err());
+    }
+
+    #[test]
+    fn test_decode_samples_rejects_histograms() {
+        let response = prompb::ReadResponse {
+            results: vec![prompb::QueryResult {
+                timeseries: vec![prompb::TimeSeries {
+                    labels: labels("node"),
+                    samples: vec![],
+                    histograms: vec![vec![0x08, 0x01]],
+                }],
+            }],
+        };
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&response.encode_to_vec())
+            .unwrap();
+        assert!(decode_samples(&body, 1).is_err());
+    }
+
+    #[test]
+    fn test_decode_streamed() {
+        let chunk = prompb::Chunk {
+            min_time_ms: 1000,
+            max_time_ms: 2441403,
+            r#type: prompb::ChunkEncoding::Xor as i32,
+            data: XOR_CHUNK.to_vec(),
+        };
+        let queries = vec![query(0, 10000000), query(16000, 46500)];
+
+        let mut body = frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![prompb::ChunkedSeries {
+                labels: labels("node"),
+                chunks: vec![chunk.clone()],
+            }],
+            query_index: 0,
+        });
+        // The same time series continued in the next frame.
+        body.extend(frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![prompb::ChunkedSeries {
+                labels: labels("node"),
+                chunks: vec![chunk.clone()],
+            }],
+            query_index: 0,
+        }));
+        body.extend(frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![
+                prompb::ChunkedSeries {
+                    labels: labels("node"),
+                    chunks: vec![chunk.clone()],
+                },
+                prompb::ChunkedSeries {
+                    labels: labels("prometheus"),
+                    chunks: vec![chunk],
+                },
+            ],
+            query_index: 1,
+        }));
+
+        let result = decode_streamed(&body, &queries).unwrap();
+        assert_eq!(result.len(), 2);
+
+        assert_eq!(result[0].len(), 1);
+        assert_eq!(result[0][0].samples().len(), 18);
+
+        assert_eq!(result[1].len(), 2);
+        assert_eq!(result[1][1].metric().get("job").unwrap(), "prometheus");
+        assert_eq!(
+            result[1][0].samples(),
+            &[
+                Sample::new(16.0, 1.0),
+                Sample::new(31.0, 2.5),
+                Sample::new(46.5, 3.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_streamed_errors() {
+        let queries = vec![query(0, 1000)];
+        let response = prompb::ChunkedReadResponse {
+            chunked_series: vec![],
+            query_index: 0,
+        };
+
+        let mut corrupted = frame(&response);
+        corrupted[1] ^= 0xff;
+        assert!(decode_streamed(&corrupted, &queries).is_err());
+
+        let truncated = frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![prompb::ChunkedSeries {
+                labels: labels("node"),
+                chunks: vec![],
+            }],
+            query_index: 0,
+        });
+        assert!(decode_streamed(&truncated[..truncated.len() - 1], &queries).is_err());
+
+        let unknown_query = frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![],
+            query_index: 1,
+        });
+        assert!(decode_streamed(&unknown_query, &queries).is_err());
+
+        let histogram = frame(&prompb::ChunkedReadResponse {
+            chunked_series: vec![prompb::ChunkedSeries {
+                labels: labels("node"),
+                chunks: vec![prompb::Chunk {
+                    min_time_ms: 0,
+                    max_time_ms: 0,
+                    r#type: prompb::ChunkEncoding::Histogram as i32,
+                    data: vec![0, 0],
+                }],
+            }],
+            query_index: 0,
+        });
+        assert!(decode_streamed(&histogram, &queries).is_err());
+    }
+}
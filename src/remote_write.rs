@@ -0,0 +1,376 @@
+//! A sender for the Prometheus remote write protocol.
+use crate::client::{Client, Request};
+use crate::error::{ClientError, Error, RemoteWriteError};
+use crate::prompb;
+use crate::response::Sample;
+use crate::util::build_final_url;
+use prost::Message;
+use reqwest::header::{
+    HeaderMap, HeaderValue, IntoHeaderName, CONTENT_ENCODING, CONTENT_TYPE, USER_AGENT,
+};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+
+const USER_AGENT_VALUE: &str = concat!("prometheus-http-query/", env!("CARGO_PKG_VERSION"));
+
+/// The version of the remote write protocol that is used to encode write requests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RemoteWriteVersion {
+    /// [Remote write 1.0](https://prometheus.io/docs/specs/prw/remote_write_spec/),
+    /// supported by Prometheus and virtually all compatible receivers.
+    #[default]
+    V1,
+    /// [Remote write 2.0](https://prometheus.io/docs/specs/prw/remote_write_spec_2_0/),
+    /// which deduplicates label names and values using a symbols table.
+    V2,
+}
+
+impl RemoteWriteVersion {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::V1 => "application/x-protobuf",
+            Self::V2 => "application/x-protobuf;proto=io.prometheus.write.v2.Request",
+        }
+    }
+
+    fn header_value(&self) -> &'static str {
+        match self {
+            Self::V1 => "0.1.0",
+            Self::V2 => "2.0.0",
+        }
+    }
+}
+
+/// A single time series, i.e. a label set and its samples.
+type Series = (Vec<(String, String)>, Vec<Sample>);
+
+/// Provides a builder to collect time series and send them to a remote write receiver.
+///
+/// Samples are sent in batches of at most [`RemoteWriteBuilder::max_samples_per_request`]
+/// samples. Batches are sent one after another and sending stops at the first failure.
+/// Note that batches that were sent before the failure have already been written.
+#[derive(Clone)]
+pub struct RemoteWriteBuilder<C = Client> {
+    client: C,
+    path: String,
+    version: RemoteWriteVersion,
+    max_samples_per_request: usize,
+    headers: Option<HeaderMap<HeaderValue>>,
+    series: Vec<Series>,
+}
+
+impl<C> RemoteWriteBuilder<C> {
+    pub(crate) fn new(client: C) -> Self {
+        RemoteWriteBuilder {
+            client,
+            path: String::from("api/v1/write"),
+            version: RemoteWriteVersion::default(),
+            max_samples_per_request: 2000,
+            headers: None,
+            series: vec![],
+        }
+    }
+
+    /// Set the path of the remote write endpoint relative to the base URL of the [`Client`],
+    /// e.g. `api/v1/push` for Mimir or `api/v1/receive` for Thanos.
+    /// Defaults to `api/v1/write`.
+    pub fn path(mut self, path: impl std::fmt::Display) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Set the version of the remote write protocol. Defaults to [`RemoteWriteVersion::V1`].
+    pub fn version(mut self, version: RemoteWriteVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the maximum number of samples per write request. Defaults to 2000.
+    /// A value of zero is treated as one.
+    pub fn max_samples_per_request(mut self, max: usize) -> Self {
+        self.max_samples_per_request = max.max(1);
+        self
+    }
+
+    /// Include an additional header to each write request.
+    pub fn header<K: IntoHeaderName, T: Into<HeaderValue>>(mut self, name: K, value: T) -> Self {
+        self.headers
+            .get_or_insert_with(Default::default)
+            .append(name, value.into());
+        self
+    }
+
+    /// Add a time series, i.e. a label set (including the metric name as label `__name__`)
+    /// and its samples. This method can be called repeatedly to add more time series.
+    /// Labels are sorted by name and samples by timestamp before they are sent as required
+    /// by the remote write specification.
+    pub fn series<L, K, V, S>(mut self, labels: L, samples: S) -> Self
+    where
+        L: IntoIterator<Item = (K, V)>,
+        K: std::fmt::Display,
+        V: std::fmt::Display,
+        S: IntoIterator,
+        S::Item: Borrow<Sample>,
+    {
+        let mut labels: Vec<(String, String)> = labels
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        labels.sort();
+
+        let mut samples: Vec<Sample> = samples.into_iter().map(|s| *s.borrow()).collect();
+        samples.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        self.series.push((labels, samples));
+        self
+    }
+
+    // Encode the time series into one request body per batch and collect the headers
+    // that are sent along with each write request.
+    pub(crate) fn into_requests(self) -> (C, Request, Vec<Vec<u8>>) {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(self.version.content_type()),
+        );
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("snappy"));
+        headers.insert(USER_AGENT, HeaderValue::from_static(USER_AGENT_VALUE));
+        headers.insert(
+            "X-Prometheus-Remote-Write-Version",
+            HeaderValue::from_static(self.version.header_value()),
+        );
+
+        // Additional headers replace the default ones with the same name.
+        if let Some(extra) = self.headers {
+            headers.extend(extra);
+        }
+
+        let bodies = batches(self.series, self.max_samples_per_request)
+            .iter()
+            .map(|batch| encode(batch, self.version))
+            .collect();
+
+        let request = Request::new(self.path, vec![], Some(headers));
+        (self.client, request, bodies)
+    }
+}
+
+impl RemoteWriteBuilder {
+    /// Encode all time series and send them to the remote write receiver (using HTTP POST).
+    pub async fn send(self) -> Result<(), Error> {
+        let (client, request, bodies) = self.into_requests();
+        let url = build_final_url(client.base_url.clone(), &request.path);
+        let headers = request.headers.unwrap_or_default();
+
+        for body in bodies {
+            let response = client
+                .request(reqwest::Method::POST, url.clone())?
+                .headers(headers.clone())
+                .body(body)
+                .send()
+                .await
+                .map_err(|source| {
+                    Error::Client(ClientError {
+                        message: "failed to send remote write request to receiver",
+                        #[cfg(not(feature = "middleware"))]
+                        source: Some(source),
+                        #[cfg(feature = "middleware")]
+                        source: match source {
+                            reqwest_middleware::Error::Middleware(_) => None,
+                            reqwest_middleware::Error::Reqwest(e) => Some(e),
+                        },
+                    })
+                })?;
+
+            let status = response.status();
+
+            if !status.is_success() {
+                let message = response.text().await.unwrap_or_default();
+                return Err(Error::RemoteWrite(RemoteWriteError {
+                    status,
+                    message: message.trim().to_string(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Split the time series into batches that contain at most `max` samples each.
+// The samples of a time series may be spread across several batches.
+fn batches(series: Vec<Series>, max: usize) -> Vec<Vec<Series>> {
+    let mut batches = vec![];
+    let mut batch: Vec<Series> = vec![];
+    let mut count = 0;
+
+    for (labels, samples) in series {
+        for chunk in samples.chunks(max) {
+            if count + chunk.len() > max {
+                batches.push(std::mem::take(&mut batch));
+                count = 0;
+            }
+            count += chunk.len();
+            batch.push((labels.clone(), chunk.to_vec()));
+        }
+    }
+
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    batches
+}
+
+fn to_prompb_samples(samples: &[Sample]) -> Vec<prompb::Sample> {
+    samples
+        .iter()
+        .map(|s| prompb::Sample {
+            value: s.value,
+            timestamp: (s.timestamp * 1000.0).round() as i64,
+        })
+        .collect()
+}
+
+// Return the reference of a string in the symbols table of a remote write 2.0
+// request and add the string to the table if it is not present yet.
+fn symbolize<'a>(s: &'a str, symbols: &mut Vec<String>, refs: &mut HashMap<&'a str, u32>) -> u32 {
+    *refs.entry(s).or_insert_with(|| {
+        symbols.push(s.to_string());
+        (symbols.len() - 1) as u32
+    })
+}
+
+// Encode a batch of time series as protobuf message and compress it using
+// the snappy block format.
+fn encode(batch: &[Series], version: RemoteWriteVersion) -> Vec<u8> {
+    let buf = match version {
+        RemoteWriteVersion::V1 => prompb::WriteRequest {
+            timeseries: batch
+                .iter()
+                .map(|(labels, samples)| prompb::TimeSeries {
+                    labels: labels
+                        .iter()
+                        .map(|(name, value)| prompb::Label {
+                            name: name.clone(),
+                            value: value.clone(),
+                        })
+                        .collect(),
+                    samples: to_prompb_samples(samples),
+                    histograms: vec![],
+                })
+                .collect(),
+        }
+        .encode_to_vec(),
+        RemoteWriteVersion::V2 => {
+            // The first symbol must always be an empty string.
+            let mut symbols = vec![String::new()];
+            let mut refs: HashMap<&str, u32> = HashMap::from([("", 0)]);
+
+            let timeseries = batch
+                .iter()
+                .map(|(labels, samples)| prompb::v2::TimeSeries {
+                    labels_refs: labels
+                        .iter()
+                        .flat_map(|(name, value)| [name, value])
+                        .map(|s| symbolize(s, &mut symbols, &mut refs))
+                        .collect(),
+                    samples: to_prompb_samples(samples),
+                })
+                .collect();
+
+            prompb::v2::Request {
+                symbols,
+                timeseries,
+            }
+            .encode_to_vec()
+        }
+    };
+
+    snap::raw::Encoder::new()
+        .compress_vec(&buf)
+        .expect("snappy compression of a write request does not fail")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(name: &str, n: usize) -> Series {
+        let samples = (0..n).map(|i| Sample::new(i as f64, i as f64)).collect();
+        (vec![(String::from("__name__"), name.to_string())], samples)
+    }
+
+    fn decompress(body: &[u8]) -> Vec<u8> {
+        snap::raw::Decoder::new().decompress_vec(body).unwrap()
+    }
+
+    #[test]
+    fn test_batches() {
+        let result = batches(vec![series("a", 3), series("b", 4)], 5);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].len(), 1);
+        assert_eq!(result[0][0].1.len(), 3);
+        assert_eq!(result[1].len(), 1);
+        assert_eq!(result[1][0].1.len(), 4);
+
+        let result = batches(vec![series("a", 5), series("b", 1)], 2);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[2].len(), 2);
+        assert_eq!(result[2][0].0[0].1, "a");
+        assert_eq!(result[2][1].0[0].1, "b");
+
+        assert!(batches(vec![], 2).is_empty());
+    }
+
+    #[test]
+    fn test_series_are_sorted() {
+        let builder = Client::default().remote_write().series(
+            [("job", "node"), ("__name__", "up")],
+            [Sample::new(2.0, 1.0), Sample::new(1.0, 0.0)],
+        );
+        let (labels, samples) = &builder.series[0];
+        assert_eq!(labels[0].0, "__name__");
+        assert_eq!(labels[1].0, "job");
+        assert_eq!(samples[0].timestamp(), 1.0);
+        assert_eq!(samples[1].timestamp(), 2.0);
+    }
+
+    #[test]
+    fn test_encode_v1() {
+        let body = encode(&[series("up", 2)], RemoteWriteVersion::V1);
+        let request = prompb::WriteRequest::decode(decompress(&body).as_slice()).unwrap();
+        assert_eq!(request.timeseries.len(), 1);
+        let ts = &request.timeseries[0];
+        assert_eq!(ts.labels[0].name, "__name__");
+        assert_eq!(ts.labels[0].value, "up");
+        assert_eq!(ts.samples.len(), 2);
+        assert_eq!(ts.samples[1].timestamp, 1000);
+        assert_eq!(ts.samples[1].value, 1.0);
+    }
+
+    #[test]
+    fn test_encode_v2() {
+        let body = encode(
+            &[series("up", 1), series("down", 1)],
+            RemoteWriteVersion::V2,
+        );
+        let request = prompb::v2::Request::decode(decompress(&body).as_slice()).unwrap();
+        assert_eq!(request.symbols, vec!["", "__name__", "up", "down"]);
+        assert_eq!(request.timeseries[0].labels_refs, vec![1, 2]);
+        assert_eq!(request.timeseries[1].labels_refs, vec![1, 3]);
+        assert_eq!(request.timeseries[1].samples[0].timestamp, 0);
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        let error = |status| RemoteWriteError {
+            status,
+            message: String::new(),
+        };
+        assert!(error(reqwest::StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+        assert!(error(reqwest::StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(!error(reqwest::StatusCode::BAD_REQUEST).is_retryable());
+        assert!(!error(reqwest::StatusCode::UNAUTHORIZED).is_retryable());
+    }
+}
@@ -1,6 +1,9 @@
 //! All types that are returned when querying the Prometheus API.
-use crate::util::{AlertState, RuleHealth, TargetHealth};
+use crate::decode::{self, Value};
+pub use crate::decode::{SAMPLES_FIELD, TIMESTAMP_FIELD, VALUE_FIELD};
+use crate::util::{AlertState, RuleHealth, TargetHealth, Timestamp};
 use enum_as_inner::EnumAsInner;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fmt;
@@ -36,6 +39,14 @@ mod de {
     }
 
     // This function is used to deserialize a specific datetime string like "20191102-16:19:59".
+    // Durations in the AST returned by the parse_query endpoint are given in milliseconds.
+    pub(super) fn deserialize_millis<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        i64::deserialize(deserializer).map(Duration::milliseconds)
+    }
+
     pub(super) fn deserialize_build_info_date<'de, D>(
         deserializer: D,
     ) -> Result<PrimitiveDateTime, D::Error>
@@ -77,7 +88,7 @@ mod de {
         let mut duration_iter = raw_str.chars().peekable();
 
         while let Some(item) = duration_iter.next() {
-            if ('0'..='9').contains(&item) {
+            if item.is_ascii_digit() {
                 raw_num.push(item);
                 continue;
             }
@@ -121,11 +132,213 @@ mod de {
 #[serde(tag = "status")]
 pub(crate) enum ApiResponse<D> {
     #[serde(alias = "success")]
-    Success { data: D },
+    Success {
+        data: D,
+        #[serde(default)]
+        warnings: Vec<Annotation>,
+        #[serde(default)]
+        infos: Vec<Annotation>,
+    },
     #[serde(alias = "error")]
     Error(crate::error::PrometheusError),
 }
 
+/// A warning or info that Prometheus attached to a successful API response.
+///
+/// Annotations that are raised by the PromQL engine (e.g. `PromQL warning: ... (1:6)`)
+/// are split into their message and the position in the query they refer to. Other
+/// annotations, e.g. sent by Thanos when a store failed to respond, only consist of
+/// a message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub struct Annotation {
+    text: String,
+    source: Option<String>,
+}
+
+impl From<String> for Annotation {
+    fn from(text: String) -> Self {
+        Annotation { text, source: None }
+    }
+}
+
+impl Annotation {
+    const PROMQL_PREFIXES: [&'static str; 2] = ["PromQL warning: ", "PromQL info: "];
+
+    // Attribute the annotation to one of the sources of a fan-out query.
+    pub(crate) fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Returns the annotation as it was sent by the server.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Check if the annotation was raised by the PromQL engine.
+    pub fn is_promql(&self) -> bool {
+        Self::PROMQL_PREFIXES
+            .iter()
+            .any(|prefix| self.text.starts_with(prefix))
+    }
+
+    /// Returns the message of the annotation, i.e. without the `PromQL warning: ` or
+    /// `PromQL info: ` prefix and the position of annotations raised by the PromQL engine.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::response::Annotation;
+    ///
+    /// let text = r#"PromQL info: metric might not be a counter, name does not end in _total/_sum/_count/_bucket: "foo" (1:6)"#;
+    /// let annotation = Annotation::from(text.to_string());
+    ///
+    /// assert!(annotation.is_promql());
+    /// assert_eq!(annotation.position(), Some((1, 6)));
+    /// assert!(annotation.message().starts_with("metric might not be a counter"));
+    /// assert!(annotation.message().ends_with(r#""foo""#));
+    /// ```
+    pub fn message(&self) -> &str {
+        let text = match self.split_position() {
+            Some((text, _)) => text,
+            None => &self.text,
+        };
+        Self::PROMQL_PREFIXES
+            .iter()
+            .find_map(|prefix| text.strip_prefix(prefix))
+            .unwrap_or(text)
+    }
+
+    /// Returns the position (line and column, starting at 1) in the query that an
+    /// annotation raised by the PromQL engine refers to.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.split_position().map(|(_, position)| position)
+    }
+
+    /// Returns the name of the source that sent the annotation in the context of a
+    /// fan-out query, see [`fanout`](crate::fanout).
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    // Split an annotation of the PromQL engine into the text before the position
+    // and the position, i.e. the trailing ` (line:column)`.
+    fn split_position(&self) -> Option<(&str, (usize, usize))> {
+        if !self.is_promql() {
+            return None;
+        }
+        let (text, position) = self.text.strip_suffix(')')?.rsplit_once(" (")?;
+        let (line, column) = position.split_once(':')?;
+        Some((text, (line.parse().ok()?, column.parse().ok()?)))
+    }
+}
+
+impl AsRef<str> for Annotation {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl PartialEq<&str> for Annotation {
+    fn eq(&self, other: &&str) -> bool {
+        self.source.is_none() && self.text == *other
+    }
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}: {}", source, self.text),
+            None => f.write_str(&self.text),
+        }
+    }
+}
+
+/// The data of a successful API response together with the warnings and infos that
+/// Prometheus attached to it. It dereferences to the data, so it can mostly be used
+/// like the data itself.
+///
+/// ```rust
+/// use prometheus_http_query::Client;
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() -> Result<(), anyhow::Error> {
+///     let client = Client::default();
+///
+///     let response = client.label_names().get().await?;
+///
+///     for warning in response.warnings() {
+///         println!("warning: {}", warning);
+///     }
+///
+///     for name in response.iter() {
+///         println!("{}", name);
+///     }
+///
+///     let names: Vec<String> = response.into_data();
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotated<T> {
+    pub(crate) data: T,
+    pub(crate) warnings: Vec<Annotation>,
+    pub(crate) infos: Vec<Annotation>,
+}
+
+impl<T> Annotated<T> {
+    /// Returns a reference to the data of the response.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Returns the data of the response when ownership is required.
+    pub fn into_data(self) -> T {
+        self.data
+    }
+
+    /// Return the warnings that Prometheus attached to the response, e.g. when the
+    /// result is incomplete.
+    pub fn warnings(&self) -> &[Annotation] {
+        &self.warnings
+    }
+
+    /// Return the informational annotations that Prometheus attached to the response.
+    pub fn infos(&self) -> &[Annotation] {
+        &self.infos
+    }
+
+    /// Check if the response contains any warnings.
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
+    pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Annotated<U> {
+        Annotated {
+            data: f(self.data),
+            warnings: self.warnings,
+            infos: self.infos,
+        }
+    }
+}
+
+impl<T> std::ops::Deref for Annotated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T: IntoIterator> IntoIterator for Annotated<T> {
+    type Item = T::Item;
+    type IntoIter = T::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Stats {
     timings: Timings,
@@ -140,6 +353,36 @@ impl Stats {
     pub fn samples(&self) -> &Samples {
         &self.samples
     }
+
+    // Aggregate the statistics of two queries that were evaluated separately.
+    pub(crate) fn merge(self, other: Stats) -> Stats {
+        let timings = Timings {
+            eval_total_time: self.timings.eval_total_time + other.timings.eval_total_time,
+            result_sort_time: self.timings.result_sort_time + other.timings.result_sort_time,
+            query_preparation_time: self.timings.query_preparation_time
+                + other.timings.query_preparation_time,
+            inner_eval_time: self.timings.inner_eval_time + other.timings.inner_eval_time,
+            exec_queue_time: self.timings.exec_queue_time + other.timings.exec_queue_time,
+            exec_total_time: self.timings.exec_total_time + other.timings.exec_total_time,
+        };
+        let total_queryable_samples_per_step = match (
+            self.samples.total_queryable_samples_per_step,
+            other.samples.total_queryable_samples_per_step,
+        ) {
+            (Some(mut a), Some(b)) => {
+                a.extend(b);
+                Some(a)
+            }
+            (a, b) => a.or(b),
+        };
+        let samples = Samples {
+            total_queryable_samples_per_step,
+            total_queryable_samples: self.samples.total_queryable_samples
+                + other.samples.total_queryable_samples,
+            peak_samples: self.samples.peak_samples.max(other.samples.peak_samples),
+        };
+        Stats { timings, samples }
+    }
 }
 
 #[derive(Debug, Copy, Clone, Deserialize)]
@@ -232,6 +475,10 @@ pub struct PromqlResult {
     #[serde(flatten)]
     pub(crate) data: Data,
     pub(crate) stats: Option<Stats>,
+    #[serde(skip)]
+    pub(crate) warnings: Vec<Annotation>,
+    #[serde(skip)]
+    pub(crate) infos: Vec<Annotation>,
 }
 
 impl PromqlResult {
@@ -245,10 +492,188 @@ impl PromqlResult {
         self.stats.as_ref()
     }
 
+    /// Return the warnings that Prometheus attached to the response, e.g. when the
+    /// result is incomplete or a PromQL annotation of type warning was raised.
+    pub fn warnings(&self) -> &[Annotation] {
+        &self.warnings
+    }
+
+    /// Return the informational annotations that Prometheus attached to the response,
+    /// e.g. when a PromQL annotation of type info was raised.
+    pub fn infos(&self) -> &[Annotation] {
+        &self.infos
+    }
+
+    /// Check if the response contains any warnings.
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+
     /// Returns the inner types when ownership is required
     pub fn into_inner(self) -> (Data, Option<Stats>) {
         (self.data, self.stats)
     }
+
+    /// Decode each time series of this result into a user-defined type that implements
+    /// [`serde::Deserialize`]. The labels of a time series are mapped onto the fields of the
+    /// type by name and label values are parsed if a field is not a string, e.g. an integer,
+    /// a float or a bool. Optional fields are `None` if a label is missing.
+    ///
+    /// The samples are mapped onto the designated fields [`VALUE_FIELD`] and
+    /// [`TIMESTAMP_FIELD`] (instant vectors and scalars) or [`SAMPLES_FIELD`] (range vectors,
+    /// a sequence of `(timestamp, value)` pairs). Native histogram samples are not decoded.
+    ///
+    /// Missing labels and label values that cannot be parsed are returned as [`Error::Decode`](crate::Error::Decode),
+    /// see [`DecodeErrorKind`](crate::error::DecodeErrorKind).
+    ///
+    /// ```rust
+    /// use prometheus_http_query::Client;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Up {
+    ///     job: String,
+    ///     instance: String,
+    ///     #[serde(rename = "__value__")]
+    ///     value: f64,
+    ///     #[serde(rename = "__timestamp__")]
+    ///     timestamp: f64,
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), anyhow::Error> {
+    ///     let client = Client::default();
+    ///
+    ///     let targets: Vec<Up> = client.query("up").get().await?.decode()?;
+    ///
+    ///     // Or decode the result directly.
+    ///     let targets: Vec<Up> = client.query("up").get_as().await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<Vec<T>, crate::error::Error> {
+        let result = match &self.data {
+            Data::Vector(vector) => vector
+                .iter()
+                .map(|series| {
+                    let fields = series.sample.iter().flat_map(|sample| {
+                        [
+                            (TIMESTAMP_FIELD, Value::Float(sample.timestamp)),
+                            (VALUE_FIELD, Value::Float(sample.value)),
+                        ]
+                    });
+                    decode::decode(Some(&series.metric), fields)
+                })
+                .collect(),
+            Data::Matrix(matrix) => matrix
+                .iter()
+                .map(|series| {
+                    let fields = [(SAMPLES_FIELD, Value::Samples(&series.samples))];
+                    decode::decode(Some(&series.metric), fields)
+                })
+                .collect(),
+            Data::Scalar(sample) => {
+                let fields = [
+                    (TIMESTAMP_FIELD, Value::Float(sample.timestamp)),
+                    (VALUE_FIELD, Value::Float(sample.value)),
+                ];
+                decode::decode(None, fields).map(|decoded| vec![decoded])
+            }
+            Data::String(sample) => {
+                let fields = [
+                    (TIMESTAMP_FIELD, Value::Float(sample.timestamp)),
+                    (VALUE_FIELD, Value::Label(&sample.value)),
+                ];
+                decode::decode(None, fields).map(|decoded| vec![decoded])
+            }
+        };
+        result.map_err(crate::error::Error::Decode)
+    }
+
+    // Stitch the results of range queries over consecutive sub-ranges back together.
+    // Series are matched by their label set, samples that occur in more than one
+    // result are de-duplicated by timestamp.
+    pub(crate) fn merge(results: Vec<PromqlResult>) -> PromqlResult {
+        let mut series: Vec<RangeVector> = vec![];
+        let mut index: HashMap<Vec<(String, String)>, usize> = HashMap::new();
+        let mut other = None;
+        let mut stats: Option<Stats> = None;
+        let mut warnings: Vec<Annotation> = vec![];
+        let mut infos: Vec<Annotation> = vec![];
+
+        for result in results {
+            match result.data {
+                Data::Matrix(matrix) => {
+                    for range_vector in matrix {
+                        let mut key: Vec<(String, String)> = range_vector
+                            .metric
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .collect();
+                        key.sort_unstable();
+                        match index.get(&key) {
+                            Some(&i) => {
+                                series[i].samples.extend(range_vector.samples);
+                                series[i].histograms.extend(range_vector.histograms);
+                            }
+                            None => {
+                                index.insert(key, series.len());
+                                series.push(range_vector);
+                            }
+                        }
+                    }
+                }
+                data => {
+                    other.get_or_insert(data);
+                }
+            }
+
+            stats = match (stats, result.stats) {
+                (Some(a), Some(b)) => Some(a.merge(b)),
+                (a, b) => a.or(b),
+            };
+
+            for warning in result.warnings {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+
+            for info in result.infos {
+                if !infos.contains(&info) {
+                    infos.push(info);
+                }
+            }
+        }
+
+        for range_vector in series.iter_mut() {
+            range_vector
+                .samples
+                .sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+            range_vector
+                .samples
+                .dedup_by(|a, b| a.timestamp == b.timestamp);
+            range_vector
+                .histograms
+                .sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+            range_vector
+                .histograms
+                .dedup_by(|a, b| a.timestamp == b.timestamp);
+        }
+
+        let data = match other {
+            Some(data) if series.is_empty() => data,
+            _ => Data::Matrix(series),
+        };
+
+        PromqlResult {
+            data,
+            stats,
+            warnings,
+            infos,
+        }
+    }
 }
 
 /// A wrapper for possible result types of expression queries ([`Client::query`](crate::Client::query) and [`Client::query_range`](crate::Client::query_range)).
@@ -261,6 +686,8 @@ pub enum Data {
     Matrix(Vec<RangeVector>),
     #[serde(alias = "scalar")]
     Scalar(Sample),
+    #[serde(alias = "string")]
+    String(StringSample),
 }
 
 impl Data {
@@ -270,16 +697,45 @@ impl Data {
             Data::Vector(v) => v.is_empty(),
             Data::Matrix(v) => v.is_empty(),
             Data::Scalar(_) => false,
+            Data::String(_) => false,
         }
     }
 }
 
 /// A single time series containing a single data point/sample.
+/// The data point is either a float [`Sample`] or a native [`HistogramSample`].
 #[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "RawInstantVector")]
 pub struct InstantVector {
     pub(crate) metric: HashMap<String, String>,
+    pub(crate) sample: Option<Sample>,
+    pub(crate) histogram: Option<HistogramSample>,
+}
+
+// The wire format of an [`InstantVector`], which must contain at least one kind of sample.
+#[derive(Deserialize)]
+struct RawInstantVector {
+    metric: HashMap<String, String>,
     #[serde(alias = "value")]
-    pub(crate) sample: Sample,
+    sample: Option<Sample>,
+    histogram: Option<HistogramSample>,
+}
+
+impl TryFrom<RawInstantVector> for InstantVector {
+    type Error = String;
+
+    fn try_from(raw: RawInstantVector) -> Result<Self, Self::Error> {
+        if raw.sample.is_none() && raw.histogram.is_none() {
+            return Err(String::from(
+                "instant vector contains neither a float sample nor a native histogram sample",
+            ));
+        }
+        Ok(InstantVector {
+            metric: raw.metric,
+            sample: raw.sample,
+            histogram: raw.histogram,
+        })
+    }
 }
 
 impl InstantVector {
@@ -289,23 +745,40 @@ impl InstantVector {
         &self.metric
     }
 
-    /// Returns a reference to the sample of this time series.
-    pub fn sample(&self) -> &Sample {
-        &self.sample
+    /// Returns a reference to the float sample of this time series or `None`
+    /// if this time series contains a native histogram sample instead.
+    pub fn sample(&self) -> Option<&Sample> {
+        self.sample.as_ref()
+    }
+
+    /// Returns a reference to the native histogram sample of this time series
+    /// or `None` if this time series contains a float sample instead.
+    pub fn histogram(&self) -> Option<&HistogramSample> {
+        self.histogram.as_ref()
     }
 
     /// Returns the inner types when ownership is required
-    pub fn into_inner(self) -> (HashMap<String, String>, Sample) {
-        (self.metric, self.sample)
+    pub fn into_inner(
+        self,
+    ) -> (
+        HashMap<String, String>,
+        Option<Sample>,
+        Option<HistogramSample>,
+    ) {
+        (self.metric, self.sample, self.histogram)
     }
 }
 
 /// A single time series containing a range of data points/samples.
+/// Float samples and native histogram samples are kept in separate collections,
+/// both ordered by timestamp.
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct RangeVector {
     pub(crate) metric: HashMap<String, String>,
-    #[serde(alias = "values")]
+    #[serde(alias = "values", default)]
     pub(crate) samples: Vec<Sample>,
+    #[serde(default)]
+    pub(crate) histograms: Vec<HistogramSample>,
 }
 
 impl RangeVector {
@@ -315,14 +788,19 @@ impl RangeVector {
         &self.metric
     }
 
-    /// Returns a reference to the set of samples of this time series.
+    /// Returns a reference to the set of float samples of this time series.
     pub fn samples(&self) -> &[Sample] {
         &self.samples
     }
 
+    /// Returns a reference to the set of native histogram samples of this time series.
+    pub fn histograms(&self) -> &[HistogramSample] {
+        &self.histograms
+    }
+
     /// Returns the inner types when ownership is required
-    pub fn into_inner(self) -> (HashMap<String, String>, Vec<Sample>) {
-        (self.metric, self.samples)
+    pub fn into_inner(self) -> (HashMap<String, String>, Vec<Sample>, Vec<HistogramSample>) {
+        (self.metric, self.samples, self.histograms)
     }
 }
 
@@ -335,6 +813,12 @@ pub struct Sample {
 }
 
 impl Sample {
+    /// Create a new sample from a timestamp (Unix timestamp in seconds) and a value,
+    /// e.g. to send it to a remote write receiver.
+    pub fn new(timestamp: f64, value: f64) -> Self {
+        Sample { timestamp, value }
+    }
+
     /// Returns the timestamp contained in this sample.
     pub fn timestamp(&self) -> f64 {
         self.timestamp
@@ -346,6 +830,393 @@ impl Sample {
     }
 }
 
+/// A single data point containing a string value, which is returned when
+/// evaluating string literal expressions.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct StringSample {
+    pub(crate) timestamp: f64,
+    pub(crate) value: String,
+}
+
+impl StringSample {
+    /// Returns the timestamp contained in this sample.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    /// Returns the string value contained in this sample.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// A single data point containing a native histogram.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct HistogramSample {
+    pub(crate) timestamp: f64,
+    pub(crate) histogram: NativeHistogram,
+}
+
+impl HistogramSample {
+    /// Returns the timestamp contained in this sample.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+
+    /// Returns the native histogram contained in this sample.
+    pub fn histogram(&self) -> &NativeHistogram {
+        &self.histogram
+    }
+}
+
+/// A native histogram as returned by the API.
+/// See also: [Prometheus API documentation](https://prometheus.io/docs/prometheus/latest/querying/api/#native-histograms)
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct NativeHistogram {
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) count: f64,
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) sum: f64,
+    #[serde(default)]
+    pub(crate) buckets: Vec<HistogramBucket>,
+}
+
+impl NativeHistogram {
+    /// Returns the total number of observations.
+    pub fn count(&self) -> f64 {
+        self.count
+    }
+
+    /// Returns the sum of all observed values.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Returns the populated buckets of this histogram, ordered from the lowest
+    /// to the highest boundaries. Empty buckets are not included by the API.
+    pub fn buckets(&self) -> &[HistogramBucket] {
+        &self.buckets
+    }
+
+    /// Estimate the `q`-quantile (0 <= q <= 1) of the observations in this histogram.
+    ///
+    /// This mirrors the behaviour of the PromQL function `histogram_quantile` when applied
+    /// to native histograms, i.e. values are interpolated exponentially within buckets of
+    /// standard exponential schemas and linearly within the zero bucket and within buckets
+    /// that have an infinite boundary (custom bucket schemas).
+    /// Returns `NaN` if the histogram has no observations, `-Inf` if `q < 0` and `+Inf` if `q > 1`.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::response::NativeHistogram;
+    ///
+    /// let data = r#"{"count":"4","sum":"10","buckets":[[0,"1","2","2"],[0,"2","4","2"]]}"#;
+    /// let histogram: NativeHistogram = serde_json::from_str(data).unwrap();
+    ///
+    /// assert_eq!(histogram.quantile(0.5), 2.0);
+    /// assert_eq!(histogram.quantile(1.0), 4.0);
+    /// ```
+    pub fn quantile(&self, q: f64) -> f64 {
+        if q < 0.0 {
+            return f64::NEG_INFINITY;
+        }
+
+        if q > 1.0 {
+            return f64::INFINITY;
+        }
+
+        if self.count == 0.0 || q.is_nan() {
+            return f64::NAN;
+        }
+
+        let buckets = self.normalized_buckets();
+        let rank = q * self.count;
+        let mut count = 0.0;
+        let (mut lower, mut upper, mut bucket_count) = (0.0, 0.0, 0.0);
+
+        for b in buckets.iter().filter(|b| b.count > 0.0) {
+            (lower, upper, bucket_count) = (b.lower, b.upper, b.count);
+            count += b.count;
+            if count >= rank {
+                break;
+            }
+        }
+
+        if lower == f64::NEG_INFINITY {
+            if upper <= 0.0 {
+                return upper;
+            }
+            lower = 0.0;
+        } else if upper == f64::INFINITY {
+            return lower;
+        }
+
+        // The rank of the quantile within the selected bucket.
+        let fraction = (rank - (count - bucket_count)) / bucket_count;
+
+        if lower <= 0.0 && upper >= 0.0 || self.has_custom_buckets() {
+            return lower + (upper - lower) * fraction;
+        }
+
+        let log_lower = lower.abs().log2();
+        let log_upper = upper.abs().log2();
+
+        if lower > 0.0 {
+            (log_lower + (log_upper - log_lower) * fraction).exp2()
+        } else {
+            -(log_upper + (log_lower - log_upper) * (1.0 - fraction)).exp2()
+        }
+    }
+
+    /// Estimate the fraction of observations between `lower` and `upper`.
+    ///
+    /// This mirrors the behaviour of the PromQL function `histogram_fraction`, i.e. the
+    /// bounds are interpolated within the buckets they fall into.
+    /// Returns `NaN` if the histogram has no observations or any of the bounds is `NaN`.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::response::NativeHistogram;
+    ///
+    /// let data = r#"{"count":"4","sum":"10","buckets":[[0,"1","2","2"],[0,"2","4","2"]]}"#;
+    /// let histogram: NativeHistogram = serde_json::from_str(data).unwrap();
+    ///
+    /// assert_eq!(histogram.fraction(0.0, 2.0), 0.5);
+    /// assert_eq!(histogram.fraction(f64::NEG_INFINITY, f64::INFINITY), 1.0);
+    /// ```
+    pub fn fraction(&self, lower: f64, upper: f64) -> f64 {
+        if self.count == 0.0 || lower.is_nan() || upper.is_nan() {
+            return f64::NAN;
+        }
+
+        if lower >= upper {
+            return 0.0;
+        }
+
+        let custom = self.has_custom_buckets();
+        let mut rank = 0.0;
+        let mut lower_rank = None;
+        let mut upper_rank = None;
+
+        for b in self.normalized_buckets().iter().filter(|b| b.count > 0.0) {
+            if lower_rank.is_none() && b.lower >= lower {
+                lower_rank = Some(rank);
+            }
+
+            if upper_rank.is_none() && b.lower >= upper {
+                upper_rank = Some(rank);
+            }
+
+            if lower_rank.is_none() && b.lower < lower && b.upper > lower {
+                lower_rank = Some(rank + b.count * b.interpolate(lower, custom));
+            }
+
+            if upper_rank.is_none() && b.lower < upper && b.upper > upper {
+                upper_rank = Some(rank + b.count * b.interpolate(upper, custom));
+            }
+
+            if lower_rank.is_some() && upper_rank.is_some() {
+                break;
+            }
+
+            rank += b.count;
+        }
+
+        let lower_rank = lower_rank.unwrap_or(self.count).min(self.count);
+        let upper_rank = upper_rank.unwrap_or(self.count).min(self.count);
+
+        (upper_rank - lower_rank) / self.count
+    }
+
+    // Histograms using custom bucket boundaries always start with a bucket whose
+    // lower boundary is -Inf and/or end with a bucket whose upper boundary is +Inf.
+    fn has_custom_buckets(&self) -> bool {
+        self.buckets
+            .iter()
+            .any(|b| b.lower.is_infinite() || b.upper.is_infinite())
+    }
+
+    // Return the buckets ordered by their boundaries. The zero bucket is clamped
+    // to zero if all other buckets are either positive or negative, just like
+    // Prometheus does it when evaluating `histogram_quantile` and `histogram_fraction`.
+    fn normalized_buckets(&self) -> Vec<HistogramBucket> {
+        let mut buckets = self.buckets.clone();
+        buckets.sort_by(|a, b| a.upper.total_cmp(&b.upper));
+
+        let has_negative = buckets.iter().any(|b| b.upper <= 0.0);
+        let has_positive = buckets.iter().any(|b| b.lower >= 0.0);
+
+        for b in buckets.iter_mut() {
+            if b.lower < 0.0 && b.upper > 0.0 && b.lower.is_finite() {
+                if !has_negative && has_positive {
+                    b.lower = 0.0;
+                } else if has_negative && !has_positive {
+                    b.upper = 0.0;
+                }
+            }
+        }
+
+        buckets
+    }
+}
+
+/// A single bucket of a [`NativeHistogram`].
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct HistogramBucket {
+    pub(crate) boundary_rule: BoundaryRule,
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) lower: f64,
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) upper: f64,
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) count: f64,
+}
+
+impl HistogramBucket {
+    /// Returns the rule that determines whether the boundaries of this bucket are inclusive.
+    pub fn boundary_rule(&self) -> BoundaryRule {
+        self.boundary_rule
+    }
+
+    /// Returns the lower boundary of this bucket.
+    pub fn lower(&self) -> f64 {
+        self.lower
+    }
+
+    /// Returns the upper boundary of this bucket.
+    pub fn upper(&self) -> f64 {
+        self.upper
+    }
+
+    /// Returns the number of observations in this bucket.
+    pub fn count(&self) -> f64 {
+        self.count
+    }
+
+    /// Check if a value falls into this bucket with respect to its [`BoundaryRule`].
+    pub fn contains(&self, value: f64) -> bool {
+        let above_lower = if self.boundary_rule.is_lower_inclusive() {
+            value >= self.lower
+        } else {
+            value > self.lower
+        };
+
+        let below_upper = if self.boundary_rule.is_upper_inclusive() {
+            value <= self.upper
+        } else {
+            value < self.upper
+        };
+
+        above_lower && below_upper
+    }
+
+    // Return the fraction of observations in this bucket that are assumed to be
+    // lower than or equal to `v`.
+    fn interpolate(&self, v: f64, linear: bool) -> f64 {
+        if linear || self.lower <= 0.0 && self.upper >= 0.0 {
+            return (v - self.lower) / (self.upper - self.lower);
+        }
+
+        let log_lower = self.lower.abs().log2();
+        let log_upper = self.upper.abs().log2();
+        let log_v = v.abs().log2();
+
+        if v > 0.0 {
+            (log_v - log_lower) / (log_upper - log_lower)
+        } else {
+            1.0 - (log_v - log_upper) / (log_lower - log_upper)
+        }
+    }
+}
+
+/// Describes which boundaries of a [`HistogramBucket`] are inclusive.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "u8")]
+pub enum BoundaryRule {
+    /// The lower boundary is exclusive, the upper boundary is inclusive.
+    OpenLeft,
+    /// The lower boundary is inclusive, the upper boundary is exclusive.
+    OpenRight,
+    /// Both boundaries are exclusive.
+    OpenBoth,
+    /// Both boundaries are inclusive.
+    ClosedBoth,
+}
+
+impl BoundaryRule {
+    pub fn is_lower_inclusive(&self) -> bool {
+        matches!(self, Self::OpenRight | Self::ClosedBoth)
+    }
+
+    pub fn is_upper_inclusive(&self) -> bool {
+        matches!(self, Self::OpenLeft | Self::ClosedBoth)
+    }
+}
+
+impl TryFrom<u8> for BoundaryRule {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::OpenLeft),
+            1 => Ok(Self::OpenRight),
+            2 => Ok(Self::OpenBoth),
+            3 => Ok(Self::ClosedBoth),
+            v => Err(format!("invalid histogram bucket boundary rule: {}", v)),
+        }
+    }
+}
+
+/// A time series and the exemplars that were recorded for it.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ExemplarSeries {
+    #[serde(alias = "seriesLabels")]
+    pub(crate) series_labels: HashMap<String, String>,
+    pub(crate) exemplars: Vec<Exemplar>,
+}
+
+impl ExemplarSeries {
+    /// Returns a reference to the set of labels (+ metric name)
+    /// of this time series.
+    pub fn series_labels(&self) -> &HashMap<String, String> {
+        &self.series_labels
+    }
+
+    /// Returns a reference to the exemplars of this time series.
+    pub fn exemplars(&self) -> &[Exemplar] {
+        &self.exemplars
+    }
+
+    /// Returns the inner types when ownership is required
+    pub fn into_inner(self) -> (HashMap<String, String>, Vec<Exemplar>) {
+        (self.series_labels, self.exemplars)
+    }
+}
+
+/// A single exemplar, e.g. a sample that references a trace.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Exemplar {
+    pub(crate) labels: HashMap<String, String>,
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) value: f64,
+    pub(crate) timestamp: f64,
+}
+
+impl Exemplar {
+    /// Returns a reference to the set of labels of this exemplar, e.g. a trace ID.
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Returns the value of this exemplar.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// Returns the timestamp of this exemplar.
+    pub fn timestamp(&self) -> f64 {
+        self.timestamp
+    }
+}
+
 /// Collection of active and dropped targets as returned by the API.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Targets {
@@ -543,14 +1414,14 @@ pub enum Rule {
 impl Rule {
     pub fn as_recording(&self) -> Option<&RecordingRule> {
         match self {
-            Self::Recording(rule) => Some(&rule),
+            Self::Recording(rule) => Some(rule),
             _ => None,
         }
     }
 
     pub fn as_alerting(&self) -> Option<&AlertingRule> {
         match self {
-            Self::Alerting(rule) => Some(&rule),
+            Self::Alerting(rule) => Some(rule),
             _ => None,
         }
     }
@@ -1007,6 +1878,20 @@ impl RuntimeInformation {
     }
 }
 
+/// A TSDB snapshot that was created using the admin API.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Snapshot {
+    pub(crate) name: String,
+}
+
+impl Snapshot {
+    /// Get the name of the snapshot, i.e. the directory below `<data-dir>/snapshots`
+    /// that contains the snapshot.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
 /// Prometheus TSDB statistics.
 #[derive(Clone, Debug, Deserialize)]
 pub struct TsdbStatistics {
@@ -1154,13 +2039,434 @@ impl WalReplayState {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    // The examples used in these test cases are partly taken from prometheus.io.
-    // Others are copied from responses of live Prometheus servers.
+/// A node of the abstract syntax tree of a PromQL expression as returned
+/// by [`Client::parse_query`](crate::Client::parse_query).
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum AstNode {
+    #[serde(alias = "aggregation")]
+    Aggregation(AggregationNode),
+    #[serde(alias = "binaryExpr")]
+    BinaryExpr(BinaryExprNode),
+    #[serde(alias = "call")]
+    Call(CallNode),
+    #[serde(alias = "matrixSelector")]
+    MatrixSelector(MatrixSelectorNode),
+    #[serde(alias = "subquery")]
+    Subquery(SubqueryNode),
+    #[serde(alias = "numberLiteral")]
+    NumberLiteral(NumberLiteralNode),
+    #[serde(alias = "parenExpr")]
+    ParenExpr(ParenExprNode),
+    #[serde(alias = "stringLiteral")]
+    StringLiteral(StringLiteralNode),
+    #[serde(alias = "unaryExpr")]
+    UnaryExpr(UnaryExprNode),
+    #[serde(alias = "vectorSelector")]
+    VectorSelector(VectorSelectorNode),
+}
+
+/// An aggregation, e.g. `sum by (job) (up)`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct AggregationNode {
+    pub(crate) op: String,
+    pub(crate) expr: Box<AstNode>,
+    pub(crate) param: Option<Box<AstNode>>,
+    pub(crate) grouping: Vec<String>,
+    pub(crate) without: bool,
+}
 
-    use super::*;
-    use std::collections::HashMap;
+impl AggregationNode {
+    /// Get the aggregation operator, e.g. `sum`.
+    pub fn op(&self) -> &str {
+        &self.op
+    }
+
+    /// Get the aggregated expression.
+    pub fn expr(&self) -> &AstNode {
+        &self.expr
+    }
+
+    /// Get the parameter of the aggregation, e.g. `k` of `topk`.
+    pub fn param(&self) -> Option<&AstNode> {
+        self.param.as_deref()
+    }
+
+    /// Get the labels of the `by` or `without` clause.
+    pub fn grouping(&self) -> &[String] {
+        &self.grouping
+    }
+
+    /// Check if the grouping labels are excluded (`without`) instead of included (`by`).
+    pub fn without(&self) -> bool {
+        self.without
+    }
+}
+
+/// A binary expression, e.g. `a / on (job) b`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct BinaryExprNode {
+    pub(crate) op: String,
+    pub(crate) lhs: Box<AstNode>,
+    pub(crate) rhs: Box<AstNode>,
+    pub(crate) matching: Option<VectorMatchingNode>,
+    #[serde(alias = "bool")]
+    pub(crate) return_bool: bool,
+}
+
+impl BinaryExprNode {
+    /// Get the binary operator, e.g. `+` or `and`.
+    pub fn op(&self) -> &str {
+        &self.op
+    }
+
+    /// Get the left-hand side of the expression.
+    pub fn lhs(&self) -> &AstNode {
+        &self.lhs
+    }
+
+    /// Get the right-hand side of the expression.
+    pub fn rhs(&self) -> &AstNode {
+        &self.rhs
+    }
+
+    /// Get the vector matching of the expression if both sides are instant vectors.
+    pub fn matching(&self) -> Option<&VectorMatchingNode> {
+        self.matching.as_ref()
+    }
+
+    /// Check if the `bool` modifier is set.
+    pub fn return_bool(&self) -> bool {
+        self.return_bool
+    }
+}
+
+/// Describes how the series of both sides of a binary expression are matched.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct VectorMatchingNode {
+    pub(crate) card: MatchingCardinality,
+    pub(crate) labels: Vec<String>,
+    pub(crate) on: bool,
+    pub(crate) include: Vec<String>,
+}
+
+impl VectorMatchingNode {
+    /// Get the cardinality of the matching.
+    pub fn card(&self) -> MatchingCardinality {
+        self.card
+    }
+
+    /// Get the labels of the `on` or `ignoring` clause.
+    pub fn labels(&self) -> &[String] {
+        &self.labels
+    }
+
+    /// Check if the series are matched `on` the labels instead of `ignoring` them.
+    pub fn on(&self) -> bool {
+        self.on
+    }
+
+    /// Get the labels of the `group_left` or `group_right` clause.
+    pub fn include(&self) -> &[String] {
+        &self.include
+    }
+}
+
+/// The cardinality of the vector matching of a binary expression.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+pub enum MatchingCardinality {
+    #[serde(alias = "one-to-one")]
+    OneToOne,
+    /// `group_left`
+    #[serde(alias = "many-to-one")]
+    ManyToOne,
+    /// `group_right`
+    #[serde(alias = "one-to-many")]
+    OneToMany,
+    /// Set operators
+    #[serde(alias = "many-to-many")]
+    ManyToMany,
+}
+
+/// A function call, e.g. `rate(up[5m])`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CallNode {
+    pub(crate) func: FunctionSignature,
+    pub(crate) args: Vec<AstNode>,
+}
+
+impl CallNode {
+    /// Get the signature of the called function.
+    pub fn func(&self) -> &FunctionSignature {
+        &self.func
+    }
+
+    /// Get the arguments of the call.
+    pub fn args(&self) -> &[AstNode] {
+        &self.args
+    }
+}
+
+/// The signature of a PromQL function.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct FunctionSignature {
+    pub(crate) name: String,
+    #[serde(alias = "argTypes")]
+    pub(crate) arg_types: Vec<String>,
+    pub(crate) variadic: i32,
+    #[serde(alias = "returnType")]
+    pub(crate) return_type: String,
+}
+
+impl FunctionSignature {
+    /// Get the name of the function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the types of the arguments, e.g. `matrix` or `scalar`.
+    pub fn arg_types(&self) -> &[String] {
+        &self.arg_types
+    }
+
+    /// Get the number of times the last argument may be repeated
+    /// (0 if the arguments are fixed, a negative number if unlimited).
+    pub fn variadic(&self) -> i32 {
+        self.variadic
+    }
+
+    /// Get the type of the value that the function returns, e.g. `vector`.
+    pub fn return_type(&self) -> &str {
+        &self.return_type
+    }
+}
+
+/// A label matcher of a vector or matrix selector.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct LabelMatcherNode {
+    pub(crate) name: String,
+    pub(crate) value: String,
+    #[serde(alias = "type")]
+    pub(crate) match_type: String,
+}
+
+impl LabelMatcherNode {
+    /// Get the label name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the value that is matched against.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the match operator, i.e. one of `=`, `!=`, `=~` and `!~`.
+    pub fn match_type(&self) -> &str {
+        &self.match_type
+    }
+}
+
+/// An instant vector selector, e.g. `up{job="node"}`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct VectorSelectorNode {
+    pub(crate) name: String,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) offset: Duration,
+    pub(crate) matchers: Vec<LabelMatcherNode>,
+    pub(crate) timestamp: Option<i64>,
+    #[serde(alias = "startOrEnd")]
+    pub(crate) start_or_end: Option<String>,
+}
+
+impl VectorSelectorNode {
+    /// Get the metric name if it is given in front of the braces.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the offset modifier (zero if none is set).
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Get all label matchers including the one for the metric name.
+    pub fn matchers(&self) -> &[LabelMatcherNode] {
+        &self.matchers
+    }
+
+    /// Get the timestamp of the `@` modifier.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp.map(Timestamp::from_unix_millis)
+    }
+
+    /// Get `start` or `end` if the `@ start()` or `@ end()` modifier is set.
+    pub fn start_or_end(&self) -> Option<&str> {
+        self.start_or_end.as_deref()
+    }
+}
+
+/// A range vector selector, e.g. `up[5m]`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct MatrixSelectorNode {
+    pub(crate) name: String,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) range: Duration,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) offset: Duration,
+    pub(crate) matchers: Vec<LabelMatcherNode>,
+    pub(crate) timestamp: Option<i64>,
+    #[serde(alias = "startOrEnd")]
+    pub(crate) start_or_end: Option<String>,
+}
+
+impl MatrixSelectorNode {
+    /// Get the metric name if it is given in front of the braces.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the selected range.
+    pub fn range(&self) -> Duration {
+        self.range
+    }
+
+    /// Get the offset modifier (zero if none is set).
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Get all label matchers including the one for the metric name.
+    pub fn matchers(&self) -> &[LabelMatcherNode] {
+        &self.matchers
+    }
+
+    /// Get the timestamp of the `@` modifier.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp.map(Timestamp::from_unix_millis)
+    }
+
+    /// Get `start` or `end` if the `@ start()` or `@ end()` modifier is set.
+    pub fn start_or_end(&self) -> Option<&str> {
+        self.start_or_end.as_deref()
+    }
+}
+
+/// A subquery, e.g. `rate(up[5m])[1h:1m]`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct SubqueryNode {
+    pub(crate) expr: Box<AstNode>,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) range: Duration,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) offset: Duration,
+    #[serde(deserialize_with = "de::deserialize_millis")]
+    pub(crate) step: Duration,
+    pub(crate) timestamp: Option<i64>,
+    #[serde(alias = "startOrEnd")]
+    pub(crate) start_or_end: Option<String>,
+}
+
+impl SubqueryNode {
+    /// Get the expression that is evaluated.
+    pub fn expr(&self) -> &AstNode {
+        &self.expr
+    }
+
+    /// Get the range of the subquery.
+    pub fn range(&self) -> Duration {
+        self.range
+    }
+
+    /// Get the offset modifier (zero if none is set).
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    /// Get the resolution step (zero if the default evaluation interval is used).
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Get the timestamp of the `@` modifier.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp.map(Timestamp::from_unix_millis)
+    }
+
+    /// Get `start` or `end` if the `@ start()` or `@ end()` modifier is set.
+    pub fn start_or_end(&self) -> Option<&str> {
+        self.start_or_end.as_deref()
+    }
+}
+
+/// A number literal.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct NumberLiteralNode {
+    #[serde(alias = "val")]
+    #[serde(deserialize_with = "de::deserialize_f64")]
+    pub(crate) value: f64,
+}
+
+impl NumberLiteralNode {
+    /// Get the value of the literal.
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+/// A string literal.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct StringLiteralNode {
+    #[serde(alias = "val")]
+    pub(crate) value: String,
+}
+
+impl StringLiteralNode {
+    /// Get the value of the literal.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// An expression enclosed in parentheses.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ParenExprNode {
+    pub(crate) expr: Box<AstNode>,
+}
+
+impl ParenExprNode {
+    /// Get the enclosed expression.
+    pub fn expr(&self) -> &AstNode {
+        &self.expr
+    }
+}
+
+/// A unary expression, e.g. `-up`.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct UnaryExprNode {
+    pub(crate) op: String,
+    pub(crate) expr: Box<AstNode>,
+}
+
+impl UnaryExprNode {
+    /// Get the unary operator, i.e. `-` or `+`.
+    pub fn op(&self) -> &str {
+        &self.op
+    }
+
+    /// Get the operand.
+    pub fn expr(&self) -> &AstNode {
+        &self.expr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // The examples used in these test cases are partly taken from prometheus.io.
+    // Others are copied from responses of live Prometheus servers.
+
+    use super::*;
+    use std::collections::HashMap;
     use time::macros::datetime;
 
     #[test]
@@ -1197,7 +2503,90 @@ mod tests {
 "#;
 
         let result = serde_json::from_str::<ApiResponse<PromqlResult>>(data)?;
-        assert!(matches!(result, ApiResponse::Success { data: _ }));
+        assert!(matches!(result, ApiResponse::Success { .. }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_api_success_with_annotations_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "status": "success",
+  "data": {
+    "resultType": "vector",
+    "result": []
+  },
+  "warnings": [
+    "PromQL warning: encountered a mix of histograms and floats for metric name \"http_request_duration_seconds\""
+  ],
+  "infos": [
+    "PromQL info: metric might not be a counter, name does not end in _total/_sum/_count/_bucket: \"go_goroutines\" (1:6)"
+  ]
+}
+"#;
+
+        let result = serde_json::from_str::<ApiResponse<PromqlResult>>(data)?;
+        match result {
+            ApiResponse::Success {
+                warnings, infos, ..
+            } => {
+                assert!(warnings.len() == 1);
+                assert!(warnings[0].is_promql());
+                assert!(warnings[0]
+                    .as_str()
+                    .starts_with("PromQL warning: encountered a mix"));
+                assert!(warnings[0].message().starts_with("encountered a mix"));
+                assert_eq!(warnings[0].position(), None);
+                assert!(infos.len() == 1);
+                assert!(infos[0]
+                    .message()
+                    .starts_with("metric might not be a counter"));
+                assert!(infos[0].message().ends_with("\"go_goroutines\""));
+                assert_eq!(infos[0].position(), Some((1, 6)));
+            }
+            ApiResponse::Error(_) => panic!("expected a successful response"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotation() {
+        let plain = Annotation::from(String::from("store 10.0.0.1:10901 failed (1:2)"));
+        assert!(!plain.is_promql());
+        assert_eq!(plain.message(), "store 10.0.0.1:10901 failed (1:2)");
+        assert_eq!(plain.position(), None);
+        assert_eq!(plain.source(), None);
+
+        let unpositioned = Annotation::from(String::from("PromQL warning: odd (query)"));
+        assert_eq!(unpositioned.message(), "odd (query)");
+        assert_eq!(unpositioned.position(), None);
+
+        let sourced = plain.with_source("eu-1");
+        assert_eq!(
+            sourced.to_string(),
+            "eu-1: store 10.0.0.1:10901 failed (1:2)"
+        );
+        assert_ne!(sourced, "store 10.0.0.1:10901 failed (1:2)");
+    }
+
+    #[test]
+    fn test_api_success_without_annotations_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "status": "success",
+  "data": {
+    "resultType": "scalar",
+    "result": [ 0, "0.0" ]
+  }
+}
+"#;
+
+        let result = serde_json::from_str::<ApiResponse<PromqlResult>>(data)?;
+        assert!(
+            matches!(result, ApiResponse::Success { warnings, infos, .. } if warnings.is_empty() && infos.is_empty())
+        );
 
         Ok(())
     }
@@ -1353,6 +2742,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_query_result_string_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "resultType": "string",
+  "result": [
+    1659268100.123,
+    "foo"
+  ]
+}
+"#;
+        let result = serde_json::from_str::<PromqlResult>(data)?;
+        let data = result.data();
+        assert!(data.is_string());
+        assert!(!data.is_empty());
+        let string = data.as_string().unwrap();
+        assert!(string.timestamp() == 1659268100.123);
+        assert!(string.value() == "foo");
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_result_scalar_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "resultType": "scalar",
+  "result": [
+    1659268100,
+    "42"
+  ]
+}
+"#;
+        let result = serde_json::from_str::<PromqlResult>(data)?;
+        let data = result.data();
+        assert!(data.is_scalar());
+        assert!(!data.is_string());
+        assert!(data.as_scalar().is_some_and(|s| s.value() == 42.0));
+        Ok(())
+    }
+
     #[test]
     fn test_query_result_no_per_step_stats_deserialization() -> Result<(), anyhow::Error> {
         let data = r#"
@@ -1449,6 +2878,98 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_merge_range_query_results() -> Result<(), anyhow::Error> {
+        let first = r#"
+{
+  "resultType": "matrix",
+  "result": [
+    {
+      "metric": { "__name__": "up", "job": "prometheus" },
+      "values": [ [ 1659268100, "1" ], [ 1659268160, "1" ] ]
+    },
+    {
+      "metric": { "__name__": "up", "job": "node" },
+      "values": [ [ 1659268100, "0" ] ]
+    }
+  ],
+  "stats": {
+    "timings": {
+      "evalTotalTime": 0.5,
+      "resultSortTime": 0,
+      "queryPreparationTime": 0.25,
+      "innerEvalTime": 0.25,
+      "execQueueTime": 0,
+      "execTotalTime": 1
+    },
+    "samples": {
+      "totalQueryableSamplesPerStep": [ [ 1659268100, 2 ], [ 1659268160, 1 ] ],
+      "totalQueryableSamples": 3,
+      "peakSamples": 2
+    }
+  }
+}
+"#;
+        let second = r#"
+{
+  "resultType": "matrix",
+  "result": [
+    {
+      "metric": { "job": "prometheus", "__name__": "up" },
+      "values": [ [ 1659268160, "1" ], [ 1659268220, "0" ] ]
+    }
+  ],
+  "stats": {
+    "timings": {
+      "evalTotalTime": 0.5,
+      "resultSortTime": 0,
+      "queryPreparationTime": 0.25,
+      "innerEvalTime": 0.25,
+      "execQueueTime": 0.5,
+      "execTotalTime": 1
+    },
+    "samples": {
+      "totalQueryableSamplesPerStep": [ [ 1659268220, 1 ] ],
+      "totalQueryableSamples": 1,
+      "peakSamples": 1
+    }
+  }
+}
+"#;
+        let mut first = serde_json::from_str::<PromqlResult>(first)?;
+        first.warnings = vec![Annotation::from(String::from("result may be incomplete"))];
+        let mut second = serde_json::from_str::<PromqlResult>(second)?;
+        second.warnings = vec![Annotation::from(String::from("result may be incomplete"))];
+
+        let result = PromqlResult::merge(vec![first, second]);
+
+        let matrix = result.data().as_matrix().unwrap();
+        assert_eq!(matrix.len(), 2);
+        assert_eq!(matrix[0].metric().get("job").unwrap(), "prometheus");
+        let timestamps: Vec<f64> = matrix[0].samples().iter().map(|s| s.timestamp()).collect();
+        assert_eq!(timestamps, vec![1659268100.0, 1659268160.0, 1659268220.0]);
+        assert_eq!(matrix[1].metric().get("job").unwrap(), "node");
+        assert_eq!(matrix[1].samples().len(), 1);
+
+        let stats = result.stats().unwrap();
+        assert_eq!(stats.timings().exec_total_time(), 2.0);
+        assert_eq!(stats.timings().exec_queue_time(), 0.5);
+        assert_eq!(stats.samples().total_queryable_samples(), 4);
+        assert_eq!(stats.samples().peak_samples(), 2);
+        assert_eq!(
+            stats
+                .samples()
+                .total_queryable_samples_per_step()
+                .unwrap()
+                .len(),
+            3
+        );
+
+        assert_eq!(result.warnings(), &["result may be incomplete"]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_instant_vector_deserialization() -> Result<(), anyhow::Error> {
         let data = r#"
@@ -1533,6 +3054,252 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_native_histogram_instant_vector_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+[
+  {
+    "metric": {
+      "__name__": "prometheus_http_request_duration_seconds"
+    },
+    "histogram": [
+      1702044180.154,
+      {
+        "count": "12",
+        "sum": "3.4",
+        "buckets": [
+          [ 3, "-0.001", "0.001", "2" ],
+          [ 0, "0.5", "1", "6" ],
+          [ 0, "1", "2", "4" ]
+        ]
+      }
+    ]
+  },
+  {
+    "metric": {
+      "__name__": "up"
+    },
+    "value": [
+      1702044180.154,
+      "1"
+    ]
+  }
+]
+"#;
+        let vector = serde_json::from_str::<Vec<InstantVector>>(data)?;
+        assert!(vector.len() == 2);
+        assert!(vector[0].sample().is_none());
+        let sample = vector[0].histogram().unwrap();
+        assert!(sample.timestamp() == 1702044180.154);
+        let histogram = sample.histogram();
+        assert!(histogram.count() == 12.0);
+        assert!(histogram.sum() == 3.4);
+        assert!(histogram.buckets().len() == 3);
+        let zero_bucket = &histogram.buckets()[0];
+        assert!(zero_bucket.boundary_rule() == BoundaryRule::ClosedBoth);
+        assert!(zero_bucket.lower() == -0.001);
+        assert!(zero_bucket.upper() == 0.001);
+        assert!(zero_bucket.count() == 2.0);
+        assert!(zero_bucket.contains(0.001));
+        assert!(histogram.buckets()[1].boundary_rule() == BoundaryRule::OpenLeft);
+        assert!(!histogram.buckets()[1].contains(0.5));
+        assert!(histogram.buckets()[1].contains(1.0));
+        assert!(vector[1].histogram().is_none());
+        assert!(vector[1].sample().is_some_and(|s| s.value() == 1.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_instant_vector_without_sample_deserialization() {
+        let data = r#"[{"metric": {"__name__": "up"}}]"#;
+        let error = serde_json::from_str::<Vec<InstantVector>>(data).unwrap_err();
+        assert!(error.to_string().contains("neither a float sample nor"));
+    }
+
+    #[test]
+    fn test_native_histogram_range_vector_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "resultType": "matrix",
+  "result": [
+    {
+      "metric": {
+        "__name__": "prometheus_http_request_duration_seconds"
+      },
+      "histograms": [
+        [
+          1702044180,
+          {
+            "count": "2",
+            "sum": "1.5",
+            "buckets": [ [ 0, "0.5", "1", "2" ] ]
+          }
+        ],
+        [
+          1702044240,
+          {
+            "count": "0",
+            "sum": "0"
+          }
+        ]
+      ]
+    }
+  ]
+}
+"#;
+        let result = serde_json::from_str::<PromqlResult>(data)?;
+        let matrix = result.data().as_matrix().unwrap();
+        assert!(matrix.len() == 1);
+        assert!(matrix[0].samples().is_empty());
+        let histograms = matrix[0].histograms();
+        assert!(histograms.len() == 2);
+        assert!(histograms[0].timestamp() == 1702044180.0);
+        assert!(histograms[0].histogram().buckets().len() == 1);
+        assert!(histograms[1].histogram().buckets().is_empty());
+        assert!(histograms[1].histogram().quantile(0.5).is_nan());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_boundary_rule_deserialization() {
+        let data = r#"[ 4, "0.5", "1", "2" ]"#;
+        assert!(serde_json::from_str::<HistogramBucket>(data).is_err());
+    }
+
+    #[test]
+    fn test_native_histogram_quantile() -> Result<(), anyhow::Error> {
+        // Exponential buckets with schema 0, i.e. bucket boundaries are powers of two.
+        let data = r#"
+{
+  "count": "10",
+  "sum": "30",
+  "buckets": [
+    [ 3, "-0.001", "0.001", "2" ],
+    [ 0, "1", "2", "4" ],
+    [ 0, "2", "4", "4" ]
+  ]
+}
+"#;
+        let histogram = serde_json::from_str::<NativeHistogram>(data)?;
+        assert!(histogram.quantile(-0.1) == f64::NEG_INFINITY);
+        assert!(histogram.quantile(1.1) == f64::INFINITY);
+        assert!(histogram.quantile(f64::NAN).is_nan());
+        // The zero bucket is clamped to [0, 0.001] as there are no negative buckets.
+        assert!(histogram.quantile(0.1) == 0.0005);
+        assert!(histogram.quantile(0.2) == 0.001);
+        // Exponential interpolation: 2^(0 + 1 * 0.5).
+        assert!(histogram.quantile(0.4) == 2_f64.sqrt());
+        assert!(histogram.quantile(0.6) == 2.0);
+        assert!(histogram.quantile(1.0) == 4.0);
+
+        // Custom buckets are interpolated linearly.
+        let data = r#"
+{
+  "count": "4",
+  "sum": "2",
+  "buckets": [
+    [ 0, "-Inf", "0.5", "2" ],
+    [ 0, "0.5", "1", "2" ]
+  ]
+}
+"#;
+        let histogram = serde_json::from_str::<NativeHistogram>(data)?;
+        assert!(histogram.quantile(0.25) == 0.25);
+        assert!(histogram.quantile(0.75) == 0.75);
+        Ok(())
+    }
+
+    #[test]
+    fn test_native_histogram_fraction() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "count": "10",
+  "sum": "30",
+  "buckets": [
+    [ 3, "-0.001", "0.001", "2" ],
+    [ 0, "1", "2", "4" ],
+    [ 0, "2", "4", "4" ]
+  ]
+}
+"#;
+        let histogram = serde_json::from_str::<NativeHistogram>(data)?;
+        assert!(histogram.fraction(f64::NEG_INFINITY, f64::INFINITY) == 1.0);
+        assert!(histogram.fraction(0.0, 2.0) == 0.6);
+        assert!(histogram.fraction(2.0, 0.0) == 0.0);
+        assert!(histogram.fraction(f64::NAN, 1.0).is_nan());
+        assert!(histogram.fraction(4.0, 8.0) == 0.0);
+        // Exponential interpolation within the bucket (1, 2].
+        assert!(histogram.fraction(1.0, 2_f64.sqrt()) == 0.2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exemplars_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+[
+  {
+    "seriesLabels": {
+      "__name__": "test_exemplar_metric_total",
+      "instance": "localhost:8090",
+      "job": "prometheus",
+      "service": "bar"
+    },
+    "exemplars": [
+      {
+        "labels": {
+          "trace_id": "EpTxMJ40fUus7aGY"
+        },
+        "value": "6",
+        "timestamp": 1600096945.479
+      }
+    ]
+  },
+  {
+    "seriesLabels": {
+      "__name__": "test_exemplar_metric_total",
+      "instance": "localhost:8090",
+      "job": "prometheus",
+      "service": "foo"
+    },
+    "exemplars": [
+      {
+        "labels": {
+          "trace_id": "Olp9XHlq763ccsfa"
+        },
+        "value": "19",
+        "timestamp": 1600096955.479
+      },
+      {
+        "labels": {
+          "trace_id": "hCtjygkIHwAN9vs4"
+        },
+        "value": "20",
+        "timestamp": 1600096965.489
+      }
+    ]
+  }
+]
+"#;
+        let series = serde_json::from_str::<Vec<ExemplarSeries>>(data)?;
+        assert!(series.len() == 2);
+        let first = &series[0];
+        assert!(first.series_labels().len() == 4);
+        assert!(first
+            .series_labels()
+            .get("service")
+            .is_some_and(|v| v == "bar"));
+        assert!(first.exemplars().len() == 1);
+        let exemplar = &first.exemplars()[0];
+        assert!(exemplar
+            .labels()
+            .get("trace_id")
+            .is_some_and(|v| v == "EpTxMJ40fUus7aGY"));
+        assert!(exemplar.value() == 6.0);
+        assert!(exemplar.timestamp() == 1600096945.479);
+        assert!(series[1].exemplars().len() == 2);
+        Ok(())
+    }
+
     #[test]
     fn test_target_deserialization() -> Result<(), anyhow::Error> {
         let data = r#"
@@ -2064,6 +3831,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_snapshot_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "status": "success",
+  "data": {
+    "name": "20171210T211224Z-2be650b6d019eb54"
+  }
+}
+"#;
+        let result = serde_json::from_str::<ApiResponse<Snapshot>>(data)?;
+        assert!(matches!(
+            result,
+            ApiResponse::Success { data, .. } if data.name() == "20171210T211224Z-2be650b6d019eb54"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_api_disabled_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "status": "error",
+  "errorType": "unavailable",
+  "error": "admin APIs disabled"
+}
+"#;
+        let result = serde_json::from_str::<ApiResponse<Snapshot>>(data)?;
+        assert!(matches!(result, ApiResponse::Error(err) if err.is_admin_api_disabled()));
+        Ok(())
+    }
+
     #[test]
     fn test_wal_replay_deserialization() -> Result<(), anyhow::Error> {
         let data = r#"
@@ -2109,4 +3908,161 @@ mod tests {
         serde_json::from_str::<WalReplayStatistics>(data)?;
         Ok(())
     }
+
+    #[test]
+    fn test_format_query_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "status": "success",
+  "data": "foo / bar"
+}
+"#;
+        let result = serde_json::from_str::<ApiResponse<String>>(data)?;
+        assert!(matches!(result, ApiResponse::Success { data, .. } if data == "foo / bar"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_query_deserialization() -> Result<(), anyhow::Error> {
+        // Response to `sum by (job) (rate(foo{a="b"}[5m] offset 1m)) > bool -1`.
+        let data = r#"
+{
+  "status": "success",
+  "data": {
+    "bool": true,
+    "lhs": {
+      "expr": {
+        "args": [
+          {
+            "matchers": [
+              { "name": "a", "type": "=", "value": "b" },
+              { "name": "__name__", "type": "=", "value": "foo" }
+            ],
+            "name": "foo",
+            "offset": 60000,
+            "range": 300000,
+            "startOrEnd": null,
+            "timestamp": null,
+            "type": "matrixSelector"
+          }
+        ],
+        "func": {
+          "argTypes": ["matrix"],
+          "name": "rate",
+          "returnType": "vector",
+          "variadic": 0
+        },
+        "type": "call"
+      },
+      "grouping": ["job"],
+      "op": "sum",
+      "param": null,
+      "type": "aggregation",
+      "without": false
+    },
+    "matching": {
+      "card": "one-to-one",
+      "include": [],
+      "labels": [],
+      "on": false
+    },
+    "op": ">",
+    "rhs": { "type": "numberLiteral", "val": "-1" },
+    "type": "binaryExpr"
+  }
+}
+"#;
+        let result = serde_json::from_str::<ApiResponse<AstNode>>(data)?;
+        let root = match result {
+            ApiResponse::Success { data, .. } => data,
+            ApiResponse::Error(e) => anyhow::bail!(e),
+        };
+
+        let binary = match root {
+            AstNode::BinaryExpr(b) => b,
+            n => anyhow::bail!("unexpected node {:?}", n),
+        };
+        assert_eq!(binary.op(), ">");
+        assert!(binary.return_bool());
+        assert_eq!(
+            binary.matching().map(|m| m.card()),
+            Some(MatchingCardinality::OneToOne)
+        );
+        assert!(matches!(binary.rhs(), AstNode::NumberLiteral(n) if n.value() == -1.0));
+
+        let aggregation = match binary.lhs() {
+            AstNode::Aggregation(a) => a,
+            n => anyhow::bail!("unexpected node {:?}", n),
+        };
+        assert_eq!(aggregation.op(), "sum");
+        assert_eq!(aggregation.grouping(), &[String::from("job")]);
+        assert!(!aggregation.without());
+        assert!(aggregation.param().is_none());
+
+        let call = match aggregation.expr() {
+            AstNode::Call(c) => c,
+            n => anyhow::bail!("unexpected node {:?}", n),
+        };
+        assert_eq!(call.func().name(), "rate");
+        assert_eq!(call.func().arg_types(), &[String::from("matrix")]);
+
+        let matrix = match &call.args()[0] {
+            AstNode::MatrixSelector(m) => m,
+            n => anyhow::bail!("unexpected node {:?}", n),
+        };
+        assert_eq!(matrix.name(), "foo");
+        assert_eq!(matrix.range(), Duration::minutes(5));
+        assert_eq!(matrix.offset(), Duration::minutes(1));
+        assert_eq!(matrix.matchers()[0].match_type(), "=");
+        assert!(matrix.timestamp().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_subquery_node_deserialization() -> Result<(), anyhow::Error> {
+        let data = r#"
+{
+  "expr": {
+    "expr": {
+      "op": "-",
+      "expr": {
+        "matchers": [{ "name": "__name__", "type": "=", "value": "up" }],
+        "name": "up",
+        "offset": 0,
+        "startOrEnd": null,
+        "timestamp": 1700000000000,
+        "type": "vectorSelector"
+      },
+      "type": "unaryExpr"
+    },
+    "type": "parenExpr"
+  },
+  "offset": 0,
+  "range": 3600000,
+  "startOrEnd": "end",
+  "step": 60000,
+  "timestamp": null,
+  "type": "subquery"
+}
+"#;
+        let node = serde_json::from_str::<AstNode>(data)?;
+        let subquery = match node {
+            AstNode::Subquery(s) => s,
+            n => anyhow::bail!("unexpected node {:?}", n),
+        };
+        assert_eq!(subquery.range(), Duration::hours(1));
+        assert_eq!(subquery.step(), Duration::minutes(1));
+        assert_eq!(subquery.start_or_end(), Some("end"));
+        assert!(matches!(
+            subquery.expr(),
+            AstNode::ParenExpr(p) if matches!(
+                p.expr(),
+                AstNode::UnaryExpr(u) if matches!(
+                    u.expr(),
+                    AstNode::VectorSelector(v) if v.timestamp() == Some(Timestamp::from(1700000000))
+                )
+            )
+        ));
+        Ok(())
+    }
 }
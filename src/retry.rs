@@ -0,0 +1,471 @@
+//! Retry requests that failed due to a transient error.
+//!
+//! A [`Client`](crate::Client) that is built with a [`RetryPolicy`] (see
+//! [`ClientBuilder::retry`](crate::ClientBuilder::retry)) repeats requests that failed
+//! because the server could not be reached or because Prometheus reported an error that
+//! is likely to be transient. Unlike a retry middleware that only sees HTTP status codes,
+//! the error responses of the Prometheus API are classified by their
+//! [`PrometheusErrorType`]: e.g. a query that timed out or a server that is currently
+//! unavailable is retried, while a query that failed due to a syntax error
+//! ([`PrometheusErrorType::BadData`]) is not.
+//!
+//! The delay between two attempts grows exponentially (optionally with random jitter).
+//! A delay requested by the server via the `Retry-After` header is honoured. No more
+//! retries are made once the total time spent on a request would exceed a configurable
+//! limit. If a request cannot be retried anymore, the result of the last attempt is returned.
+//!
+//! Retries apply to all requests to the HTTP API except remote write and remote read
+//! requests and the requests to the TSDB admin endpoints (creating snapshots, deleting
+//! series and cleaning tombstones), which are not idempotent. When several replicas are configured (see [`ha`](crate::ha)), a request is only
+//! retried after all replicas have been tried.
+//!
+//! ```rust
+//! use prometheus_http_query::{retry::RetryPolicy, Client};
+//! use std::time::Duration;
+//!
+//! fn main() -> Result<(), prometheus_http_query::Error> {
+//!     let policy = RetryPolicy::new()
+//!         .max_retries(5)
+//!         .initial_backoff(Duration::from_millis(200))
+//!         .max_retry_time(Duration::from_secs(10));
+//!
+//!     let client = Client::builder().retry(policy).build()?;
+//!     Ok(())
+//! }
+//! ```
+use crate::error::{ClientError, Error, PrometheusErrorType};
+use crate::response::ApiResponse;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{ResponseBuilderExt, StatusCode};
+use serde::de::IgnoredAny;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant, SystemTime};
+use url::Url;
+
+/// Determines which requests are retried and how long to wait between two attempts.
+///
+/// By default a request is retried up to 3 times, starting with a delay of 100
+/// milliseconds that is doubled after every attempt (up to 10 seconds) and randomized
+/// by jitter. No retries are made once 30 seconds have passed since the first attempt.
+/// The following failures are retried:
+///
+/// - The server could not be reached or did not respond in time.
+/// - The server responded with an error of type [`PrometheusErrorType::Timeout`] or
+///   [`PrometheusErrorType::Unavailable`] (see [`RetryPolicy::retry_on`]).
+/// - The server (or a proxy) responded with the status code 429, 502, 503 or 504 and
+///   without a Prometheus error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_retry_time: Duration,
+    jitter: bool,
+    retry_on: Vec<PrometheusErrorType>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            max_retry_time: Duration::from_secs(30),
+            jitter: true,
+            retry_on: vec![
+                PrometheusErrorType::Timeout,
+                PrometheusErrorType::Unavailable,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a retry policy with the default settings, see [`RetryPolicy`].
+    pub fn new() -> Self {
+        RetryPolicy::default()
+    }
+
+    /// Set the maximum number of retries, i.e. a request is sent at most `retries + 1` times.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry. The delay is doubled after every retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the maximum delay between two attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Set the maximum time that may pass between the first attempt and the start of
+    /// the last retry. A retry that would start later (including a delay requested via
+    /// `Retry-After`) is not made.
+    pub fn max_retry_time(mut self, time: Duration) -> Self {
+        self.max_retry_time = time;
+        self
+    }
+
+    /// Enable or disable jitter. With jitter enabled (the default) every delay is
+    /// randomly reduced by up to half so that many clients do not retry in lockstep.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set the types of Prometheus errors that are retried. Defaults to
+    /// [`PrometheusErrorType::Timeout`] and [`PrometheusErrorType::Unavailable`].
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{error::PrometheusErrorType, retry::RetryPolicy};
+    ///
+    /// let policy = RetryPolicy::new().retry_on([
+    ///     PrometheusErrorType::Timeout,
+    ///     PrometheusErrorType::Unavailable,
+    ///     PrometheusErrorType::Internal,
+    /// ]);
+    ///
+    /// assert!(policy.is_retryable(PrometheusErrorType::Internal));
+    /// assert!(!policy.is_retryable(PrometheusErrorType::BadData));
+    /// ```
+    pub fn retry_on(mut self, types: impl IntoIterator<Item = PrometheusErrorType>) -> Self {
+        self.retry_on = types.into_iter().collect();
+        self
+    }
+
+    /// Check if a request that failed with an error of the given type is retried.
+    pub fn is_retryable(&self, error_type: PrometheusErrorType) -> bool {
+        self.retry_on.contains(&error_type)
+    }
+
+    // Only transport errors occur before a response has been received, see `ha`. Of
+    // these, only errors that are likely to be transient (e.g. a refused connection or
+    // a timeout) are retried, but not e.g. an invalid URL or a redirect loop.
+    fn is_retryable_error(error: &Error) -> bool {
+        match error {
+            Error::Client(e) => e
+                .source
+                .as_ref()
+                .is_some_and(|e| e.is_connect() || e.is_timeout() || e.is_request()),
+            _ => false,
+        }
+    }
+
+    // Classify an error response by the error type in its body. Responses that do not
+    // contain a Prometheus error are classified by their status code.
+    fn is_retryable_response(&self, status: StatusCode, body: &[u8]) -> bool {
+        match serde_json::from_slice::<ApiResponse<IgnoredAny>>(body) {
+            Ok(ApiResponse::Error(e)) => self.is_retryable(e.error_type()),
+            _ => matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+        }
+    }
+
+    // The delay before the given retry (starting at 0) without jitter.
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+// The outcome of a single attempt, with the body of an error response read in
+// order to classify it.
+pub(crate) struct Attempt<R> {
+    pub(crate) result: Result<R, Error>,
+    retryable: bool,
+    retry_after: Option<Duration>,
+}
+
+// Keeps track of the retries of a single request.
+pub(crate) struct Retries<'a> {
+    policy: &'a RetryPolicy,
+    start: Instant,
+    retries: u32,
+}
+
+impl<'a> Retries<'a> {
+    pub(crate) fn new(policy: &'a RetryPolicy) -> Self {
+        Retries {
+            policy,
+            start: Instant::now(),
+            retries: 0,
+        }
+    }
+
+    // Return the delay before the next attempt or `None` if the request is not retried.
+    pub(crate) fn next_delay<R>(&mut self, attempt: &Attempt<R>) -> Option<Duration> {
+        if !attempt.retryable || self.retries >= self.policy.max_retries {
+            return None;
+        }
+
+        let delay = match attempt.retry_after {
+            Some(delay) => delay,
+            None if self.policy.jitter => jitter(self.policy.backoff(self.retries)),
+            None => self.policy.backoff(self.retries),
+        };
+
+        if self.start.elapsed() + delay > self.policy.max_retry_time {
+            return None;
+        }
+
+        self.retries += 1;
+        Some(delay)
+    }
+}
+
+// Randomly reduce a delay by up to half.
+fn jitter(delay: Duration) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = (random >> 11) as f64 / (1u64 << 53) as f64;
+    delay.mul_f64(1.0 - fraction / 2.0)
+}
+
+// Parse the value of a `Retry-After` header, either a number of seconds or a date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = httpdate::parse_http_date(value).ok()?;
+            Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+        }
+    }
+}
+
+// Reassemble a response whose body has already been read, including its URL.
+fn rebuild_response(
+    status: StatusCode,
+    version: reqwest::Version,
+    headers: HeaderMap,
+    url: Url,
+    body: Vec<u8>,
+) -> http::Response<Vec<u8>> {
+    // Building a response that only has an extension set does not fail.
+    let mut response = http::Response::builder()
+        .url(url)
+        .body(body)
+        .unwrap_or_default();
+    *response.status_mut() = status;
+    *response.version_mut() = version;
+    *response.headers_mut() = headers;
+    response
+}
+
+fn read_body_error(source: reqwest::Error) -> Error {
+    Error::Client(ClientError {
+        message: "failed to read response from server",
+        source: Some(source),
+    })
+}
+
+impl RetryPolicy {
+    // Determine if a request may be retried after the given attempt.
+    pub(crate) async fn inspect(
+        &self,
+        result: Result<reqwest::Response, Error>,
+    ) -> Attempt<reqwest::Response> {
+        let response = match result {
+            Ok(response) if !response.status().is_success() => response,
+            result => {
+                let retryable = result.as_ref().err().is_some_and(Self::is_retryable_error);
+                return Attempt {
+                    result,
+                    retryable,
+                    retry_after: None,
+                };
+            }
+        };
+
+        let (status, version) = (response.status(), response.version());
+        let headers = response.headers().clone();
+        let url = response.url().clone();
+        let body = match response.bytes().await {
+            Ok(body) => body.to_vec(),
+            Err(e) => {
+                return Attempt {
+                    result: Err(read_body_error(e)),
+                    retryable: true,
+                    retry_after: None,
+                }
+            }
+        };
+
+        Attempt {
+            retryable: self.is_retryable_response(status, &body),
+            retry_after: retry_after(&headers),
+            result: Ok(rebuild_response(status, version, headers, url, body).into()),
+        }
+    }
+
+    // Determine if a request of the blocking client may be retried after the given attempt.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn inspect_blocking(
+        &self,
+        result: Result<reqwest::blocking::Response, Error>,
+    ) -> Attempt<reqwest::blocking::Response> {
+        let response = match result {
+            Ok(response) if !response.status().is_success() => response,
+            result => {
+                let retryable = result.as_ref().err().is_some_and(Self::is_retryable_error);
+                return Attempt {
+                    result,
+                    retryable,
+                    retry_after: None,
+                };
+            }
+        };
+
+        let (status, version) = (response.status(), response.version());
+        let headers = response.headers().clone();
+        let url = response.url().clone();
+        let body = match response.bytes() {
+            Ok(body) => body.to_vec(),
+            Err(e) => {
+                return Attempt {
+                    result: Err(read_body_error(e)),
+                    retryable: true,
+                    retry_after: None,
+                }
+            }
+        };
+
+        Attempt {
+            retryable: self.is_retryable_response(status, &body),
+            retry_after: retry_after(&headers),
+            result: Ok(rebuild_response(status, version, headers, url, body).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn attempt(retryable: bool, retry_after: Option<Duration>) -> Attempt<()> {
+        Attempt {
+            result: Ok(()),
+            retryable,
+            retry_after,
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500));
+
+        let delays: Vec<u128> = (0..5).map(|r| policy.backoff(r).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_jitter() {
+        for _ in 0..100 {
+            let delay = jitter(Duration::from_millis(100));
+            assert!(delay > Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_max_retries() {
+        let policy = RetryPolicy::new().max_retries(2).jitter(false);
+        let mut retries = Retries::new(&policy);
+
+        assert_eq!(
+            retries.next_delay(&attempt(true, None)),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            retries.next_delay(&attempt(true, None)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(retries.next_delay(&attempt(true, None)), None);
+    }
+
+    #[test]
+    fn test_max_retry_time() {
+        let policy = RetryPolicy::new().max_retry_time(Duration::from_secs(1));
+        let mut retries = Retries::new(&policy);
+
+        assert_eq!(retries.next_delay(&attempt(false, None)), None);
+        assert_eq!(
+            retries.next_delay(&attempt(true, Some(Duration::from_secs(2)))),
+            None
+        );
+        assert_eq!(
+            retries.next_delay(&attempt(true, Some(Duration::ZERO))),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_classify_error() {
+        let error = Error::Client(ClientError {
+            message: "failed to send request to server",
+            source: None,
+        });
+        assert!(!RetryPolicy::is_retryable_error(&error));
+        assert!(!RetryPolicy::is_retryable_error(
+            &Error::EmptySeriesSelector
+        ));
+    }
+
+    #[test]
+    fn test_classify_response() {
+        let policy = RetryPolicy::new();
+        let error = |error_type: &str| {
+            format!(
+                r#"{{"status": "error", "errorType": "{}", "error": "..."}}"#,
+                error_type
+            )
+        };
+
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        assert!(policy.is_retryable_response(unavailable, error("unavailable").as_bytes()));
+        assert!(policy.is_retryable_response(unavailable, error("timeout").as_bytes()));
+        assert!(!policy.is_retryable_response(unavailable, error("canceled").as_bytes()));
+        assert!(
+            !policy.is_retryable_response(StatusCode::BAD_REQUEST, error("bad_data").as_bytes())
+        );
+        assert!(policy.is_retryable_response(unavailable, b"upstream unavailable"));
+        assert!(policy.is_retryable_response(StatusCode::TOO_MANY_REQUESTS, b""));
+        assert!(!policy.is_retryable_response(StatusCode::NOT_FOUND, b"not found"));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
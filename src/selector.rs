@@ -1,20 +1,49 @@
+use crate::error::{Error, InvalidNameError, ParseError};
+use crate::lexer::{Lexer, Token};
 use crate::util::*;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
 /// A time series selector that is gradually built from a metric name and/or
 /// a set of label matchers.
-#[derive(Debug, Clone, PartialEq)]
-pub struct Selector<'a> {
-    pub(crate) labels: Vec<Label<'a>>,
+///
+/// A selector owns its label names and values, so it can be built from runtime data,
+/// stored in configuration structs and sent across tasks. It (de)serializes as a list
+/// of matchers, e.g. `[{"eq":["job","node"]},{"regex_ne":["mode","idle"]}]`.
+///
+/// A selector can also be parsed from its PromQL representation. Formatting a selector
+/// yields a string that parses to the same selector again.
+///
+/// ```rust
+/// use prometheus_http_query::Selector;
+///
+/// let select: Selector = r#"http_requests_total{job=~"api.*",code!="500"}"#.parse().unwrap();
+///
+/// let expected = Selector::new()
+///     .metric("http_requests_total")
+///     .regex_eq("job", "api.*")
+///     .ne("code", "500");
+///
+/// assert_eq!(select, expected);
+/// assert_eq!(select.to_string().parse::<Selector>().unwrap(), expected);
+///
+/// let err = r#"up{job=~"api.*" code!="500"}"#.parse::<Selector>().unwrap_err();
+/// assert_eq!(err.column(), 17);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Selector {
+    pub(crate) labels: Vec<Label>,
 }
 
-impl<'a> Default for Selector<'a> {
+impl Default for Selector {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<'a> Selector<'a> {
+impl Selector {
     /// Create a new instance of [Selector].
     pub fn new() -> Self {
         Selector { labels: vec![] }
@@ -32,11 +61,12 @@ impl<'a> Selector<'a> {
     ///
     /// assert_eq!(select, other_select);
     /// ```
-    pub fn metric(mut self, metric: &'a str) -> Self
+    pub fn metric(mut self, metric: impl fmt::Display) -> Self
     where
         Self: Sized,
     {
-        self.labels.push(Label::Equal(("__name__", metric)));
+        self.labels
+            .push(Label::Equal((String::from("__name__"), metric.to_string())));
         self
     }
 
@@ -56,11 +86,12 @@ impl<'a> Selector<'a> {
     ///
     /// assert_eq!(select, expected);
     /// ```
-    pub fn eq(mut self, label: &'a str, value: &'a str) -> Self
+    pub fn eq(mut self, label: impl fmt::Display, value: impl fmt::Display) -> Self
     where
         Self: Sized,
     {
-        self.labels.push(Label::Equal((label, value)));
+        self.labels
+            .push(Label::Equal((label.to_string(), value.to_string())));
         self
     }
 
@@ -80,11 +111,12 @@ impl<'a> Selector<'a> {
     ///
     /// assert_eq!(select, expected);
     /// ```
-    pub fn ne(mut self, label: &'a str, value: &'a str) -> Self
+    pub fn ne(mut self, label: impl fmt::Display, value: impl fmt::Display) -> Self
     where
         Self: Sized,
     {
-        self.labels.push(Label::NotEqual((label, value)));
+        self.labels
+            .push(Label::NotEqual((label.to_string(), value.to_string())));
         self
     }
 
@@ -104,11 +136,12 @@ impl<'a> Selector<'a> {
     ///
     /// assert_eq!(select, expected);
     /// ```
-    pub fn regex_eq(mut self, label: &'a str, value: &'a str) -> Self
+    pub fn regex_eq(mut self, label: impl fmt::Display, value: impl fmt::Display) -> Self
     where
         Self: Sized,
     {
-        self.labels.push(Label::RegexEqual((label, value)));
+        self.labels
+            .push(Label::RegexEqual((label.to_string(), value.to_string())));
         self
     }
 
@@ -128,16 +161,62 @@ impl<'a> Selector<'a> {
     ///
     /// assert_eq!(select, expected);
     /// ```
-    pub fn regex_ne(mut self, label: &'a str, value: &'a str) -> Self
+    pub fn regex_ne(mut self, label: impl fmt::Display, value: impl fmt::Display) -> Self
     where
         Self: Sized,
     {
-        self.labels.push(Label::RegexNotEqual((label, value)));
+        self.labels
+            .push(Label::RegexNotEqual((label.to_string(), value.to_string())));
         self
     }
+
+    /// Validate all label names and the metric name (if any) of this [Selector] against
+    /// the given [NameValidationScheme]. Label values are always valid as they are escaped
+    /// when the [Selector] is formatted.<br>
+    /// Note that all builders that send selectors to Prometheus (e.g.
+    /// [`Client::series`](crate::Client::series) or
+    /// [`LabelNamesQueryBuilder::selectors`](crate::LabelNamesQueryBuilder::selectors))
+    /// validate them using [`NameValidationScheme::Utf8`] before sending them.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::{NameValidationScheme, Selector};
+    ///
+    /// let select = Selector::new()
+    ///     .metric("http.server.requests")
+    ///     .eq("service.name", "api");
+    ///
+    /// assert!(select.validate(NameValidationScheme::Utf8).is_ok());
+    /// assert!(select.validate(NameValidationScheme::Legacy).is_err());
+    ///
+    /// // Names that are invalid according to the legacy rules are quoted.
+    /// let expected = r#"{__name__="http.server.requests","service.name"="api"}"#;
+    /// assert_eq!(select.to_string(), expected);
+    /// ```
+    pub fn validate(&self, scheme: NameValidationScheme) -> Result<(), Error> {
+        for label in &self.labels {
+            let name = label.name();
+
+            if !scheme.is_valid_label_name(name) {
+                return Err(Error::InvalidName(InvalidNameError {
+                    name: name.to_string(),
+                    is_metric_name: false,
+                }));
+            }
+
+            if let Label::Equal((name, metric)) = label {
+                if name == "__name__" && !scheme.is_valid_metric_name(metric) {
+                    return Err(Error::InvalidName(InvalidNameError {
+                        name: metric.to_string(),
+                        is_metric_name: true,
+                    }));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
-impl<'a> fmt::Display for Selector<'a> {
+impl fmt::Display for Selector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         let matchers = self
             .labels
@@ -149,6 +228,167 @@ impl<'a> fmt::Display for Selector<'a> {
     }
 }
 
+/// Parses a selector like `http_requests_total{job=~"api.*"}`, including quoted
+/// UTF-8 names like `{"http.requests", "service.name"="api"}` (Prometheus >= 3.0).
+/// Like Prometheus, this rejects selectors without a metric name that only consist of
+/// matchers that match the empty string, e.g. `{}` or `{job=""}`.
+impl FromStr for Selector {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lexer = Lexer::new(s);
+        let selector = parse_selector(&mut lexer)?;
+
+        let next = lexer.next()?;
+        if next.token != Token::Eof {
+            return Err(lexer.error(next.start, format!("unexpected {}", next.token)));
+        }
+
+        Ok(selector)
+    }
+}
+
+pub(crate) fn parse_selector(lexer: &mut Lexer) -> Result<Selector, ParseError> {
+    let first = lexer.next()?;
+
+    match first.token {
+        Token::Identifier(metric) => {
+            let mut selector = Selector::new().metric(metric);
+            if lexer.peek()?.token == Token::LeftBrace {
+                lexer.next()?;
+                parse_matchers(lexer, &mut selector, true)?;
+            }
+            Ok(selector)
+        }
+        Token::LeftBrace => {
+            let mut selector = Selector::new();
+            parse_matchers(lexer, &mut selector, false)?;
+            if !selector.labels.iter().any(is_non_empty_matcher) {
+                return Err(lexer.error(
+                    first.start,
+                    "vector selector must contain at least one non-empty matcher",
+                ));
+            }
+            Ok(selector)
+        }
+        t => Err(lexer.error(
+            first.start,
+            format!("unexpected {}, expected metric name or \"{{\"", t),
+        )),
+    }
+}
+
+// Check if a matcher does not match the empty string. Regular expressions are not
+// evaluated client-side, so only a few trivial patterns are recognized as matching the
+// empty string. A negative regex matcher only excludes the empty string if its pattern
+// is one of these.
+pub(crate) fn is_non_empty_matcher(label: &Label) -> bool {
+    let matches_empty = |value: &str| matches!(value, "" | ".*" | "^$");
+    match label {
+        Label::Equal((_, value)) => !value.is_empty(),
+        Label::NotEqual((_, value)) => value.is_empty(),
+        Label::RegexEqual((_, value)) => !matches_empty(value),
+        Label::RegexNotEqual((_, value)) => matches_empty(value),
+    }
+}
+
+// Parse the label matchers of a selector after the opening brace up to and including
+// the closing brace. `has_metric` indicates that the metric name was already given
+// in front of the braces.
+pub(crate) fn parse_matchers(
+    lexer: &mut Lexer,
+    selector: &mut Selector,
+    has_metric: bool,
+) -> Result<(), ParseError> {
+    loop {
+        let token = lexer.next()?;
+        let start = token.start;
+
+        let (name, quoted) = match token.token {
+            Token::RightBrace => return Ok(()),
+            Token::Identifier(name) => {
+                if let Some(i) = name.find(':') {
+                    return Err(lexer.error(start + i, "unexpected character inside braces: ':'"));
+                }
+                (name, false)
+            }
+            Token::String(name) => (name, true),
+            t => {
+                return Err(lexer.error(
+                    start,
+                    format!("unexpected {} in label matching, expected label name", t),
+                ))
+            }
+        };
+
+        let op = lexer.next()?;
+
+        let (label, separator) = match op.token {
+            Token::Eq | Token::Neq | Token::EqRegex | Token::NeqRegex => {
+                if !NameValidationScheme::Utf8.is_valid_label_name(&name) {
+                    return Err(lexer.error(start, format!("invalid label name {:?}", name)));
+                }
+
+                let value = lexer.next()?;
+                let value = match value.token {
+                    Token::String(value) => value,
+                    t => {
+                        return Err(lexer.error(
+                            value.start,
+                            format!("unexpected {} in label matching, expected string", t),
+                        ))
+                    }
+                };
+
+                let label = match op.token {
+                    Token::Eq => Label::Equal((name, value)),
+                    Token::Neq => Label::NotEqual((name, value)),
+                    Token::EqRegex => Label::RegexEqual((name, value)),
+                    _ => Label::RegexNotEqual((name, value)),
+                };
+
+                (label, lexer.next()?)
+            }
+            // A quoted string without operator selects the metric name.
+            Token::Comma | Token::RightBrace if quoted => {
+                if !NameValidationScheme::Utf8.is_valid_metric_name(&name) {
+                    return Err(lexer.error(start, format!("invalid metric name {:?}", name)));
+                }
+                (Label::Equal((String::from("__name__"), name)), op)
+            }
+            t => {
+                return Err(lexer.error(
+                    op.start,
+                    format!(
+                        "unexpected {} in label matching, expected label matching operator",
+                        t
+                    ),
+                ))
+            }
+        };
+
+        if has_metric && label.name() == "__name__" {
+            return Err(lexer.error(start, "metric name must not be set twice"));
+        }
+
+        selector.labels.push(label);
+
+        match separator.token {
+            Token::Comma => continue,
+            Token::RightBrace => return Ok(()),
+            t => {
+                return Err(lexer.error(
+                    separator.start,
+                    format!(
+                        "unexpected {} in label matching, expected \",\" or \"}}\"",
+                        t
+                    ),
+                ))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -158,11 +398,11 @@ mod tests {
     fn test_selector_display_impl() {
         let s = Selector {
             labels: vec![
-                Label::Equal(("__name__", "http_requests_total")),
-                Label::Equal(("handler", "/api/comments")),
-                Label::RegexEqual(("job", ".*server")),
-                Label::RegexNotEqual(("status", "4..")),
-                Label::NotEqual(("env", "test")),
+                Label::Equal(("__name__".to_string(), "http_requests_total".to_string())),
+                Label::Equal(("handler".to_string(), "/api/comments".to_string())),
+                Label::RegexEqual(("job".to_string(), ".*server".to_string())),
+                Label::RegexNotEqual(("status".to_string(), "4..".to_string())),
+                Label::NotEqual(("env".to_string(), "test".to_string())),
             ],
         };
 
@@ -170,4 +410,245 @@ mod tests {
 
         assert_eq!(s.to_string(), result);
     }
+
+    #[test]
+    fn test_selector_display_escapes_values() {
+        let s = Selector::new()
+            .eq("path", "C:\\temp\\\"x\"")
+            .regex_eq("msg", "line1\nline2\t\u{7}ü");
+
+        let result = r#"{path="C:\\temp\\\"x\"",msg=~"line1\nline2\t\x07ü"}"#;
+
+        assert_eq!(s.to_string(), result);
+    }
+
+    #[test]
+    fn test_selector_display_quotes_utf8_names() {
+        let s = Selector::new()
+            .metric("http.requests")
+            .eq("service.name", "api")
+            .ne("le", "+Inf")
+            .eq("ä\"", "x");
+
+        let result = r#"{__name__="http.requests","service.name"="api",le!="+Inf","ä\""="x"}"#;
+
+        assert_eq!(s.to_string(), result);
+    }
+
+    #[test]
+    fn test_selector_validate() {
+        let s = Selector::new().metric("up").eq("job", "node");
+        assert!(s.validate(NameValidationScheme::Legacy).is_ok());
+        assert!(s.validate(NameValidationScheme::Utf8).is_ok());
+
+        let s = Selector::new().metric("node:cpu:rate5m").eq("instance", "");
+        assert!(s.validate(NameValidationScheme::Legacy).is_ok());
+
+        let s = Selector::new().eq("", "value");
+        match s.validate(NameValidationScheme::Utf8) {
+            Err(Error::InvalidName(e)) => {
+                assert_eq!(e.name(), "");
+                assert!(!e.is_metric_name());
+            }
+            _ => panic!("expected invalid label name"),
+        }
+
+        let s = Selector::new().metric("1up");
+        assert!(s.validate(NameValidationScheme::Utf8).is_ok());
+        match s.validate(NameValidationScheme::Legacy) {
+            Err(Error::InvalidName(e)) => {
+                assert_eq!(e.name(), "1up");
+                assert!(e.is_metric_name());
+            }
+            _ => panic!("expected invalid metric name"),
+        }
+
+        let s = Selector::new().metric("");
+        assert!(s.validate(NameValidationScheme::Utf8).is_err());
+
+        let s = Selector::new().eq("job:name", "x");
+        assert!(s.validate(NameValidationScheme::Legacy).is_err());
+
+        // Only equality matchers on the metric name are validated as metric names.
+        let s = Selector::new().regex_eq("__name__", "1.*");
+        assert!(s.validate(NameValidationScheme::Legacy).is_ok());
+    }
+
+    #[test]
+    fn test_selector_from_runtime_data() {
+        fn build(job: &str, instances: &[String]) -> Selector {
+            let instances = instances.join("|");
+            Selector::new()
+                .metric("up")
+                .eq("job", job)
+                .regex_eq("instance", instances)
+        }
+
+        let instances = vec![String::from("a:9100"), String::from("b:9100")];
+        let s = build(&String::from("node"), &instances);
+
+        assert_eq!(
+            s.to_string(),
+            r#"{__name__="up",job="node",instance=~"a:9100|b:9100"}"#
+        );
+    }
+
+    #[test]
+    fn test_selector_serde() {
+        let s = Selector::new()
+            .metric("up")
+            .ne("job", "node")
+            .regex_ne("mode", "idle");
+
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"eq":["__name__","up"]},{"ne":["job","node"]},{"regex_ne":["mode","idle"]}]"#
+        );
+        assert_eq!(serde_json::from_str::<Selector>(&json).unwrap(), s);
+    }
+
+    #[test]
+    fn test_selector_eq_and_hash() {
+        let a = Selector::new().metric("up").eq("job", "node");
+        let b = Selector::new().metric("up").eq("job", String::from("node"));
+        let c = Selector::new().eq("job", "node").metric("up");
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let set = std::collections::HashSet::from([a, b, c]);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_selector_from_str() {
+        let cases = [
+            ("up", Selector::new().metric("up")),
+            (
+                "node:cpu:rate5m { job = 'node' , }",
+                Selector::new().metric("node:cpu:rate5m").eq("job", "node"),
+            ),
+            (
+                r#"{job!="a",mode=~`idle|iowait`,cpu!~"1\\d"}"#,
+                Selector::new()
+                    .ne("job", "a")
+                    .regex_eq("mode", "idle|iowait")
+                    .regex_ne("cpu", "1\\d"),
+            ),
+            (
+                r#"{"http.requests", "service.name"="api"}"#,
+                Selector::new()
+                    .metric("http.requests")
+                    .eq("service.name", "api"),
+            ),
+            (
+                r#"{by="x", __name__="up"}"#,
+                Selector::new().eq("by", "x").metric("up"),
+            ),
+            (r#"{job!~".*"}"#, Selector::new().regex_ne("job", ".*")),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Selector>().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_selector_round_trip() {
+        let selectors = [
+            Selector::new()
+                .metric("http_requests_total")
+                .regex_eq("job", "api.*")
+                .ne("code", "500"),
+            Selector::new()
+                .eq("path", "C:\\temp\\\"x\"")
+                .regex_eq("msg", "line1\nline2\t\u{7}\u{7f}ü"),
+            Selector::new()
+                .metric("http.requests")
+                .eq("service.name", "api")
+                .ne("ä\"", "'x'"),
+        ];
+
+        for selector in selectors {
+            let s = selector.to_string();
+            assert_eq!(s.parse::<Selector>().unwrap(), selector, "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_selector_from_str_errors() {
+        let cases = [
+            (
+                "",
+                0,
+                "unexpected end of input, expected metric name or \"{\"",
+            ),
+            (
+                "up{",
+                3,
+                "unexpected end of input in label matching, expected label name",
+            ),
+            (
+                "up{job}",
+                6,
+                "unexpected \"}\" in label matching, expected label matching operator",
+            ),
+            (
+                "up{job=}",
+                7,
+                "unexpected \"}\" in label matching, expected string",
+            ),
+            (
+                r#"up{job="a" x="b"}"#,
+                11,
+                "unexpected identifier \"x\" in label matching, expected \",\" or \"}\"",
+            ),
+            (r#"{a:b="c"}"#, 2, "unexpected character inside braces: ':'"),
+            (
+                r#"up{__name__="x"}"#,
+                3,
+                "metric name must not be set twice",
+            ),
+            (r#"up{"x"}"#, 3, "metric name must not be set twice"),
+            (r#"{""="x"}"#, 1, "invalid label name \"\""),
+            (
+                "{}",
+                0,
+                "vector selector must contain at least one non-empty matcher",
+            ),
+            (
+                r#"{job="", instance=~".*"}"#,
+                0,
+                "vector selector must contain at least one non-empty matcher",
+            ),
+            (
+                r#"{job!~"foo"}"#,
+                0,
+                "vector selector must contain at least one non-empty matcher",
+            ),
+            (r#"{""}"#, 1, "invalid metric name \"\""),
+            (r#"up{job="x"} foo"#, 12, "unexpected identifier \"foo\""),
+            (
+                "up{job=~\"a\",\n  job=\"b\"",
+                22,
+                "unexpected end of input in label matching, expected \",\" or \"}\"",
+            ),
+        ];
+
+        for (input, position, message) in cases {
+            let err = input.parse::<Selector>().unwrap_err();
+            assert_eq!(
+                (err.position(), err.message()),
+                (position, message),
+                "{}",
+                input
+            );
+        }
+
+        let err = "up{job=~\"a\",\n  job!\"b\"}"
+            .parse::<Selector>()
+            .unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 6));
+    }
 }
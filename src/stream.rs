@@ -0,0 +1,575 @@
+use crate::error::{ClientError, Error, PrometheusError, PrometheusErrorType, WarningsError};
+use crate::response::Annotation;
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A stream of time series that are deserialized one at a time while the response
+/// body is received, see e.g. [`InstantQueryBuilder::get_stream`](crate::InstantQueryBuilder::get_stream).
+/// In contrast to the regular query methods the response is never buffered as a whole,
+/// so arbitrarily large results can be processed with constant memory.
+///
+/// Errors reported by Prometheus are yielded as [`Error::Prometheus`]. As Prometheus
+/// sends warnings and infos after the result, they are only available via
+/// [`SeriesStream::warnings`] and [`SeriesStream::infos`] once the stream is exhausted.
+/// In strict mode the stream yields [`Error::Warnings`] as its last item if Prometheus
+/// attached any warnings to the response.
+pub struct SeriesStream<T> {
+    inner: BoxStream<'static, Result<Event<T>, Error>>,
+    strict: bool,
+    warnings: Vec<Annotation>,
+    infos: Vec<Annotation>,
+}
+
+impl<T> SeriesStream<T> {
+    /// Return the warnings that Prometheus attached to the response. This is always empty
+    /// until the stream has been consumed completely.
+    pub fn warnings(&self) -> &[Annotation] {
+        &self.warnings
+    }
+
+    /// Return the informational annotations that Prometheus attached to the response.
+    /// This is always empty until the stream has been consumed completely.
+    pub fn infos(&self) -> &[Annotation] {
+        &self.infos
+    }
+}
+
+impl<T: DeserializeOwned + Send + 'static> SeriesStream<T> {
+    // Create a stream of series from a stream of chunks of the response body.
+    pub(crate) fn new<S, B>(chunks: S, layout: Layout, strict: bool) -> Self
+    where
+        S: Stream<Item = Result<B, Error>> + Unpin + Send + 'static,
+        B: AsRef<[u8]>,
+    {
+        let walker = Walker {
+            reader: Reader {
+                chunks,
+                buf: vec![],
+                pos: 0,
+            },
+            layout,
+            position: Position::Start,
+            status: None,
+            error_type: None,
+            error: None,
+            warnings: vec![],
+            infos: vec![],
+            marker: PhantomData,
+        };
+
+        let inner = stream::try_unfold(walker, |mut walker| async move {
+            Ok(walker.next().await?.map(|event| (event, walker)))
+        })
+        .boxed();
+
+        SeriesStream {
+            inner,
+            strict,
+            warnings: vec![],
+            infos: vec![],
+        }
+    }
+
+    // Create a stream of series from the body of a successful response.
+    pub(crate) fn from_response(response: reqwest::Response, layout: Layout, strict: bool) -> Self {
+        let chunks = stream::try_unfold(response, |mut response| async move {
+            match response.chunk().await {
+                Ok(chunk) => Ok(chunk.map(|chunk| (chunk, response))),
+                Err(source) => Err(Error::Client(ClientError {
+                    message: "failed to read response body from server",
+                    source: Some(source),
+                })),
+            }
+        });
+        Self::new(Box::pin(chunks), layout, strict)
+    }
+
+    // Create a stream of series from the body of a successful blocking response. The body
+    // is read synchronously, so polling the stream never returns `Poll::Pending`.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn from_blocking_response(
+        mut response: reqwest::blocking::Response,
+        layout: Layout,
+        strict: bool,
+    ) -> Self {
+        use std::io::{ErrorKind, Read};
+
+        let chunks = std::iter::from_fn(move || {
+            let mut buf = vec![0; 8192];
+            loop {
+                match response.read(&mut buf) {
+                    Ok(0) => return None,
+                    Ok(n) => {
+                        buf.truncate(n);
+                        return Some(Ok(buf));
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        return Some(Err(Error::Client(ClientError {
+                            message: "failed to read response body from server",
+                            source: e
+                                .into_inner()
+                                .and_then(|e| e.downcast::<reqwest::Error>().ok())
+                                .map(|e| *e),
+                        })))
+                    }
+                }
+            }
+        });
+        Self::new(stream::iter(chunks), layout, strict)
+    }
+}
+
+impl<T> Stream for SeriesStream<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            match this.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(Event::Series(series)))) => {
+                    return Poll::Ready(Some(Ok(series)))
+                }
+                Poll::Ready(Some(Ok(Event::Annotations { warnings, infos }))) => {
+                    this.warnings = warnings;
+                    this.infos = infos;
+                    if this.strict && !this.warnings.is_empty() {
+                        return Poll::Ready(Some(Err(Error::Warnings(WarningsError {
+                            warnings: this.warnings.clone(),
+                        }))));
+                    }
+                }
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+// Describes where the series are located in the response body.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Layout {
+    // The result of an instant query, i.e. `data.result` if `data.resultType` is "vector".
+    Vector,
+    // The result of a range query, i.e. `data.result` if `data.resultType` is "matrix".
+    Matrix,
+    // The result of a series query, i.e. the array in `data`.
+    Series,
+}
+
+impl Layout {
+    fn result_type(self) -> Option<&'static str> {
+        match self {
+            Self::Vector => Some("vector"),
+            Self::Matrix => Some("matrix"),
+            Self::Series => None,
+        }
+    }
+}
+
+enum Event<T> {
+    Series(T),
+    Annotations {
+        warnings: Vec<Annotation>,
+        infos: Vec<Annotation>,
+    },
+}
+
+// The current position of the walker within the response body. `first` tracks whether
+// the next member of an object or array must be preceded by a comma.
+#[derive(Clone, Copy)]
+enum Position {
+    Start,
+    Top { first: bool },
+    Data { first: bool },
+    Items { first: bool },
+    Done,
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::Client(ClientError {
+        message,
+        source: None,
+    })
+}
+
+// Walks the JSON response body and deserializes the members of the result array
+// one at a time, all other members of the response are kept or skipped.
+struct Walker<T, S> {
+    reader: Reader<S>,
+    layout: Layout,
+    position: Position,
+    status: Option<String>,
+    error_type: Option<PrometheusErrorType>,
+    error: Option<String>,
+    warnings: Vec<Annotation>,
+    infos: Vec<Annotation>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T, S, B> Walker<T, S>
+where
+    T: DeserializeOwned,
+    S: Stream<Item = Result<B, Error>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    async fn next(&mut self) -> Result<Option<Event<T>>, Error> {
+        loop {
+            match self.position {
+                Position::Start => {
+                    self.reader.expect(b'{').await?;
+                    self.position = Position::Top { first: true };
+                }
+                Position::Top { first } => {
+                    let Some(key) = self.reader.key(first).await? else {
+                        self.position = Position::Done;
+                        return self.finish().map(Some);
+                    };
+                    self.position = Position::Top { first: false };
+                    match key.as_str() {
+                        "status" => self.status = Some(self.reader.value().await?),
+                        "data" if self.layout == Layout::Series => {
+                            self.reader.expect(b'[').await?;
+                            self.position = Position::Items { first: true };
+                        }
+                        "data" => {
+                            self.reader.expect(b'{').await?;
+                            self.position = Position::Data { first: true };
+                        }
+                        "warnings" => {
+                            let warnings: Option<Vec<Annotation>> = self.reader.value().await?;
+                            self.warnings = warnings.unwrap_or_default();
+                        }
+                        "infos" => {
+                            let infos: Option<Vec<Annotation>> = self.reader.value().await?;
+                            self.infos = infos.unwrap_or_default();
+                        }
+                        "errorType" => self.error_type = Some(self.reader.value().await?),
+                        "error" => self.error = Some(self.reader.value().await?),
+                        _ => self.reader.skip().await?,
+                    }
+                }
+                Position::Data { first } => {
+                    let Some(key) = self.reader.key(first).await? else {
+                        self.position = Position::Top { first: false };
+                        continue;
+                    };
+                    self.position = Position::Data { first: false };
+                    match key.as_str() {
+                        "resultType" => {
+                            let result_type: String = self.reader.value().await?;
+                            if self.layout.result_type() != Some(result_type.as_str()) {
+                                return Err(invalid(
+                                    "failed to stream response from server due to unsupported result type",
+                                ));
+                            }
+                        }
+                        "result" => {
+                            self.reader.expect(b'[').await?;
+                            self.position = Position::Items { first: true };
+                        }
+                        _ => self.reader.skip().await?,
+                    }
+                }
+                Position::Items { first } => {
+                    if self.reader.peek().await? == b']' {
+                        self.reader.pos += 1;
+                        self.position = match self.layout {
+                            Layout::Series => Position::Top { first: false },
+                            _ => Position::Data { first: false },
+                        };
+                        continue;
+                    }
+                    if !first {
+                        self.reader.expect(b',').await?;
+                    }
+                    self.position = Position::Items { first: false };
+                    return Ok(Some(Event::Series(self.reader.value().await?)));
+                }
+                Position::Done => return Ok(None),
+            }
+        }
+    }
+
+    // Check the status of the response once the body was read completely.
+    fn finish(&mut self) -> Result<Event<T>, Error> {
+        match self.status.as_deref() {
+            Some("success") => Ok(Event::Annotations {
+                warnings: std::mem::take(&mut self.warnings),
+                infos: std::mem::take(&mut self.infos),
+            }),
+            Some("error") => match (self.error_type, self.error.take()) {
+                (Some(error_type), Some(message)) => Err(Error::Prometheus(PrometheusError {
+                    error_type,
+                    message,
+                })),
+                _ => Err(invalid("failed to parse JSON response from server")),
+            },
+            _ => Err(invalid("failed to parse JSON response from server")),
+        }
+    }
+}
+
+// Buffers the chunks of the response body until a complete JSON value was received.
+struct Reader<S> {
+    chunks: S,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<S, B> Reader<S>
+where
+    S: Stream<Item = Result<B, Error>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    // Append the next chunk of the response body to the buffer and discard all bytes
+    // that were consumed already. Fails at the end of the response body.
+    async fn fill(&mut self) -> Result<(), Error> {
+        let chunk = self.chunks.next().await.ok_or_else(|| {
+            invalid("failed to parse JSON response from server due to unexpected end of input")
+        })??;
+        self.buf.drain(..self.pos);
+        self.pos = 0;
+        self.buf.extend_from_slice(chunk.as_ref());
+        Ok(())
+    }
+
+    // Skip whitespace and return the next byte without consuming it.
+    async fn peek(&mut self) -> Result<u8, Error> {
+        loop {
+            match self.buf[self.pos..]
+                .iter()
+                .position(|b| !b.is_ascii_whitespace())
+            {
+                Some(offset) => {
+                    self.pos += offset;
+                    return Ok(self.buf[self.pos]);
+                }
+                None => {
+                    self.pos = self.buf.len();
+                    self.fill().await?;
+                }
+            }
+        }
+    }
+
+    async fn expect(&mut self, byte: u8) -> Result<(), Error> {
+        if self.peek().await? != byte {
+            return Err(invalid("failed to parse JSON response from server"));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Read the key of the next object member or return `None` at the end of the object.
+    async fn key(&mut self, first: bool) -> Result<Option<String>, Error> {
+        if self.peek().await? == b'}' {
+            self.pos += 1;
+            return Ok(None);
+        }
+        if !first {
+            self.expect(b',').await?;
+        }
+        let key = self.value().await?;
+        self.expect(b':').await?;
+        Ok(Some(key))
+    }
+
+    // Deserialize the next JSON value.
+    async fn value<T: DeserializeOwned>(&mut self) -> Result<T, Error> {
+        let len = self.scan().await?;
+        let value = serde_json::from_slice(&self.buf[self.pos..self.pos + len])
+            .map_err(|_| invalid("failed to parse JSON response from server"))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    async fn skip(&mut self) -> Result<(), Error> {
+        self.value::<IgnoredAny>().await.map(|_| ())
+    }
+
+    // Return the length of the next JSON value, reading more chunks as needed.
+    async fn scan(&mut self) -> Result<usize, Error> {
+        self.peek().await?;
+        let mut scanner = Scanner::default();
+        let mut scanned = 0;
+        loop {
+            let start = self.pos + scanned;
+            match scanner.feed(&self.buf[start..]) {
+                Some(len) => return Ok(scanned + len),
+                None => {
+                    scanned = self.buf.len() - self.pos;
+                    self.fill().await?;
+                }
+            }
+        }
+    }
+}
+
+// Finds the end of a JSON value, keeping its state between chunks.
+#[derive(Default)]
+struct Scanner {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    in_scalar: bool,
+}
+
+impl Scanner {
+    // Returns the number of bytes up to the end of the value, or `None` if the
+    // value continues after the given bytes.
+    fn feed(&mut self, bytes: &[u8]) -> Option<usize> {
+        for (i, &b) in bytes.iter().enumerate() {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                continue;
+            }
+
+            if self.in_scalar {
+                if matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace() {
+                    return Some(i);
+                }
+                continue;
+            }
+
+            match b {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    if self.depth == 0 {
+                        return Some(i);
+                    }
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        return Some(i + 1);
+                    }
+                }
+                b if b.is_ascii_whitespace() => {}
+                _ if self.depth == 0 => self.in_scalar = true,
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{InstantVector, RangeVector};
+    use futures_util::TryStreamExt;
+    use std::collections::HashMap;
+
+    const VECTOR: &str = r#"{"status":"success","data":{"resultType":"vector","result":[{"metric":{"__name__":"up","job":"prometheus","instance":"localhost:9090"},"value":[1659268100,"1"]},{"metric":{"__name__":"up","job":"node","instance":"localhost:9100"},"value":[1659268100,"0"]}],"stats":{"timings":{"evalTotalTime":0.1}}},"warnings":["result may be incomplete"],"infos":["metric might not be a counter"]}"#;
+
+    fn chunked<T: DeserializeOwned + Send + 'static>(
+        body: &str,
+        size: usize,
+        layout: Layout,
+        strict: bool,
+    ) -> SeriesStream<T> {
+        let chunks: Vec<Result<Vec<u8>, Error>> = body
+            .as_bytes()
+            .chunks(size)
+            .map(|chunk| Ok(chunk.to_vec()))
+            .collect();
+        SeriesStream::new(stream::iter(chunks), layout, strict)
+    }
+
+    #[tokio::test]
+    async fn test_stream_vector() -> Result<(), anyhow::Error> {
+        for size in [1, 7, VECTOR.len()] {
+            let mut stream = chunked::<InstantVector>(VECTOR, size, Layout::Vector, false);
+            let first = stream.try_next().await?.unwrap();
+            assert_eq!(first.metric().get("job").unwrap(), "prometheus");
+            assert!(stream.warnings().is_empty());
+            let second = stream.try_next().await?.unwrap();
+            assert_eq!(second.sample().unwrap().value(), 0.0);
+            assert!(stream.try_next().await?.is_none());
+            assert_eq!(stream.warnings(), &["result may be incomplete"]);
+            assert_eq!(stream.infos(), &["metric might not be a counter"]);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_matrix() -> Result<(), anyhow::Error> {
+        let body = r#"
+{
+  "status" : "success",
+  "data" : {
+    "resultType" : "matrix",
+    "result" : [
+      {
+        "metric" : { "__name__" : "up", "job" : "prometheus\"\\]}" },
+        "values" : [ [ 1435781430.781, "1" ], [ 1435781445.781, "1" ] ]
+      },
+      { "metric" : { "__name__" : "up", "job" : "node" }, "values" : [ ] }
+    ]
+  }
+}
+"#;
+        let series: Vec<RangeVector> = chunked(body, 3, Layout::Matrix, false)
+            .try_collect()
+            .await?;
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].metric().get("job").unwrap(), "prometheus\"\\]}");
+        assert_eq!(series[0].samples().len(), 2);
+        assert!(series[1].samples().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_series() -> Result<(), anyhow::Error> {
+        let body = r#"{"status":"success","data":[{"__name__":"up","job":"prometheus"},{"__name__":"up","job":"node"}]}"#;
+        let series: Vec<HashMap<String, String>> = chunked(body, 5, Layout::Series, false)
+            .try_collect()
+            .await?;
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[1].get("job").unwrap(), "node");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stream_strict() {
+        let mut stream = chunked::<InstantVector>(VECTOR, 16, Layout::Vector, true);
+        assert!(stream.try_next().await.unwrap().is_some());
+        assert!(stream.try_next().await.unwrap().is_some());
+        assert!(matches!(stream.try_next().await, Err(Error::Warnings(_))));
+    }
+
+    #[tokio::test]
+    async fn test_stream_errors() {
+        let body = r#"{"status":"error","errorType":"bad_data","error":"invalid parameter"}"#;
+        let mut stream = chunked::<InstantVector>(body, 4, Layout::Vector, false);
+        match stream.try_next().await {
+            Err(Error::Prometheus(e)) => {
+                assert_eq!(e.error_type(), PrometheusErrorType::BadData);
+                assert_eq!(e.message(), "invalid parameter");
+            }
+            _ => panic!("expected Prometheus error"),
+        }
+
+        let body =
+            r#"{"status":"success","data":{"resultType":"scalar","result":[1659268100,"1"]}}"#;
+        let mut stream = chunked::<InstantVector>(body, 4, Layout::Vector, false);
+        assert!(matches!(stream.try_next().await, Err(Error::Client(_))));
+
+        let truncated = &VECTOR[..120];
+        let mut stream = chunked::<InstantVector>(truncated, 8, Layout::Vector, false);
+        assert!(matches!(stream.try_next().await, Err(Error::Client(_))));
+    }
+}
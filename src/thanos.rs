@@ -0,0 +1,434 @@
+//! Extensions for the query API of [Thanos](https://thanos.io/tip/components/query.md/).
+//!
+//! Thanos Query accepts a number of parameters on top of those of the Prometheus API,
+//! e.g. to control deduplication or partial responses. The traits of this module add
+//! typed setters for these parameters to the builders of the endpoints that support them.
+//! Each trait builds on the previous one, so all of them need to be in scope to use all
+//! setters of a builder:
+//!
+//! - [`LabelsExt`] for label name and label value queries (and all of the below)
+//! - [`SeriesExt`] for series queries (and all of the below)
+//! - [`QueryExt`] for instant and range queries
+//!
+//! ```rust
+//! use prometheus_http_query::thanos::{
+//!     Engine, LabelsExt, MaxSourceResolution, QueryExt, SeriesExt, Warning,
+//! };
+//! use prometheus_http_query::Client;
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() -> Result<(), anyhow::Error> {
+//!     let client = Client::try_from("http://thanos-query.example.com:10902")?;
+//!
+//!     let result = client
+//!         .query("up")
+//!         .dedup(true)
+//!         .replica_labels(["replica"])
+//!         .partial_response(true)
+//!         .max_source_resolution(MaxSourceResolution::Auto)
+//!         .engine(Engine::Thanos)
+//!         .get()
+//!         .await?;
+//!
+//!     for warning in Warning::parse_all(result.warnings()) {
+//!         if let Warning::Store(e) = warning {
+//!             eprintln!("incomplete result, store {:?} failed: {}", e.address(), e.message());
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Thanos reports stores that failed to respond as warnings if partial responses are
+//! enabled, or fails the whole query otherwise. [`Warning`] and [`StoreError`] help to
+//! tell these failures apart from other warnings and errors.
+use crate::client::{
+    InstantQueryBuilder, LabelNamesQueryBuilder, LabelValuesQueryBuilder, RangeQueryBuilder,
+    SeriesQueryBuilder,
+};
+use crate::error::Error;
+use crate::selector::Selector;
+use std::borrow::Borrow;
+use std::fmt;
+
+/// The warning that Thanos attaches to a response when no store matched the
+/// time range or the `storeMatch[]` selectors of a query.
+const NO_STORE_MATCHED: &str = "No StoreAPIs matched for this query";
+
+/// Phrases that Thanos uses to report that a store failed to respond.
+const STORE_ERROR_PHRASES: [&str; 3] = [
+    "receive series from ",
+    "fetch series for ",
+    "failed to receive any data",
+];
+
+/// The highest resolution of downsampled data that Thanos may use to answer a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxSourceResolution {
+    /// Let Thanos pick the resolution based on the step of the query.
+    Auto,
+    /// Only use raw data.
+    Raw,
+    /// Use data that was downsampled to 5 minutes or raw data.
+    FiveMinutes,
+    /// Use data that was downsampled to 1 hour, to 5 minutes or raw data.
+    OneHour,
+}
+
+impl fmt::Display for MaxSourceResolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Auto => f.write_str("auto"),
+            Self::Raw => f.write_str("0s"),
+            Self::FiveMinutes => f.write_str("5m"),
+            Self::OneHour => f.write_str("1h"),
+        }
+    }
+}
+
+/// The engine that Thanos uses to evaluate a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// The Prometheus query engine.
+    Prometheus,
+    /// The distributed Thanos query engine.
+    Thanos,
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Prometheus => f.write_str("prometheus"),
+            Self::Thanos => f.write_str("thanos"),
+        }
+    }
+}
+
+mod private {
+    pub trait Sealed: Sized {
+        fn param(self, name: &'static str, value: String) -> Self;
+    }
+}
+
+/// Thanos-specific parameters of label name and label value queries. These are also
+/// supported by series queries and instant and range queries, see [`SeriesExt`] and
+/// [`QueryExt`].
+pub trait LabelsExt: private::Sealed {
+    /// Allow or forbid partial responses, i.e. results that are returned although
+    /// some stores failed to respond. The default depends on the
+    /// `--query.partial-response` flag of Thanos Query.
+    fn partial_response(self, enabled: bool) -> Self {
+        self.param("partial_response", enabled.to_string())
+    }
+
+    /// Only query the stores whose external labels match one of the selectors.
+    /// This can be called repeatedly to merge the selectors with those that have
+    /// been set before.
+    fn store_matches<T>(self, selectors: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Borrow<Selector>,
+    {
+        selectors.into_iter().fold(self, |builder, selector| {
+            builder.param("storeMatch[]", selector.borrow().to_string())
+        })
+    }
+}
+
+/// Thanos-specific parameters of series queries, on top of those of [`LabelsExt`].
+pub trait SeriesExt: LabelsExt {
+    /// Enable or disable the deduplication of series that only differ in their
+    /// replica labels. Thanos enables deduplication by default.
+    fn dedup(self, enabled: bool) -> Self {
+        self.param("dedup", enabled.to_string())
+    }
+
+    /// Set the labels that are used for deduplication, overriding the
+    /// `--query.replica-label` flags of Thanos Query. This can be called repeatedly
+    /// to merge the labels with those that have been set before.
+    fn replica_labels<T>(self, labels: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: fmt::Display,
+    {
+        labels.into_iter().fold(self, |builder, label| {
+            builder.param("replicaLabels[]", label.to_string())
+        })
+    }
+}
+
+/// Thanos-specific parameters of instant and range queries, on top of those of
+/// [`SeriesExt`] and [`LabelsExt`].
+pub trait QueryExt: SeriesExt {
+    /// Set the highest resolution of downsampled data that may be used to answer the query.
+    fn max_source_resolution(self, resolution: MaxSourceResolution) -> Self {
+        self.param("max_source_resolution", resolution.to_string())
+    }
+
+    /// Select the engine that evaluates the query.
+    fn engine(self, engine: Engine) -> Self {
+        self.param("engine", engine.to_string())
+    }
+}
+
+macro_rules! impl_sealed {
+    ($($builder:ident),*) => {
+        $(
+            impl<C> private::Sealed for $builder<C> {
+                fn param(self, name: &'static str, value: String) -> Self {
+                    self.query(name, value)
+                }
+            }
+        )*
+    };
+}
+
+impl_sealed!(
+    InstantQueryBuilder,
+    RangeQueryBuilder,
+    SeriesQueryBuilder,
+    LabelNamesQueryBuilder,
+    LabelValuesQueryBuilder
+);
+
+impl<C> LabelsExt for InstantQueryBuilder<C> {}
+impl<C> LabelsExt for RangeQueryBuilder<C> {}
+impl<C> LabelsExt for SeriesQueryBuilder<C> {}
+impl<C> LabelsExt for LabelNamesQueryBuilder<C> {}
+impl<C> LabelsExt for LabelValuesQueryBuilder<C> {}
+impl<C> SeriesExt for InstantQueryBuilder<C> {}
+impl<C> SeriesExt for RangeQueryBuilder<C> {}
+impl<C> SeriesExt for SeriesQueryBuilder<C> {}
+impl<C> QueryExt for InstantQueryBuilder<C> {}
+impl<C> QueryExt for RangeQueryBuilder<C> {}
+
+/// A warning that Thanos attached to a response, see e.g.
+/// [`PromqlResult::warnings`](crate::response::PromqlResult::warnings).
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// No store matched the time range or the `storeMatch[]` selectors of the query,
+    /// so the result is empty.
+    NoStoreMatched,
+    /// A store failed to respond and the result is incomplete (partial response).
+    Store(StoreError),
+    /// Any other warning, e.g. a PromQL annotation.
+    Other(String),
+}
+
+impl Warning {
+    /// Classify a single warning.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::thanos::Warning;
+    ///
+    /// let warning = Warning::parse("No StoreAPIs matched for this query");
+    /// assert_eq!(warning, Warning::NoStoreMatched);
+    /// ```
+    pub fn parse(warning: &str) -> Self {
+        if warning == NO_STORE_MATCHED {
+            Warning::NoStoreMatched
+        } else if let Some(e) = StoreError::parse(warning) {
+            Warning::Store(e)
+        } else {
+            Warning::Other(warning.to_string())
+        }
+    }
+
+    /// Classify all warnings of a response.
+    pub fn parse_all<T: AsRef<str>>(warnings: &[T]) -> Vec<Self> {
+        warnings
+            .iter()
+            .map(|w| Warning::parse(w.as_ref()))
+            .collect()
+    }
+
+    /// Check if the warning indicates that the result is incomplete.
+    pub fn is_partial_response(&self) -> bool {
+        matches!(self, Warning::Store(_))
+    }
+}
+
+/// A store (e.g. a sidecar, store gateway or receiver) that failed to respond to
+/// Thanos Query. It is reported as [`Warning::Store`] if partial responses are
+/// enabled, otherwise the query fails, see [`StoreError::from_error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreError {
+    address: Option<String>,
+    message: String,
+}
+
+impl StoreError {
+    // Check if the message reports a failed store and extract its address,
+    // e.g. "receive series from Addr: 10.0.0.1:10901 LabelSets: ...: rpc error: ...".
+    fn parse(message: &str) -> Option<Self> {
+        if !STORE_ERROR_PHRASES.iter().any(|p| message.contains(p)) {
+            return None;
+        }
+
+        let address = message.split_once("Addr: ").and_then(|(_, rest)| {
+            rest.split_whitespace()
+                .next()
+                .map(|a| a.trim_end_matches(':').to_string())
+        });
+
+        Some(StoreError {
+            address,
+            message: message.to_string(),
+        })
+    }
+
+    /// Check if a query failed because a store failed to respond and partial
+    /// responses were disabled.
+    ///
+    /// ```rust
+    /// use prometheus_http_query::thanos::{LabelsExt, StoreError};
+    /// use prometheus_http_query::Client;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let client = Client::try_from("http://thanos-query.example.com:10902").unwrap();
+    ///
+    ///     if let Err(e) = client.query("up").partial_response(false).get().await {
+    ///         match StoreError::from_error(&e) {
+    ///             Some(store) => eprintln!("store {:?} failed", store.address()),
+    ///             None => eprintln!("query failed: {}", e),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn from_error(error: &Error) -> Option<Self> {
+        match error {
+            Error::Prometheus(e) => StoreError::parse(e.message()),
+            _ => None,
+        }
+    }
+
+    /// Returns the address of the store, if Thanos included it in the message.
+    pub fn address(&self) -> Option<&str> {
+        self.address.as_deref()
+    }
+
+    /// Returns the complete message as reported by Thanos.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PrometheusError;
+    use crate::Client;
+
+    #[test]
+    fn test_query_params() {
+        let selector = Selector::new().eq("cluster", "eu-1");
+        let (_, request) = Client::default()
+            .query("up")
+            .dedup(false)
+            .partial_response(true)
+            .replica_labels(["replica", "rule_replica"])
+            .max_source_resolution(MaxSourceResolution::FiveMinutes)
+            .engine(Engine::Thanos)
+            .store_matches([selector])
+            .into_request()
+            .unwrap();
+
+        assert_eq!(
+            request.params,
+            vec![
+                ("query", String::from("up")),
+                ("dedup", String::from("false")),
+                ("partial_response", String::from("true")),
+                ("replicaLabels[]", String::from("replica")),
+                ("replicaLabels[]", String::from("rule_replica")),
+                ("max_source_resolution", String::from("5m")),
+                ("engine", String::from("thanos")),
+                ("storeMatch[]", String::from("{cluster=\"eu-1\"}")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_series_and_labels_params() {
+        let selector = Selector::new().metric("up");
+        let (_, request) = Client::default()
+            .series(&[selector])
+            .unwrap()
+            .dedup(true)
+            .replica_labels(["replica"])
+            .into_request();
+
+        assert_eq!(
+            request.params,
+            vec![
+                ("match[]", String::from("{__name__=\"up\"}")),
+                ("dedup", String::from("true")),
+                ("replicaLabels[]", String::from("replica")),
+            ]
+        );
+
+        let (_, request) = Client::default()
+            .label_values("job")
+            .partial_response(false)
+            .into_request()
+            .unwrap();
+
+        assert_eq!(
+            request.params,
+            vec![("partial_response", String::from("false"))]
+        );
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let warnings = Warning::parse_all(&[
+            "No StoreAPIs matched for this query",
+            "receive series from Addr: 10.0.0.1:10901 LabelSets: {cluster=\"eu-1\"} MinTime: 0 MaxTime: 1: rpc error: code = Unavailable desc = connection refused",
+            "fetch series for {cluster=\"us-1\"}: context deadline exceeded",
+            "PromQL info: metric might not be a counter",
+        ]);
+
+        assert_eq!(warnings[0], Warning::NoStoreMatched);
+        assert!(!warnings[0].is_partial_response());
+
+        match &warnings[1] {
+            Warning::Store(e) => {
+                assert_eq!(e.address(), Some("10.0.0.1:10901"));
+                assert!(e.message().ends_with("connection refused"));
+            }
+            w => panic!("unexpected warning: {:?}", w),
+        }
+        assert!(warnings[1].is_partial_response());
+
+        match &warnings[2] {
+            Warning::Store(e) => assert_eq!(e.address(), None),
+            w => panic!("unexpected warning: {:?}", w),
+        }
+
+        assert_eq!(
+            warnings[3],
+            Warning::Other(String::from("PromQL info: metric might not be a counter"))
+        );
+    }
+
+    #[test]
+    fn test_store_error_from_error() {
+        let error: PrometheusError = serde_json::from_str(
+            r#"{"errorType": "internal", "error": "receive series from Addr: 10.0.0.2:10901 LabelSets: {} MinTime: 0 MaxTime: 1: EOF"}"#,
+        )
+        .unwrap();
+
+        let store = StoreError::from_error(&Error::Prometheus(error)).unwrap();
+        assert_eq!(store.address(), Some("10.0.0.2:10901"));
+
+        assert!(StoreError::from_error(&Error::EmptySeriesSelector).is_none());
+    }
+}
@@ -1,8 +1,10 @@
-use crate::error::{Error, ParseUrlError};
+use crate::error::{Error, InvalidTenantError, ParseUrlError};
 use mime::Mime;
-use reqwest::header::HeaderValue;
-use serde::Deserialize;
-use std::fmt;
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::time::SystemTime;
+use time::{Duration, OffsetDateTime};
 use url::Url;
 
 /// A helper enum to filter targets by state.
@@ -136,7 +138,7 @@ pub enum RuleKind {
 }
 
 impl RuleKind {
-    pub(crate) fn to_query_param(&self) -> String {
+    pub(crate) fn to_query_param(self) -> String {
         match self {
             Self::Alerting => String::from("alert"),
             Self::Recording => String::from("record"),
@@ -153,26 +155,427 @@ impl fmt::Display for RuleKind {
     }
 }
 
+/// A point in time that is sent to Prometheus as query parameter, e.g. the evaluation
+/// time of an instant query or the start and end of a range query. Prometheus
+/// processes timestamps with millisecond precision.
+///
+/// Timestamps can be created from Unix timestamps in seconds (`i64` or `f64`),
+/// [`OffsetDateTime`], [`SystemTime`] or relative to the current time using [`Timestamp::ago`].
+///
+/// ```rust
+/// use prometheus_http_query::Timestamp;
+/// use time::{macros::datetime, Duration};
+///
+/// let a = Timestamp::from(1648373100);
+/// let b = Timestamp::from(datetime!(2022-03-27 09:25:00 UTC));
+/// assert_eq!(a, b);
+///
+/// let c = Timestamp::from(1648373100.25);
+/// assert_eq!(c.to_string(), "1648373100.250");
+///
+/// let d = Timestamp::ago(Duration::hours(1));
+/// assert!(d < Timestamp::now());
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Timestamp {
+    millis: i64,
+}
+
+impl Timestamp {
+    /// Create a timestamp from a Unix timestamp in milliseconds.
+    pub fn from_unix_millis(millis: i64) -> Self {
+        Timestamp { millis }
+    }
+
+    /// Create a timestamp of the current time.
+    pub fn now() -> Self {
+        Self::from(OffsetDateTime::now_utc())
+    }
+
+    /// Create a timestamp that lies the given duration before the current time.
+    /// Note that the current time is determined when this function is called,
+    /// not when the request is sent.
+    pub fn ago(duration: Duration) -> Self {
+        Self::from(OffsetDateTime::now_utc() - duration)
+    }
+
+    /// Returns the Unix timestamp in milliseconds.
+    pub fn unix_millis(&self) -> i64 {
+        self.millis
+    }
+}
+
+impl From<i64> for Timestamp {
+    fn from(seconds: i64) -> Self {
+        Timestamp {
+            millis: seconds.saturating_mul(1000),
+        }
+    }
+}
+
+impl From<f64> for Timestamp {
+    fn from(seconds: f64) -> Self {
+        Timestamp {
+            millis: (seconds * 1000.0).round() as i64,
+        }
+    }
+}
+
+impl From<OffsetDateTime> for Timestamp {
+    fn from(datetime: OffsetDateTime) -> Self {
+        let millis = datetime.unix_timestamp_nanos().div_euclid(1_000_000);
+        Timestamp {
+            millis: millis.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+        }
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        Self::from(OffsetDateTime::from(time))
+    }
+}
+
+/// Formats the timestamp as Unix timestamp in seconds, including a fractional part
+/// only when the timestamp has sub-second precision.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.millis < 0 { "-" } else { "" };
+        let abs = self.millis.unsigned_abs();
+        match abs % 1000 {
+            0 => write!(f, "{}{}", sign, abs / 1000),
+            millis => write!(f, "{}{}.{:03}", sign, abs / 1000, millis),
+        }
+    }
+}
+
+/// The resolution step width of a range query.
+///
+/// A step can be created from a float number of seconds or from a [`Duration`]
+/// (either [`time::Duration`] or [`std::time::Duration`]).
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Step {
+    seconds: f64,
+}
+
+impl Step {
+    /// Returns the step width as float number of seconds.
+    pub fn as_seconds_f64(&self) -> f64 {
+        self.seconds
+    }
+}
+
+impl From<f64> for Step {
+    fn from(seconds: f64) -> Self {
+        Step { seconds }
+    }
+}
+
+impl From<Duration> for Step {
+    fn from(duration: Duration) -> Self {
+        Step {
+            seconds: duration.as_seconds_f64(),
+        }
+    }
+}
+
+impl From<std::time::Duration> for Step {
+    fn from(duration: std::time::Duration) -> Self {
+        Step {
+            seconds: duration.as_secs_f64(),
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.seconds)
+    }
+}
+
+/// The maximum number of points per time series that Prometheus returns for a range query.
+pub(crate) const MAX_POINTS_PER_SERIES: i64 = 11_000;
+
+// Step widths (in milliseconds) that are preferred when a step is selected automatically.
+const NICE_STEPS: [i64; 20] = [
+    1_000,
+    2_000,
+    5_000,
+    10_000,
+    15_000,
+    30_000,
+    60_000,
+    120_000,
+    300_000,
+    600_000,
+    900_000,
+    1_800_000,
+    3_600_000,
+    7_200_000,
+    10_800_000,
+    21_600_000,
+    43_200_000,
+    86_400_000,
+    172_800_000,
+    604_800_000,
+];
+
+/// The desired resolution of a range query whose step is selected automatically,
+/// see [`Client::query_range_auto`](crate::Client::query_range_auto).
+///
+/// The step is the smallest of a list of human-friendly step widths (1s, 2s, 5s, 10s, 15s,
+/// 30s, 1m, 2m, 5m, 10m, 15m, 30m, 1h, 2h, 3h, 6h, 12h, 1d, 2d, 1w and multiples of 1w)
+/// that yields no more than the maximum number of points per time series. The server limit
+/// of 11,000 points per time series is always respected. An optional minimum step (e.g.
+/// the scrape interval) is used as is if it is sufficient, larger steps are picked from
+/// the list above.
+///
+/// ```rust
+/// use prometheus_http_query::{Resolution, Timestamp};
+/// use time::Duration;
+///
+/// let start = Timestamp::from(1648373100);
+/// let end = Timestamp::from(1648373100 + 86400);
+///
+/// // At most one point per pixel of a 1200px wide graph.
+/// let resolution = Resolution::pixels(1200);
+/// assert_eq!(resolution.step(start, end).as_seconds_f64(), 120.0);
+///
+/// // At most 100 points, but never less than the scrape interval.
+/// let resolution = Resolution::max_points(100).min_step(Duration::seconds(20));
+/// assert_eq!(resolution.step(start, end).as_seconds_f64(), 900.0);
+///
+/// let short = Timestamp::from(1648373100 + 600);
+/// assert_eq!(resolution.step(start, short).as_seconds_f64(), 20.0);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Resolution {
+    max_points: usize,
+    min_step: Option<Step>,
+}
+
+impl Resolution {
+    /// Select a step that yields at most the given number of points per time series.
+    pub fn max_points(max_points: usize) -> Self {
+        Resolution {
+            max_points,
+            min_step: None,
+        }
+    }
+
+    /// Select a step that yields at most one point per pixel of a graph with the given
+    /// width. This is the same as [`Resolution::max_points`] with the width as the
+    /// maximum number of points.
+    pub fn pixels(width: usize) -> Self {
+        Self::max_points(width)
+    }
+
+    /// Never select a step smaller than the given step, e.g. the scrape interval of the
+    /// queried time series.
+    pub fn min_step(mut self, step: impl Into<Step>) -> Self {
+        self.min_step = Some(step.into());
+        self
+    }
+
+    /// Returns the step that is selected for a range query between `start` and `end`.
+    pub fn step(&self, start: impl Into<Timestamp>, end: impl Into<Timestamp>) -> Step {
+        self.resolve(start.into(), end.into()).1
+    }
+
+    // Select the step and align the start timestamp to a multiple of the step, so that
+    // the evaluation timestamps do not shift when the same range is queried repeatedly.
+    pub(crate) fn resolve(&self, start: Timestamp, end: Timestamp) -> (Timestamp, Step) {
+        let limit = (self.max_points as i64).clamp(1, MAX_POINTS_PER_SERIES);
+        let min_step = self
+            .min_step
+            .map(|step| (step.as_seconds_f64() * 1000.0).ceil() as i64)
+            .filter(|millis| *millis > 0);
+
+        let points = |step: i64| {
+            let start = start.unix_millis().div_euclid(step) * step;
+            end.unix_millis().saturating_sub(start).max(0) / step + 1
+        };
+
+        let step = min_step
+            .into_iter()
+            .chain(
+                NICE_STEPS
+                    .iter()
+                    .copied()
+                    .filter(move |step| *step > min_step.unwrap_or(0)),
+            )
+            .chain((2..).map(|weeks| weeks * NICE_STEPS[NICE_STEPS.len() - 1]))
+            .find(|step| points(*step) <= limit)
+            .unwrap_or(i64::MAX);
+
+        let aligned = Timestamp::from_unix_millis(start.unix_millis().div_euclid(step) * step);
+        (aligned, Step::from(step as f64 / 1000.0))
+    }
+}
+
+impl From<usize> for Resolution {
+    fn from(max_points: usize) -> Self {
+        Self::max_points(max_points)
+    }
+}
+
+/// The evaluation timeout of a query.
+///
+/// A timeout can be created from a number of milliseconds (`i64`) or from a [`Duration`]
+/// (either [`time::Duration`] or [`std::time::Duration`]).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Timeout {
+    millis: i64,
+}
+
+impl Timeout {
+    /// Returns the timeout in milliseconds.
+    pub fn as_millis(&self) -> i64 {
+        self.millis
+    }
+}
+
+impl From<i64> for Timeout {
+    fn from(millis: i64) -> Self {
+        Timeout { millis }
+    }
+}
+
+impl From<Duration> for Timeout {
+    fn from(duration: Duration) -> Self {
+        Timeout {
+            millis: duration
+                .whole_milliseconds()
+                .clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+        }
+    }
+}
+
+impl From<std::time::Duration> for Timeout {
+    fn from(duration: std::time::Duration) -> Self {
+        Timeout {
+            millis: i64::try_from(duration.as_millis()).unwrap_or(i64::MAX),
+        }
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}ms", self.millis)
+    }
+}
+
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, PartialEq)]
-pub(crate) enum Label<'a> {
-    Equal((&'a str, &'a str)),
-    NotEqual((&'a str, &'a str)),
-    RegexEqual((&'a str, &'a str)),
-    RegexNotEqual((&'a str, &'a str)),
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum Label {
+    #[serde(rename = "eq")]
+    Equal((String, String)),
+    #[serde(rename = "ne")]
+    NotEqual((String, String)),
+    #[serde(rename = "regex_eq")]
+    RegexEqual((String, String)),
+    #[serde(rename = "regex_ne")]
+    RegexNotEqual((String, String)),
 }
 
-impl<'a> fmt::Display for Label<'a> {
+impl Label {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Self::Equal((k, _))
+            | Self::NotEqual((k, _))
+            | Self::RegexEqual((k, _))
+            | Self::RegexNotEqual((k, _)) => k,
+        }
+    }
+}
+
+/// Label names that are not valid according to the legacy naming rules are
+/// quoted (Prometheus >= 3.0), label values are always quoted and escaped.
+impl fmt::Display for Label {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let (op, (k, v)) = match self {
+            Self::Equal(l) => ("=", l),
+            Self::NotEqual(l) => ("!=", l),
+            Self::RegexEqual(l) => ("=~", l),
+            Self::RegexNotEqual(l) => ("!~", l),
+        };
+
+        if is_legacy_label_name(k) {
+            write!(f, "{}{}{}", k, op, Quoted(v))
+        } else {
+            write!(f, "{}{}{}", Quoted(k), op, Quoted(v))
+        }
+    }
+}
+
+/// The rules that label names and metric names are validated against,
+/// see [`Selector::validate`](crate::Selector::validate).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum NameValidationScheme {
+    /// Names must match the regular expression `[a-zA-Z_][a-zA-Z0-9_]*`
+    /// (label names) or `[a-zA-Z_:][a-zA-Z0-9_:]*` (metric names).
+    /// This is the only scheme supported by Prometheus < 3.0.
+    Legacy,
+    /// Names may contain any UTF-8 characters but must not be empty (Prometheus >= 3.0).
+    /// Names that are not valid according to the legacy rules are quoted in PromQL.
+    #[default]
+    Utf8,
+}
+
+impl NameValidationScheme {
+    pub(crate) fn is_valid_label_name(self, name: &str) -> bool {
+        match self {
+            Self::Legacy => is_legacy_label_name(name),
+            Self::Utf8 => !name.is_empty(),
+        }
+    }
+
+    pub(crate) fn is_valid_metric_name(self, name: &str) -> bool {
         match self {
-            Self::Equal((k, v)) => write!(f, "{}=\"{}\"", k, v),
-            Self::NotEqual((k, v)) => write!(f, "{}!=\"{}\"", k, v),
-            Self::RegexEqual((k, v)) => write!(f, "{}=~\"{}\"", k, v),
-            Self::RegexNotEqual((k, v)) => write!(f, "{}!~\"{}\"", k, v),
+            Self::Legacy => is_legacy_metric_name(name),
+            Self::Utf8 => !name.is_empty(),
         }
     }
 }
 
+pub(crate) fn is_legacy_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub(crate) fn is_legacy_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+/// Formats a string as double-quoted PromQL string literal, escaping backslashes,
+/// double quotes and control characters.
+pub(crate) struct Quoted<'a>(pub(crate) &'a str);
+
+impl<'a> fmt::Display for Quoted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_ascii_control() => write!(f, "\\x{:02x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
 /// Create a base URL that is common to all queries from a string literal.
 /// The implementations are probably a little more complicated than they need
 /// to be as we need to allocate a new string during the URL construction
@@ -215,22 +618,139 @@ pub(crate) fn build_final_url(mut url: Url, path: &str) -> Url {
     url
 }
 
+/// The header that Cortex, Mimir and Loki use to identify the tenant of a request.
+pub(crate) const DEFAULT_TENANT_HEADER: HeaderName = HeaderName::from_static("x-scope-orgid");
+
+/// Join one or more tenant IDs to the value of the tenant header. Multiple tenants
+/// are separated by `|` which instructs e.g. Mimir to federate the query across
+/// all of them. No tenants at all result in no header.
+pub(crate) fn tenant_header_value<T>(tenants: T) -> Result<Option<HeaderValue>, Error>
+where
+    T: IntoIterator,
+    T::Item: fmt::Display,
+{
+    let mut value = String::new();
+
+    for tenant in tenants {
+        let tenant = tenant.to_string();
+
+        if tenant.is_empty()
+            || tenant.contains('|')
+            || !tenant.bytes().all(|b| b.is_ascii_graphic())
+        {
+            return Err(Error::InvalidTenant(InvalidTenantError { tenant }));
+        }
+
+        if !value.is_empty() {
+            value.push('|');
+        }
+        value.push_str(&tenant);
+    }
+
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(HeaderValue::from_str(&value).unwrap()))
+}
+
 pub(crate) fn is_json(v: Option<&HeaderValue>) -> bool {
     match v
         .and_then(|h| h.to_str().ok())
         .and_then(|h| h.parse::<Mime>().ok())
     {
-        Some(mime) => match (mime.type_(), mime.subtype()) {
-            (mime::APPLICATION, mime::JSON) => true,
-            _ => false,
-        },
+        Some(mime) => matches!(
+            (mime.type_(), mime.subtype()),
+            (mime::APPLICATION, mime::JSON)
+        ),
         None => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{build_final_url, is_json, ToBaseUrl};
+    use super::{
+        build_final_url, is_json, tenant_header_value, Resolution, Step, Timeout, Timestamp,
+        ToBaseUrl,
+    };
+    use crate::error::Error;
+    use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
+    use time::{macros::datetime, Duration};
+
+    #[test]
+    fn test_timestamp_to_query_param() {
+        assert_eq!(Timestamp::from(1648373100).to_string(), "1648373100");
+        assert_eq!(Timestamp::from(1648373100.5).to_string(), "1648373100.500");
+        assert_eq!(Timestamp::from(-1.5).to_string(), "-1.500");
+        assert_eq!(Timestamp::from(-2).to_string(), "-2");
+        assert_eq!(
+            Timestamp::from(datetime!(2022-03-27 09:25:00.123456 UTC)).to_string(),
+            "1648373100.123"
+        );
+        assert_eq!(
+            Timestamp::from(UNIX_EPOCH + StdDuration::from_millis(1648373100001)).to_string(),
+            "1648373100.001"
+        );
+        assert_eq!(Timestamp::from_unix_millis(1500).unix_millis(), 1500);
+    }
+
+    #[test]
+    fn test_relative_timestamp() {
+        let ago = Timestamp::ago(Duration::minutes(5));
+        let after = Timestamp::from(SystemTime::now());
+        assert!(ago < after);
+        assert!(after.unix_millis() - ago.unix_millis() >= 300_000);
+    }
+
+    #[test]
+    fn test_resolution_step() {
+        let step = |resolution: Resolution, start: i64, end: i64| {
+            resolution.step(start, end).as_seconds_f64()
+        };
+
+        // 1 hour
+        assert_eq!(step(Resolution::max_points(1000), 0, 3600), 5.0);
+        assert_eq!(step(Resolution::max_points(60), 0, 3600), 120.0);
+        assert_eq!(step(Resolution::max_points(61), 0, 3600), 60.0);
+        assert_eq!(step(Resolution::pixels(100_000), 0, 3600), 1.0);
+
+        // 30 days, bounded by the server limit
+        assert_eq!(step(Resolution::max_points(100_000), 0, 30 * 86400), 300.0);
+
+        // one year
+        assert_eq!(
+            step(Resolution::max_points(10), 0, 365 * 86400),
+            3_628_800.0
+        );
+
+        // minimum step
+        let resolution = Resolution::max_points(1000).min_step(Duration::seconds(20));
+        assert_eq!(step(resolution, 0, 3600), 20.0);
+        assert_eq!(step(resolution, 0, 86400), 120.0);
+        let resolution = Resolution::max_points(1000).min_step(Duration::minutes(5));
+        assert_eq!(step(resolution, 0, 3600), 300.0);
+    }
+
+    #[test]
+    fn test_resolution_aligns_start() {
+        let (start, step) = Resolution::max_points(100)
+            .resolve(Timestamp::from(1648373123), Timestamp::from(1648376723));
+        assert_eq!(step.as_seconds_f64(), 60.0);
+        assert_eq!(start, Timestamp::from(1648373100));
+    }
+
+    #[test]
+    fn test_step_and_timeout_to_query_param() {
+        assert_eq!(Step::from(10.0).to_string(), "10");
+        assert_eq!(Step::from(Duration::milliseconds(1500)).to_string(), "1.5");
+        assert_eq!(Step::from(StdDuration::from_secs(60)).to_string(), "60");
+        assert_eq!(Timeout::from(1000).to_string(), "1000ms");
+        assert_eq!(Timeout::from(Duration::seconds(2)).to_string(), "2000ms");
+        assert_eq!(
+            Timeout::from(StdDuration::from_millis(250)).to_string(),
+            "250ms"
+        );
+    }
 
     #[test]
     fn test_simple_str_to_url() {
@@ -290,4 +810,20 @@ mod tests {
         let header = reqwest::header::HeaderValue::from_static("application/json; charset=utf-8");
         assert!(is_json(Some(&header)));
     }
+    #[test]
+    fn test_tenant_header_value() {
+        assert!(tenant_header_value(Vec::<String>::new()).unwrap().is_none());
+        assert_eq!(tenant_header_value(["team-a"]).unwrap().unwrap(), "team-a");
+        assert_eq!(
+            tenant_header_value(["team-a", "team-b"]).unwrap().unwrap(),
+            "team-a|team-b"
+        );
+
+        for invalid in ["", "team a", "team-a|team-b", "tëam"] {
+            match tenant_header_value([invalid]) {
+                Err(Error::InvalidTenant(e)) => assert_eq!(e.tenant(), invalid),
+                r => panic!("unexpected result for '{}': {:?}", invalid, r),
+            }
+        }
+    }
 }
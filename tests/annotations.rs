@@ -0,0 +1,100 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::{Client, Selector};
+
+// Answer every endpoint with a minimal successful response that carries one warning
+// and one info.
+fn server() -> MockServer {
+    MockServer::start(|request| {
+        let data = match request.path.as_str() {
+            "/api/v1/query" => r#"{"resultType": "vector", "result": []}"#,
+            "/api/v1/series" => r#"[{"__name__": "up", "job": "node"}]"#,
+            "/api/v1/labels" => r#"["__name__", "job"]"#,
+            "/api/v1/label/job/values" => r#"["node"]"#,
+            "/api/v1/rules" => r#"{"groups": []}"#,
+            "/api/v1/targets/metadata" => r#"[]"#,
+            "/api/v1/metadata" => r#"{}"#,
+            "/api/v1/query_exemplars" => r#"[]"#,
+            _ => return Response::new(404),
+        };
+        let body = format!(
+            r#"{{"status": "success", "data": {}, "warnings": ["{}"], "infos": ["{}"]}}"#,
+            data,
+            "remote read failed, result may be incomplete",
+            r#"PromQL info: metric might not be a counter, name does not end in _total/_sum/_count/_bucket: \"up\" (1:1)"#,
+        );
+        Response::json(200, &body)
+    })
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_annotations_on_metadata_queries() {
+    let server = server();
+    let client = Client::try_from(server.url()).unwrap();
+    let selector = Selector::new().metric("up");
+
+    let names = client.label_names().get().await.unwrap();
+    assert_eq!(*names, vec!["__name__", "job"]);
+    assert_eq!(
+        names.warnings(),
+        &["remote read failed, result may be incomplete"]
+    );
+    assert!(!names.warnings()[0].is_promql());
+    assert_eq!(names.infos()[0].position(), Some((1, 1)));
+    assert!(names.infos()[0]
+        .message()
+        .starts_with("metric might not be a counter"));
+
+    let values = client.label_values("job").get().await.unwrap();
+    assert!(values.has_warnings());
+    assert_eq!(values.into_data(), vec!["node"]);
+
+    let series = client.series(&[selector]).unwrap().get().await.unwrap();
+    assert_eq!(series.len(), 1);
+    assert!(series.has_warnings());
+
+    assert!(client.rules().get().await.unwrap().has_warnings());
+    assert!(client.target_metadata().get().await.unwrap().has_warnings());
+    assert!(client.metric_metadata().get().await.unwrap().has_warnings());
+    assert!(client.exemplars("up").get().await.unwrap().has_warnings());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_annotations_on_query_results() {
+    let server = server();
+    let client = Client::try_from(server.url()).unwrap();
+
+    let response = client.query("up").get().await.unwrap();
+    assert_eq!(
+        response.warnings()[0].message(),
+        response.warnings()[0].as_str()
+    );
+    assert!(response.infos()[0].is_promql());
+
+    let error = client.query("up").strict().get().await.unwrap_err();
+    match error {
+        prometheus_http_query::Error::Warnings(e) => {
+            assert_eq!(
+                e.warnings(),
+                &["remote read failed, result may be incomplete"]
+            )
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_annotations() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .build_blocking()
+        .unwrap();
+
+    let values = client.label_values("job").get().unwrap();
+    assert_eq!(*values, vec!["node"]);
+    assert_eq!(values.warnings().len(), 1);
+    assert_eq!(values.infos().len(), 1);
+}
@@ -0,0 +1,343 @@
+#![cfg(feature = "blocking")]
+
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::blocking::Client;
+use prometheus_http_query::{Error, Selector};
+use reqwest::header::HeaderValue;
+use serde::Deserialize;
+use std::str::FromStr;
+
+const VECTOR: &str = r#"{
+    "resultType": "vector",
+    "result": [
+        {"metric": {"__name__": "up", "job": "node"}, "value": [1700000000, "1"]},
+        {"metric": {"__name__": "up", "job": "prometheus"}, "value": [1700000000, "0"]}
+    ]
+}"#;
+
+fn client(server: &MockServer) -> Client {
+    Client::from_str(server.url()).unwrap()
+}
+
+#[test]
+fn test_instant_query_get() {
+    let server = MockServer::respond_with(Response::success(VECTOR));
+
+    let result = client(&server)
+        .query("up")
+        .at(1700000000)
+        .timeout(1000)
+        .get()
+        .unwrap();
+
+    let vector = result.data().as_vector().unwrap();
+    assert_eq!(vector.len(), 2);
+    assert_eq!(vector[0].metric().get("job").unwrap(), "node");
+    assert_eq!(vector[0].sample().unwrap().value(), 1.0);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].path, "/api/v1/query");
+    assert_eq!(requests[0].param("query").unwrap(), "up");
+    assert_eq!(requests[0].param("time").unwrap(), "1700000000");
+    assert_eq!(requests[0].param("timeout").unwrap(), "1000ms");
+}
+
+#[test]
+fn test_instant_query_post() {
+    let server = MockServer::respond_with(Response::success(VECTOR));
+
+    let result = client(&server)
+        .query("up")
+        .header("X-Custom", HeaderValue::from_static("value"))
+        .post()
+        .unwrap();
+
+    assert_eq!(result.data().as_vector().unwrap().len(), 2);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].query, "");
+    assert_eq!(requests[0].param("query").unwrap(), "up");
+    assert_eq!(requests[0].header("x-custom").unwrap(), "value");
+}
+
+#[test]
+fn test_range_query_split() {
+    let server = MockServer::start(|request| {
+        let start: i64 = request.param("start").unwrap().parse().unwrap();
+        let end: i64 = request.param("end").unwrap().parse().unwrap();
+        let values: Vec<String> = (start..=end)
+            .step_by(10)
+            .map(|t| format!(r#"[{}, "{}"]"#, t, t / 10))
+            .collect();
+        Response::success(&format!(
+            r#"{{"resultType": "matrix", "result": [{{"metric": {{"job": "node"}}, "values": [{}]}}]}}"#,
+            values.join(",")
+        ))
+    });
+
+    let result = client(&server)
+        .query_range("up", 0, 95, 10.0)
+        .split(5)
+        .get()
+        .unwrap();
+
+    let matrix = result.data().as_matrix().unwrap();
+    assert_eq!(matrix.len(), 1);
+    let timestamps: Vec<f64> = matrix[0].samples().iter().map(|s| s.timestamp()).collect();
+    assert_eq!(
+        timestamps,
+        vec![0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0]
+    );
+
+    let mut ranges: Vec<(String, String)> = server
+        .requests()
+        .iter()
+        .map(|r| (r.param("start").unwrap(), r.param("end").unwrap()))
+        .collect();
+    ranges.sort();
+    assert_eq!(
+        ranges,
+        vec![
+            ("0".to_string(), "40".to_string()),
+            ("50".to_string(), "90".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_prometheus_error() {
+    let server = MockServer::respond_with(Response::error(
+        400,
+        "bad_data",
+        "invalid parameter \\\"query\\\"",
+    ));
+
+    let error = client(&server).query("up{").get().unwrap_err();
+
+    match error {
+        Error::Prometheus(e) => {
+            assert!(e.is_bad_data());
+            assert_eq!(e.message(), "invalid parameter \"query\"");
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn test_invalid_media_type() {
+    let server = MockServer::respond_with(
+        Response::new(502)
+            .header("Content-Type", "text/plain")
+            .body("bad gateway"),
+    );
+
+    let error = client(&server).query("up").get().unwrap_err();
+
+    match error {
+        Error::Client(e) => assert_eq!(e.inner().unwrap().status().unwrap(), 502),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn test_strict_warnings() {
+    let body = format!(
+        r#"{{"status":"success","data":{},"warnings":["something is off"]}}"#,
+        VECTOR
+    );
+    let server = MockServer::respond_with(Response::json(200, &body));
+
+    let result = client(&server).query("up").get().unwrap();
+    assert_eq!(result.warnings(), ["something is off"]);
+
+    let error = client(&server).query("up").strict().get().unwrap_err();
+    match error {
+        Error::Warnings(e) => assert_eq!(e.warnings(), ["something is off"]),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn test_get_stream() {
+    let body = format!(
+        r#"{{"status":"success","data":{},"warnings":["something is off"]}}"#,
+        VECTOR
+    );
+    let server = MockServer::respond_with(Response::json(200, &body));
+
+    let mut series = client(&server).query("up").get_stream().unwrap();
+    let jobs: Vec<String> = series
+        .by_ref()
+        .map(|v| v.unwrap().metric().get("job").unwrap().clone())
+        .collect();
+
+    assert_eq!(jobs, vec!["node", "prometheus"]);
+    assert_eq!(series.warnings(), ["something is off"]);
+}
+
+#[test]
+fn test_get_as() {
+    #[derive(Deserialize)]
+    struct Up {
+        job: String,
+        #[serde(rename = "__value__")]
+        value: f64,
+    }
+
+    let server = MockServer::respond_with(Response::success(VECTOR));
+
+    let up: Vec<Up> = client(&server).query("up").get_as().unwrap();
+
+    assert_eq!(up.len(), 2);
+    assert_eq!(up[1].job, "prometheus");
+    assert_eq!(up[1].value, 0.0);
+}
+
+#[test]
+fn test_series() {
+    let server =
+        MockServer::respond_with(Response::success(r#"[{"__name__": "up", "job": "node"}]"#));
+
+    let selector = Selector::new().metric("up");
+    let series = client(&server)
+        .series(&[selector])
+        .unwrap()
+        .start(1700000000)
+        .get()
+        .unwrap();
+
+    assert_eq!(series.len(), 1);
+    assert_eq!(series[0].get("job").unwrap(), "node");
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "/api/v1/series");
+    assert_eq!(requests[0].params("match[]"), vec!["{__name__=\"up\"}"]);
+    assert_eq!(requests[0].param("start").unwrap(), "1700000000");
+}
+
+#[test]
+fn test_label_values() {
+    let server = MockServer::respond_with(Response::success(r#"["node", "prometheus"]"#));
+
+    let values = client(&server).label_values("job").get().unwrap();
+
+    assert_eq!(*values, vec!["node", "prometheus"]);
+    assert_eq!(server.requests()[0].path, "/api/v1/label/job/values");
+}
+
+#[test]
+fn test_flags() {
+    let server = MockServer::respond_with(Response::success(r#"{"log.level": "info"}"#));
+
+    let flags = client(&server).flags().unwrap();
+
+    assert_eq!(flags.get("log.level").unwrap(), "info");
+    assert_eq!(server.requests()[0].path, "/api/v1/status/flags");
+}
+
+#[test]
+fn test_delete_series() {
+    let server = MockServer::respond_with(Response::new(204));
+
+    let selector = Selector::new().metric("up");
+    client(&server)
+        .delete_series(&[selector])
+        .unwrap()
+        .post()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/api/v1/admin/tsdb/delete_series");
+}
+
+#[test]
+fn test_admin_api_disabled() {
+    let server =
+        MockServer::respond_with(Response::error(503, "unavailable", "admin APIs disabled"));
+
+    let error = client(&server).clean_tombstones().unwrap_err();
+
+    assert!(matches!(error, Error::AdminApiDisabled));
+}
+
+#[test]
+fn test_server_health() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/-/healthy" => Response::new(200),
+        _ => Response::new(503),
+    });
+
+    assert!(client(&server).is_server_healthy().unwrap());
+    assert!(client(&server).is_server_ready().is_err());
+}
+
+#[test]
+fn test_base_url_path() {
+    let server = MockServer::respond_with(Response::success(r#"["up"]"#));
+
+    let client = Client::from_str(&format!("{}/prometheus", server.url())).unwrap();
+    client.label_names().get().unwrap();
+
+    assert_eq!(server.requests()[0].path, "/prometheus/api/v1/labels");
+}
+
+#[cfg(feature = "remote-write")]
+#[test]
+fn test_remote_write() {
+    use prometheus_http_query::response::Sample;
+
+    let server = MockServer::respond_with(Response::new(204));
+
+    let samples = [
+        Sample::new(1.0, 1.0),
+        Sample::new(2.0, 2.0),
+        Sample::new(3.0, 3.0),
+    ];
+    client(&server)
+        .remote_write()
+        .series([("__name__", "up"), ("job", "node")], samples)
+        .max_samples_per_request(2)
+        .header("X-Scope-OrgID", HeaderValue::from_static("tenant"))
+        .send()
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/api/v1/write");
+    assert_eq!(requests[0].header("content-encoding").unwrap(), "snappy");
+    assert_eq!(
+        requests[0]
+            .header("x-prometheus-remote-write-version")
+            .unwrap(),
+        "0.1.0"
+    );
+    assert_eq!(requests[1].header("x-scope-orgid").unwrap(), "tenant");
+}
+
+#[cfg(feature = "remote-write")]
+#[test]
+fn test_remote_write_error() {
+    use prometheus_http_query::response::Sample;
+
+    let server = MockServer::respond_with(Response::new(400).body("out of order sample\n"));
+
+    let error = client(&server)
+        .remote_write()
+        .series([("__name__", "up")], [Sample::new(1.0, 1.0)])
+        .send()
+        .unwrap_err();
+
+    match error {
+        Error::RemoteWrite(e) => {
+            assert_eq!(e.status(), 400);
+            assert_eq!(e.message(), "out of order sample");
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
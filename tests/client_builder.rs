@@ -0,0 +1,206 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::{Client, Error};
+use reqwest::header::HeaderValue;
+use std::fs::File;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const EMPTY: &str = r#"{"resultType": "vector", "result": []}"#;
+
+fn token_file(name: &str, token: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "prometheus-http-query-{}-{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::write(&path, token).unwrap();
+    path
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_basic_auth() {
+    let server = MockServer::start(|request| match request.path.as_str() {
+        "/-/healthy" => Response::new(200),
+        _ => Response::success(EMPTY),
+    });
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .basic_auth("user", Some("secret"))
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+    assert!(client.is_server_healthy().await.unwrap());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in requests {
+        assert_eq!(
+            request.header("authorization").unwrap(),
+            "Basic dXNlcjpzZWNyZXQ="
+        );
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_bearer_auth() {
+    let server = MockServer::respond_with(Response::success(EMPTY));
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .bearer_auth("token")
+        .build()
+        .unwrap();
+
+    client.query("up").post().await.unwrap();
+
+    assert_eq!(
+        server.requests()[0].header("authorization").unwrap(),
+        "Bearer token"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_bearer_auth_file() {
+    let server = MockServer::respond_with(Response::success(EMPTY));
+    let path = token_file("bearer-auth-file", "first\n");
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .bearer_auth_file(&path)
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+
+    std::fs::write(&path, "second\n").unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(60))
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("authorization").unwrap(), "Bearer first");
+    assert_eq!(
+        requests[1].header("authorization").unwrap(),
+        "Bearer second"
+    );
+}
+
+#[test]
+fn test_bearer_auth_file_missing() {
+    let error = Client::builder()
+        .bearer_auth_file("/nonexistent/token")
+        .build()
+        .err()
+        .unwrap();
+
+    match error {
+        Error::Auth(e) => {
+            assert_eq!(e.path().unwrap().to_str().unwrap(), "/nonexistent/token");
+            assert!(e.inner().is_some());
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_default_headers() {
+    let server = MockServer::respond_with(Response::success(EMPTY));
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .header("X-Default", HeaderValue::from_static("default"))
+        .header("X-Override", HeaderValue::from_static("default"))
+        .build()
+        .unwrap();
+
+    client
+        .query("up")
+        .header("X-Override", HeaderValue::from_static("request"))
+        .get()
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("x-default").unwrap(), "default");
+    assert_eq!(requests[0].header("x-override").unwrap(), "request");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_timeout() {
+    let server = MockServer::start(|_| {
+        std::thread::sleep(Duration::from_millis(500));
+        Response::success(EMPTY)
+    });
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let error = client.query("up").get().await.unwrap_err();
+
+    match error {
+        Error::Client(e) => assert!(e.inner().unwrap().is_timeout()),
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_proxy() {
+    let proxy = MockServer::respond_with(Response::success(EMPTY));
+
+    let client = Client::builder()
+        .base_url("http://prometheus.invalid:9090")
+        .proxy(reqwest::Proxy::http(proxy.url()).unwrap())
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+
+    let requests = proxy.requests();
+    assert_eq!(
+        requests[0].path,
+        "http://prometheus.invalid:9090/api/v1/query"
+    );
+}
+
+#[test]
+fn test_invalid_base_url() {
+    let error = Client::builder()
+        .base_url("not a url")
+        .build()
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, Error::ParseUrl(_)));
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_build_blocking() {
+    let server = MockServer::respond_with(Response::success(EMPTY));
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .bearer_auth("token")
+        .header("X-Default", HeaderValue::from_static("default"))
+        .build_blocking()
+        .unwrap();
+
+    client.query("up").get().unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("authorization").unwrap(), "Bearer token");
+    assert_eq!(requests[0].header("x-default").unwrap(), "default");
+}
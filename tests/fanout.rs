@@ -0,0 +1,150 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::fanout::FanOutClient;
+use prometheus_http_query::{Client, Error};
+use std::net::TcpListener;
+use std::time::{Duration, Instant};
+
+const VECTOR: &str = r#"{"resultType": "vector", "result": [{"metric": {"__name__": "up", "job": "node"}, "value": [1659182624, "1"]}]}"#;
+const MATRIX: &str = r#"{"resultType": "matrix", "result": [{"metric": {"__name__": "up"}, "values": [[1659182624, "1"], [1659182684, "1"]]}]}"#;
+
+fn client(server: &MockServer) -> Client {
+    Client::try_from(server.url()).unwrap()
+}
+
+// A client for a port that nobody listens on, i.e. a source that is down.
+fn unreachable_client() -> Client {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    Client::try_from(url.as_str()).unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_instant_query() {
+    let a = MockServer::respond_with(Response::success(VECTOR));
+    let b = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = FanOutClient::new()
+        .source("a", client(&a))
+        .source("b", client(&b))
+        .source_label("cluster");
+
+    let response = client.query("up").at(1659182624).get().await.unwrap();
+
+    let series = response.data().as_vector().unwrap();
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].metric()["cluster"], "a");
+    assert_eq!(series[1].metric()["cluster"], "b");
+    assert_eq!(series[1].metric()["job"], "node");
+    assert!(response.warnings().is_empty());
+
+    for server in [&a, &b] {
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/v1/query");
+        assert_eq!(requests[0].param("time").as_deref(), Some("1659182624"));
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_range_query() {
+    let a = MockServer::respond_with(Response::success(MATRIX));
+    let b = MockServer::respond_with(Response::success(MATRIX));
+
+    let client = FanOutClient::new()
+        .source("a", client(&a))
+        .source("b", client(&b));
+
+    let response = client
+        .query_range("up", 1659182624, 1659182684, 60.0)
+        .post()
+        .await
+        .unwrap();
+
+    let series = response.data().as_matrix().unwrap();
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].metric()["source"], "a");
+    assert_eq!(series[1].metric()["source"], "b");
+    assert_eq!(series[0].samples().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_queries_are_sent_concurrently() {
+    let slow = || {
+        MockServer::start(|_| {
+            std::thread::sleep(Duration::from_millis(500));
+            Response::success(VECTOR)
+        })
+    };
+    let (a, b) = (slow(), slow());
+
+    let client = FanOutClient::new()
+        .source("a", client(&a))
+        .source("b", client(&b));
+
+    let start = Instant::now();
+    client.query("up").get().await.unwrap();
+
+    assert!(start.elapsed() < Duration::from_millis(900));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_partial_result() {
+    let a = MockServer::respond_with(Response::success(VECTOR));
+    let b = MockServer::respond_with(Response::error(503, "unavailable", "shutting down"));
+
+    let client = FanOutClient::new()
+        .source("a", client(&a))
+        .source("b", client(&b))
+        .source("c", unreachable_client());
+
+    let response = client.query("up").get().await.unwrap();
+
+    assert_eq!(response.data().as_vector().unwrap().len(), 1);
+    let warnings = response.warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].source(), Some("b"));
+    assert!(
+        warnings[0].to_string().starts_with("b: "),
+        "{}",
+        warnings[0]
+    );
+    assert_eq!(warnings[1].source(), Some("c"));
+
+    let error = client.query("up").strict().get().await.unwrap_err();
+    assert!(matches!(error, Error::Warnings(_)));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_source_warnings_are_prefixed() {
+    let body = format!(
+        r#"{{"status": "success", "data": {}, "warnings": ["store unavailable"]}}"#,
+        VECTOR
+    );
+    let a = MockServer::respond_with(Response::json(200, &body));
+
+    let client = FanOutClient::new().source("a", client(&a));
+
+    let response = client.query("up").get().await.unwrap();
+    let warning = &response.warnings()[0];
+    assert_eq!(warning.source(), Some("a"));
+    assert_eq!(warning.as_str(), "store unavailable");
+    assert_eq!(warning.to_string(), "a: store unavailable");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_all_sources_fail() {
+    let a = MockServer::respond_with(Response::error(400, "bad_data", "parse error"));
+
+    let client = FanOutClient::new()
+        .source("a", client(&a))
+        .source("b", unreachable_client());
+
+    let error = client.query("up{").get().await.unwrap_err();
+    assert!(matches!(error, Error::Prometheus(_)));
+
+    // Queries that fail local validation are not sent at all.
+    let error = client.query("up{").validate().get().await.unwrap_err();
+    assert!(matches!(error, Error::Parse(_)));
+    assert_eq!(a.requests().len(), 1);
+}
@@ -0,0 +1,195 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::ha::Strategy;
+use prometheus_http_query::{Client, Error};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const VECTOR: &str = r#"{"resultType": "vector", "result": []}"#;
+
+// The URL of a port that nobody listens on, i.e. a replica that is down.
+fn unreachable_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_failover() {
+    let replica = MockServer::respond_with(Response::success(VECTOR));
+    let primary = unreachable_url();
+
+    let client = Client::builder()
+        .base_url(&primary)
+        .replica(replica.url())
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+
+    let replicas = client.replicas();
+    assert!(!replicas[0].is_healthy());
+    assert!(replicas[1].is_healthy());
+
+    client.query("up").get().await.unwrap();
+    assert_eq!(replica.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_no_failover_on_api_error() {
+    let primary = MockServer::respond_with(Response::error(400, "bad_data", "parse error"));
+    let replica = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = Client::builder()
+        .base_url(primary.url())
+        .replica(replica.url())
+        .build()
+        .unwrap();
+
+    let error = client.query("up{").get().await.unwrap_err();
+
+    assert!(matches!(error, Error::Prometheus(_)));
+    assert!(replica.requests().is_empty());
+    assert!(client.replicas().iter().all(|r| r.is_healthy()));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_round_robin() {
+    let a = MockServer::respond_with(Response::success(VECTOR));
+    let b = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = Client::builder()
+        .base_url(a.url())
+        .replica(b.url())
+        .strategy(Strategy::RoundRobin)
+        .build()
+        .unwrap();
+
+    for _ in 0..4 {
+        client.query("up").get().await.unwrap();
+    }
+
+    assert_eq!(a.requests().len(), 2);
+    assert_eq!(b.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_hedged() {
+    let slow = MockServer::start(|_| {
+        std::thread::sleep(Duration::from_millis(1000));
+        Response::success(VECTOR)
+    });
+    let fast = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = Client::builder()
+        .base_url(slow.url())
+        .replica(fast.url())
+        .strategy(Strategy::Hedged(Duration::from_millis(50)))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    client.query("up").get().await.unwrap();
+
+    assert!(start.elapsed() < Duration::from_millis(900));
+    assert_eq!(slow.requests().len(), 1);
+    assert_eq!(fast.requests().len(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_check_replicas() {
+    let primary = MockServer::start(|request| match request.path.as_str() {
+        "/-/ready" => Response::new(503),
+        _ => Response::success(VECTOR),
+    });
+    let replica = MockServer::start(|request| match request.path.as_str() {
+        "/-/ready" => Response::new(200),
+        _ => Response::success(VECTOR),
+    });
+
+    let client = Client::builder()
+        .base_url(primary.url())
+        .replica(replica.url())
+        .build()
+        .unwrap();
+
+    let status = client.check_replicas().await;
+    assert!(!status[0].is_healthy());
+    assert!(status[1].is_healthy());
+
+    // Unhealthy replicas are skipped, also by clones of the client.
+    client.clone().query("up").get().await.unwrap();
+
+    let paths = |server: &MockServer| {
+        server
+            .requests()
+            .into_iter()
+            .map(|r| r.path)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(paths(&primary), vec!["/-/ready"]);
+    assert_eq!(paths(&replica), vec!["/-/ready", "/api/v1/query"]);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_readmission_after_cooldown() {
+    let ready = Arc::new(AtomicBool::new(false));
+    let primary = {
+        let ready = Arc::clone(&ready);
+        MockServer::start(move |request| match request.path.as_str() {
+            "/-/ready" if !ready.load(Ordering::SeqCst) => Response::new(503),
+            "/-/ready" => Response::new(200),
+            _ => Response::success(VECTOR),
+        })
+    };
+    let replica = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = Client::builder()
+        .base_url(primary.url())
+        .replica(replica.url())
+        .replica_cooldown(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let queries = |server: &MockServer| {
+        server
+            .requests()
+            .iter()
+            .filter(|r| r.path == "/api/v1/query")
+            .count()
+    };
+
+    assert!(!client.check_replicas().await[0].is_healthy());
+    client.query("up").get().await.unwrap();
+    assert_eq!((queries(&primary), queries(&replica)), (0, 1));
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    client.query("up").get().await.unwrap();
+    assert_eq!((queries(&primary), queries(&replica)), (1, 1));
+
+    // A successful readiness check re-admits a replica immediately.
+    client.check_replicas().await;
+    ready.store(true, Ordering::SeqCst);
+    assert!(!client.replicas()[0].is_healthy());
+    assert!(client.check_replicas().await[0].is_healthy());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_failover() {
+    let replica = MockServer::respond_with(Response::success(VECTOR));
+
+    let client = Client::builder()
+        .base_url(unreachable_url())
+        .replica(replica.url())
+        .strategy(Strategy::Hedged(Duration::from_millis(50)))
+        .build_blocking()
+        .unwrap();
+
+    client.query("up").get().unwrap();
+
+    assert!(!client.replicas()[0].is_healthy());
+    assert_eq!(replica.requests().len(), 1);
+}
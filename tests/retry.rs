@@ -0,0 +1,244 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::error::PrometheusErrorType;
+use prometheus_http_query::retry::RetryPolicy;
+use prometheus_http_query::{Client, Error, Selector};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+const VECTOR: &str = r#"{"resultType": "vector", "result": []}"#;
+
+// A server that fails the first `failures` requests with the given response.
+fn flaky(failures: usize, failure: Response) -> MockServer {
+    let count = AtomicUsize::new(0);
+    MockServer::start(move |_| {
+        if count.fetch_add(1, Ordering::SeqCst) < failures {
+            failure.clone()
+        } else {
+            Response::success(VECTOR)
+        }
+    })
+}
+
+fn client(server: &MockServer, policy: RetryPolicy) -> Client {
+    Client::builder()
+        .base_url(server.url())
+        .retry(policy.initial_backoff(Duration::from_millis(10)))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retry_unavailable() {
+    let server = flaky(2, Response::error(503, "unavailable", "starting up"));
+    let client = client(&server, RetryPolicy::new());
+
+    let response = client.query("up").get().await.unwrap();
+
+    assert!(response.data().as_vector().is_some());
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retry_without_prometheus_error() {
+    let server = flaky(1, Response::new(502).body("bad gateway"));
+    let client = client(&server, RetryPolicy::new());
+
+    client.query("up").get().await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_no_retry_on_bad_data() {
+    let server = flaky(1, Response::error(400, "bad_data", "parse error"));
+    let client = client(&server, RetryPolicy::new());
+
+    let error = client.query("up{").get().await.unwrap_err();
+
+    match error {
+        Error::Prometheus(e) => assert_eq!(e.error_type(), PrometheusErrorType::BadData),
+        e => panic!("unexpected error: {:?}", e),
+    }
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retry_on_custom_error_types() {
+    let server = flaky(1, Response::error(500, "internal", "oops"));
+    let client = client(
+        &server,
+        RetryPolicy::new().retry_on([PrometheusErrorType::Internal]),
+    );
+
+    client.query("up").get().await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_max_retries() {
+    let server = flaky(10, Response::error(503, "timeout", "query timed out"));
+    let client = client(&server, RetryPolicy::new().max_retries(2));
+
+    let error = client.query("up").get().await.unwrap_err();
+
+    match error {
+        Error::Prometheus(e) => assert_eq!(e.error_type(), PrometheusErrorType::Timeout),
+        e => panic!("unexpected error: {:?}", e),
+    }
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retry_after() {
+    let failure = Response::error(503, "unavailable", "overloaded").header("Retry-After", "1");
+    let server = flaky(1, failure);
+    let client = client(&server, RetryPolicy::new());
+
+    let start = Instant::now();
+    client.query("up").get().await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_max_retry_time() {
+    let failure = Response::error(503, "unavailable", "overloaded").header("Retry-After", "60");
+    let server = flaky(1, failure);
+    let client = client(
+        &server,
+        RetryPolicy::new().max_retry_time(Duration::from_secs(5)),
+    );
+
+    let start = Instant::now();
+    let error = client.query("up").get().await.unwrap_err();
+
+    assert!(matches!(error, Error::Prometheus(_)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_no_retry_by_default() {
+    let server = flaky(1, Response::error(503, "unavailable", "starting up"));
+    let client = Client::try_from(server.url()).unwrap();
+
+    assert!(client.query("up").get().await.is_err());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retried_response_keeps_url() {
+    let server = flaky(10, Response::error(503, "unavailable", "starting up"));
+    let client = client(&server, RetryPolicy::new().max_retries(1));
+
+    let response = client.query("up").get_raw().await.unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.url().path(), "/api/v1/query");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_retry_connection_refused() {
+    // Nobody listens on the port once the listener is dropped.
+    let url = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    };
+    let policy = RetryPolicy::new()
+        .max_retries(2)
+        .initial_backoff(Duration::from_millis(100))
+        .jitter(false);
+    let client = Client::builder()
+        .base_url(url)
+        .retry(policy)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let error = client.query("up").get().await.unwrap_err();
+
+    assert!(matches!(error, Error::Client(_)));
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_no_retry_on_redirect_loop() {
+    let server =
+        MockServer::respond_with(Response::new(302).header("Location", "/api/v1/query?query=up"));
+    let client = client(&server, RetryPolicy::new());
+
+    let error = client.query("up").get().await.unwrap_err();
+
+    assert!(matches!(error, Error::Client(_)));
+    // The redirect policy of reqwest gives up after 10 requests, which are not repeated.
+    assert_eq!(server.requests().len(), 10);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_no_retry_on_admin_endpoints() {
+    let server = flaky(10, Response::error(503, "unavailable", "starting up"));
+    let client = client(&server, RetryPolicy::new());
+
+    assert!(client.snapshot().post().await.is_err());
+    assert!(client
+        .delete_series([Selector::new().metric("up")])
+        .unwrap()
+        .post()
+        .await
+        .is_err());
+    assert!(client.clean_tombstones().await.is_err());
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_retry() {
+    let server = flaky(2, Response::error(503, "unavailable", "starting up"));
+    let client = Client::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::new().initial_backoff(Duration::from_millis(10)))
+        .build_blocking()
+        .unwrap();
+
+    client.query("up").get().unwrap();
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_retried_response_keeps_url() {
+    let server = flaky(10, Response::error(503, "unavailable", "starting up"));
+    let client = Client::builder()
+        .base_url(server.url())
+        .retry(
+            RetryPolicy::new()
+                .max_retries(1)
+                .initial_backoff(Duration::from_millis(10)),
+        )
+        .build_blocking()
+        .unwrap();
+
+    let response = client.query("up").get_raw().unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.url().path(), "/api/v1/query");
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_no_retry_on_admin_endpoints() {
+    let server = flaky(10, Response::error(503, "unavailable", "starting up"));
+    let client = Client::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::new().initial_backoff(Duration::from_millis(10)))
+        .build_blocking()
+        .unwrap();
+
+    assert!(client.snapshot().post().is_err());
+    assert!(client.clean_tombstones().is_err());
+    assert_eq!(server.requests().len(), 2);
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::{MockServer, Response};
+use prometheus_http_query::{Client, Error, Selector};
+use reqwest::header::{HeaderName, HeaderValue};
+
+// Answer every endpoint with a minimal response of the expected shape.
+fn server() -> MockServer {
+    MockServer::start(|request| match request.path.as_str() {
+        "/api/v1/query" => Response::success(r#"{"resultType": "vector", "result": []}"#),
+        "/api/v1/series" => Response::success(r#"[]"#),
+        "/api/v1/rules" => Response::success(r#"{"groups": []}"#),
+        "/api/v1/label/job/values" => Response::success(r#"[]"#),
+        "/api/v1/targets" => Response::success(r#"{"activeTargets": [], "droppedTargets": []}"#),
+        "/-/ready" => Response::new(200),
+        _ => Response::new(404),
+    })
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_tenant_on_every_endpoint() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .tenant("team-a")
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+    client
+        .series(&[Selector::new().metric("up")])
+        .unwrap()
+        .get()
+        .await
+        .unwrap();
+    client.rules().get().await.unwrap();
+    client.label_values("job").get().await.unwrap();
+    client.targets(None).await.unwrap();
+    assert!(client.is_server_ready().await.unwrap());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 6);
+    for request in requests {
+        assert_eq!(
+            request.header("x-scope-orgid"),
+            Some("team-a"),
+            "{}",
+            request.path
+        );
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_federated_tenants() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .tenants(["team-a", "team-b"])
+        .build()
+        .unwrap();
+
+    assert_eq!(client.tenant(), Some("team-a|team-b"));
+
+    client.query("up").get().await.unwrap();
+
+    assert_eq!(
+        server.requests()[0].header("x-scope-orgid").unwrap(),
+        "team-a|team-b"
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_tenant_overrides() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .tenant("team-a")
+        .build()
+        .unwrap();
+
+    client
+        .with_tenant("team-b")
+        .unwrap()
+        .targets(None)
+        .await
+        .unwrap();
+    client
+        .rules()
+        .header("X-Scope-OrgID", HeaderValue::from_static("team-c"))
+        .get()
+        .await
+        .unwrap();
+    client
+        .with_tenants(Vec::<String>::new())
+        .unwrap()
+        .query("up")
+        .get()
+        .await
+        .unwrap();
+    client.query("up").get().await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("x-scope-orgid").unwrap(), "team-b");
+    assert_eq!(requests[1].header("x-scope-orgid").unwrap(), "team-c");
+    assert!(requests[2].header("x-scope-orgid").is_none());
+    assert_eq!(requests[3].header("x-scope-orgid").unwrap(), "team-a");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_custom_tenant_header() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .tenant_header(HeaderName::from_static("thanos-tenant"))
+        .tenant("team-a")
+        .build()
+        .unwrap();
+
+    client.query("up").get().await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("thanos-tenant").unwrap(), "team-a");
+    assert!(requests[0].header("x-scope-orgid").is_none());
+}
+
+#[test]
+fn test_invalid_tenant() {
+    let error = Client::builder()
+        .tenants(["team-a", "team|b"])
+        .build()
+        .err()
+        .unwrap();
+
+    match error {
+        Error::InvalidTenant(e) => assert_eq!(e.tenant(), "team|b"),
+        e => panic!("unexpected error: {:?}", e),
+    }
+
+    assert!(matches!(
+        Client::default().with_tenant("").err().unwrap(),
+        Error::InvalidTenant(_)
+    ));
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_tenant() {
+    let server = server();
+    let client = Client::builder()
+        .base_url(server.url())
+        .tenant("team-a")
+        .build_blocking()
+        .unwrap();
+
+    client.query("up").get().unwrap();
+    client.with_tenant("team-b").unwrap().targets(None).unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("x-scope-orgid").unwrap(), "team-a");
+    assert_eq!(requests[1].header("x-scope-orgid").unwrap(), "team-b");
+}